@@ -0,0 +1,66 @@
+//! Compares the word-at-a-time unpacking kernels against bit-by-bit reading with `bitstream-io`.
+//!
+//! Run with `cargo bench --bench unpack`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bitstream_io::{BigEndian, BitRead, BitReader};
+use tinygrib2::templates::unpack::{packed_len, unpack_bits};
+
+/// Number of values in a 0.1° global field (3600 x 1801).
+const NUMBER_OF_VALUES: usize = 3600 * 1801;
+
+fn packed_field(bits: u32) -> Vec<u8> {
+    // xorshift so that the result does not depend on an RNG crate
+    let mut state: u64 = 0x9e3779b97f4a7c15;
+    (0..packed_len(NUMBER_OF_VALUES, bits))
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+fn read_bitstream(data: &[u8], bits: u32) -> Vec<u32> {
+    let mut reader = BitReader::<_, BigEndian>::new(data);
+    (0..NUMBER_OF_VALUES)
+        .map(|_| reader.read_var::<u32>(bits).unwrap())
+        .collect()
+}
+
+fn read_words(data: &[u8], bits: u32) -> Vec<u32> {
+    let mut values = vec![0u32; NUMBER_OF_VALUES];
+    unpack_bits(data, 0, bits, &mut values).unwrap();
+    values
+}
+
+fn time(f: impl Fn() -> Vec<u32>) -> Duration {
+    let iterations = 5;
+    let start = Instant::now();
+    for _ in 0..iterations {
+        black_box(f());
+    }
+    start.elapsed() / iterations
+}
+
+fn main() {
+    println!(
+        "{:>4}  {:>12}  {:>12}  {:>7}",
+        "bits", "bitstream", "words", "speedup"
+    );
+    for bits in [1, 7, 8, 10, 12, 13, 16, 17, 24, 31, 32] {
+        let data = packed_field(bits);
+        let slow = time(|| read_bitstream(black_box(&data), bits));
+        let fast = time(|| read_words(black_box(&data), bits));
+        println!(
+            "{:>4}  {:>12.2?}  {:>12.2?}  {:>6.1}x",
+            bits,
+            slow,
+            fast,
+            slow.as_secs_f64() / fast.as_secs_f64()
+        );
+    }
+}
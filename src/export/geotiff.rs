@@ -0,0 +1,733 @@
+use std::io::Write;
+
+use super::north_west;
+use crate::field::{Field, FieldMetadata};
+use crate::grid::crs::{GridTransform, Projection};
+use crate::grid::{EarthShape, GridDefinition};
+use crate::{Error, Result};
+
+/// Target size in bytes of the strips of untiled images
+const STRIP_SIZE: usize = 8192;
+
+/// Compression of the image data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    #[default]
+    None,
+    /// zlib stream (TIFF compression 8), needs the `deflate` feature
+    #[cfg(feature = "deflate")]
+    Deflate,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoTiffOptions {
+    /// Width and height of square tiles, a multiple of 16; None to write strips of rows
+    pub tile_size: Option<u32>,
+    pub compression: Compression,
+    /// Value written for missing values
+    pub nodata: f32,
+}
+
+impl Default for GeoTiffOptions {
+    fn default() -> Self {
+        Self {
+            tile_size: None,
+            compression: Compression::None,
+            nodata: f32::NAN,
+        }
+    }
+}
+
+/// Value of a TIFF tag
+enum TagValue {
+    Ascii(String),
+    Short(Vec<u16>),
+    Long(Vec<u32>),
+    Double(Vec<f64>),
+}
+
+impl TagValue {
+    /// Field type, count and little-endian bytes of the value
+    fn encode(&self) -> (u16, u32, Vec<u8>) {
+        match self {
+            Self::Ascii(s) => {
+                let mut bytes = s.as_bytes().to_vec();
+                bytes.push(0);
+                (2, bytes.len() as u32, bytes)
+            }
+            Self::Short(v) => (
+                3,
+                v.len() as u32,
+                v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            ),
+            Self::Long(v) => (
+                4,
+                v.len() as u32,
+                v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            ),
+            Self::Double(v) => (
+                12,
+                v.len() as u32,
+                v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            ),
+        }
+    }
+}
+
+/// Writes a field as a single-band 32-bit float GeoTIFF.
+///
+/// Values are laid out north-up, and the coordinate reference system is described with
+/// GeoKeys. The parameter, level and times of the field, if known, are written as GDAL metadata.
+/// Fails for grids whose points aren't evenly spaced on a supported projection (rotated,
+/// Gaussian, space view and unstructured grids among others).
+pub fn write_geotiff<W: Write>(
+    mut writer: W,
+    field: &Field,
+    options: &GeoTiffOptions,
+) -> Result<()> {
+    let unsupported = || {
+        Error::UnsupportedData(format!(
+            "grids of template 3.{} can't be written as GeoTIFF",
+            field.grid.template.template_number()
+        ))
+    };
+    let grid = field.grid.as_structured().ok_or_else(unsupported)?;
+    let transform = field
+        .grid
+        .template
+        .crs_transform()
+        .ok_or_else(unsupported)?;
+    let geo_keys = geo_keys(&field.grid).ok_or_else(unsupported)?;
+    if let Some(size) = options.tile_size
+        && (size == 0 || size % 16 != 0)
+    {
+        return Err(Error::InvalidData(format!(
+            "tile size {} is not a positive multiple of 16",
+            size
+        )));
+    }
+
+    let (ni, nj) = grid.dimensions();
+    let (mut values, _) = field.grid.to_canonical(&field.values)?;
+    if !options.nodata.is_nan() {
+        for value in values.iter_mut().filter(|v| v.is_nan()) {
+            *value = options.nodata;
+        }
+    }
+
+    // Blocks of the image: tiles padded with nodata, or strips of whole rows
+    let (block_width, block_height) = match options.tile_size {
+        Some(size) => (size as usize, size as usize),
+        None => (ni, (STRIP_SIZE / (4 * ni.max(1))).clamp(1, nj.max(1))),
+    };
+    let (blocks_across, blocks_down) = (ni.div_ceil(block_width), nj.div_ceil(block_height));
+    let mut data = vec![];
+    let (mut offsets, mut byte_counts) = (vec![], vec![]);
+    for block in 0..blocks_across * blocks_down {
+        let (column, row) = (
+            block % blocks_across * block_width,
+            block / blocks_across * block_height,
+        );
+        let height = match options.tile_size {
+            Some(_) => block_height,
+            None => block_height.min(nj - row),
+        };
+        let mut raw = Vec::with_capacity(block_width * height * 4);
+        for j in row..row + height {
+            for i in column..column + block_width {
+                let value = match (i < ni, j < nj) {
+                    (true, true) => values[j * ni + i],
+                    _ => options.nodata,
+                };
+                raw.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        let bytes = compress(raw, options.compression)?;
+        offsets.push(8 + data.len());
+        byte_counts.push(bytes.len() as u32);
+        data.extend(bytes);
+        // Word boundaries, as TIFF recommends
+        if data.len() % 2 == 1 {
+            data.push(0);
+        }
+    }
+
+    // Upper-left corner of the north-west cell
+    let (west, north) = north_west(grid);
+    let GridTransform { step, .. } = transform;
+    let (x, y) = transform.apply(west, north);
+    let (left, top) = (x - step.0 / 2.0, y + step.1 / 2.0);
+
+    let compression_tag = match options.compression {
+        Compression::None => 1,
+        #[cfg(feature = "deflate")]
+        Compression::Deflate => 8,
+    };
+    let offsets = offsets
+        .into_iter()
+        .map(|offset| u32::try_from(offset).map_err(|_| too_large()))
+        .collect::<Result<Vec<_>>>()?;
+    let mut tags: Vec<(u16, TagValue)> = vec![
+        (256, TagValue::Long(vec![ni as u32])),
+        (257, TagValue::Long(vec![nj as u32])),
+        (258, TagValue::Short(vec![32])),
+        (259, TagValue::Short(vec![compression_tag])),
+        // BlackIsZero
+        (262, TagValue::Short(vec![1])),
+        (277, TagValue::Short(vec![1])),
+        (284, TagValue::Short(vec![1])),
+        // IEEE floating point
+        (339, TagValue::Short(vec![3])),
+        (33550, TagValue::Double(vec![step.0, step.1, 0.0])),
+        (33922, TagValue::Double(vec![0.0, 0.0, 0.0, left, top, 0.0])),
+        (34735, TagValue::Short(geo_keys.directory)),
+        (
+            42113,
+            TagValue::Ascii(match options.nodata.is_nan() {
+                true => "nan".to_string(),
+                false => options.nodata.to_string(),
+            }),
+        ),
+    ];
+    if !geo_keys.doubles.is_empty() {
+        tags.push((34736, TagValue::Double(geo_keys.doubles)));
+    }
+    if !geo_keys.ascii.is_empty() {
+        tags.push((34737, TagValue::Ascii(geo_keys.ascii)));
+    }
+    if let Some(metadata) = &field.metadata {
+        tags.push((42112, TagValue::Ascii(gdal_metadata(metadata))));
+    }
+    match options.tile_size {
+        Some(size) => tags.extend([
+            (322, TagValue::Long(vec![size])),
+            (323, TagValue::Long(vec![size])),
+            (324, TagValue::Long(offsets)),
+            (325, TagValue::Long(byte_counts)),
+        ]),
+        None => tags.extend([
+            (273, TagValue::Long(offsets)),
+            (278, TagValue::Long(vec![block_height as u32])),
+            (279, TagValue::Long(byte_counts)),
+        ]),
+    }
+    tags.sort_by_key(|&(tag, _)| tag);
+
+    // The image file directory follows the data, and the values that don't fit in its entries
+    // follow it
+    let ifd_offset = 8 + data.len();
+    let mut extra_offset = ifd_offset + 2 + 12 * tags.len() + 4;
+    let (mut entries, mut extra) = (vec![], vec![]);
+    entries.extend((tags.len() as u16).to_le_bytes());
+    for (tag, value) in &tags {
+        let (field_type, count, mut bytes) = value.encode();
+        entries.extend(tag.to_le_bytes());
+        entries.extend(field_type.to_le_bytes());
+        entries.extend(count.to_le_bytes());
+        if bytes.len() <= 4 {
+            bytes.resize(4, 0);
+            entries.extend(bytes);
+        } else {
+            let offset = u32::try_from(extra_offset).map_err(|_| too_large())?;
+            entries.extend(offset.to_le_bytes());
+            extra_offset += bytes.len() + bytes.len() % 2;
+            if bytes.len() % 2 == 1 {
+                bytes.push(0);
+            }
+            extra.extend(bytes);
+        }
+    }
+    entries.extend(0u32.to_le_bytes());
+    let ifd_offset = u32::try_from(ifd_offset).map_err(|_| too_large())?;
+
+    writer.write_all(b"II*\0")?;
+    writer.write_all(&ifd_offset.to_le_bytes())?;
+    writer.write_all(&data)?;
+    writer.write_all(&entries)?;
+    writer.write_all(&extra)?;
+    Ok(())
+}
+
+fn too_large() -> Error {
+    Error::UnsupportedData("GeoTIFF files larger than 4 GiB are not supported".to_string())
+}
+
+fn compress(raw: Vec<u8>, compression: Compression) -> Result<Vec<u8>> {
+    match compression {
+        Compression::None => Ok(raw),
+        #[cfg(feature = "deflate")]
+        Compression::Deflate => {
+            let mut encoder =
+                flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+            encoder.write_all(&raw)?;
+            Ok(encoder.finish()?)
+        }
+    }
+}
+
+/// Contents of the GeoKeyDirectory, GeoDoubleParams and GeoAsciiParams tags
+struct GeoKeys {
+    directory: Vec<u16>,
+    doubles: Vec<f64>,
+    ascii: String,
+}
+
+enum GeoKey {
+    Short(u16),
+    Double(f64),
+    Ascii(String),
+}
+
+/// GeoKeys of the coordinate reference system of a grid, None if it can't be described
+fn geo_keys(grid: &GridDefinition) -> Option<GeoKeys> {
+    use GeoKey::*;
+
+    const USER_DEFINED: u16 = 32767;
+    let earth = grid.template.earth_shape()?;
+    let projection = grid.template.projection()?;
+
+    let mut keys = vec![
+        // Raster type: pixel is area
+        (1025, Short(1)),
+    ];
+    match earth {
+        EarthShape::Wgs84 => keys.push((2048, Short(4326))),
+        _ => {
+            let ellipsoid = earth.ellipsoid();
+            keys.extend([
+                (2048, Short(USER_DEFINED)),
+                (2049, Ascii(earth.name().to_string())),
+                (2050, Short(USER_DEFINED)),
+                // Greenwich, degrees
+                (2051, Short(8901)),
+                (2054, Short(9102)),
+                (2056, Short(USER_DEFINED)),
+                (2057, Double(ellipsoid.a)),
+                (2058, Double(ellipsoid.b)),
+            ]);
+        }
+    }
+
+    // Coordinate transformation (GeoTIFF Appendix 6.3.3.3) and its parameters
+    let (transformation, parameters) = match projection {
+        Projection::LatLon => {
+            // Model type: geographic
+            keys.push((1024, Short(2)));
+            return Some(encode_geo_keys(keys));
+        }
+        Projection::Mercator { lat_ts } => (7, vec![(3078, lat_ts), (3080, 0.0)]),
+        Projection::PolarStereographic {
+            south: _,
+            lat_ts,
+            lon_0,
+        } => (15, vec![(3081, lat_ts), (3092, 1.0), (3095, lon_0)]),
+        Projection::LambertConformal {
+            lat_0,
+            lon_0,
+            lat_1,
+            lat_2,
+        } => (
+            8,
+            vec![
+                (3078, lat_1),
+                (3079, lat_2),
+                (3084, lon_0),
+                (3085, lat_0),
+                (3086, 0.0),
+                (3087, 0.0),
+            ],
+        ),
+        Projection::LambertAzimuthal { lat_0, lon_0 } => (10, vec![(3088, lon_0), (3089, lat_0)]),
+        Projection::Rotated { .. } | Projection::Geostationary { .. } => return None,
+    };
+    keys.extend([
+        // Model type: projected
+        (1024, Short(1)),
+        (3072, Short(USER_DEFINED)),
+        (3074, Short(USER_DEFINED)),
+        (3075, Short(transformation)),
+        // Metres
+        (3076, Short(9001)),
+    ]);
+    if !parameters.iter().any(|&(key, _)| key == 3086) {
+        keys.extend([(3082, Double(0.0)), (3083, Double(0.0))]);
+    }
+    keys.extend(
+        parameters
+            .into_iter()
+            .map(|(key, value)| (key, Double(value))),
+    );
+    Some(encode_geo_keys(keys))
+}
+
+fn encode_geo_keys(mut keys: Vec<(u16, GeoKey)>) -> GeoKeys {
+    keys.sort_by_key(|&(key, _)| key);
+    // Version 1.1.0
+    let mut directory = vec![1, 1, 0, keys.len() as u16];
+    let (mut doubles, mut ascii) = (vec![], String::new());
+    for (key, value) in keys {
+        let entry = match value {
+            GeoKey::Short(value) => [key, 0, 1, value],
+            GeoKey::Double(value) => {
+                doubles.push(value);
+                [key, 34736, 1, (doubles.len() - 1) as u16]
+            }
+            GeoKey::Ascii(value) => {
+                let offset = ascii.len() as u16;
+                ascii.push_str(&value);
+                ascii.push('|');
+                [key, 34737, (value.len() + 1) as u16, offset]
+            }
+        };
+        directory.extend(entry);
+    }
+    GeoKeys {
+        directory,
+        doubles,
+        ascii,
+    }
+}
+
+/// GDAL_METADATA XML with the band metadata items of the GDAL GRIB driver
+fn gdal_metadata(metadata: &FieldMetadata) -> String {
+    let mut items = vec![
+        ("GRIB_DISCIPLINE", metadata.discipline.to_string()),
+        (
+            "GRIB_PDS_PDTN",
+            metadata.product_definition_template_number.to_string(),
+        ),
+        ("GRIB_REF_TIME", metadata.reference_time.to_string()),
+    ];
+    if let Some(parameter) = metadata.parameter() {
+        items.extend([
+            ("GRIB_ELEMENT", parameter.short_name.to_string()),
+            (
+                "GRIB_COMMENT",
+                format!("{} [{}]", parameter.name, parameter.units),
+            ),
+            ("GRIB_UNIT", format!("[{}]", parameter.units)),
+        ]);
+    } else {
+        items.push((
+            "GRIB_ELEMENT",
+            format!(
+                "VAR{}-{}-{}",
+                metadata.discipline, metadata.parameter_category, metadata.parameter_number
+            ),
+        ));
+    }
+    if let Some(level) = level_name(metadata) {
+        items.push(("GRIB_SHORT_NAME", level));
+    }
+    if let Some(seconds) = metadata.forecast_seconds() {
+        items.push(("GRIB_FORECAST_SECONDS", seconds.to_string()));
+    }
+    if let Some(valid_time) = metadata.valid_time() {
+        items.push(("GRIB_VALID_TIME", valid_time.to_string()));
+    }
+
+    let mut xml = "<GDALMetadata>\n".to_string();
+    for (name, value) in items {
+        xml.push_str(&format!(
+            "  <Item name=\"{}\" sample=\"0\">{}</Item>\n",
+            name,
+            escape_xml(&value)
+        ));
+    }
+    xml.push_str("</GDALMetadata>");
+    xml
+}
+
+/// Level in the notation of the GDAL GRIB driver, e.g. `50000-ISBL` or `0-0.1-DBLL`
+fn level_name(metadata: &FieldMetadata) -> Option<String> {
+    let first = metadata.first_fixed_surface?;
+    let name = first.abbreviation()?;
+    let value = |value: Option<f64>| value.unwrap_or(0.0).to_string();
+    Some(match metadata.second_fixed_surface {
+        Some(second) if second.surface_type == first.surface_type => {
+            format!("{}-{}-{}", value(first.value), value(second.value), name)
+        }
+        _ => format!("{}-{}", value(first.value), name),
+    })
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::field::{grib_message, product_definition_template, read_metadata};
+    use crate::grid::{global_grid, predefined_grid};
+
+    /// Entries of the image file directory of a TIFF file, as field types and value bytes
+    fn read_tags(tiff: &[u8]) -> BTreeMap<u16, (u16, Vec<u8>)> {
+        assert_eq!(&tiff[..4], b"II*\0");
+        let u16_at = |at: usize| u16::from_le_bytes(tiff[at..at + 2].try_into().unwrap());
+        let u32_at = |at: usize| u32::from_le_bytes(tiff[at..at + 4].try_into().unwrap());
+        let ifd = u32_at(4) as usize;
+        let mut tags = BTreeMap::new();
+        for e in 0..u16_at(ifd) as usize {
+            let entry = ifd + 2 + 12 * e;
+            let (field_type, count) = (u16_at(entry + 2), u32_at(entry + 4) as usize);
+            let size = count
+                * match field_type {
+                    2 => 1,
+                    3 => 2,
+                    4 => 4,
+                    _ => 8,
+                };
+            let at = match size <= 4 {
+                true => entry + 8,
+                false => u32_at(entry + 8) as usize,
+            };
+            tags.insert(u16_at(entry), (field_type, tiff[at..at + size].to_vec()));
+        }
+        // No other image follows
+        assert_eq!(u32_at(ifd + 2 + 12 * tags.len()), 0);
+        tags
+    }
+
+    fn shorts(tags: &BTreeMap<u16, (u16, Vec<u8>)>, tag: u16) -> Vec<u16> {
+        let (field_type, bytes) = &tags[&tag];
+        assert_eq!(*field_type, 3, "{tag}");
+        bytes
+            .chunks(2)
+            .map(|b| u16::from_le_bytes(b.try_into().unwrap()))
+            .collect()
+    }
+
+    fn longs(tags: &BTreeMap<u16, (u16, Vec<u8>)>, tag: u16) -> Vec<u32> {
+        let (field_type, bytes) = &tags[&tag];
+        assert_eq!(*field_type, 4, "{tag}");
+        bytes
+            .chunks(4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+            .collect()
+    }
+
+    fn doubles(tags: &BTreeMap<u16, (u16, Vec<u8>)>, tag: u16) -> Vec<f64> {
+        let (field_type, bytes) = &tags[&tag];
+        assert_eq!(*field_type, 12, "{tag}");
+        bytes
+            .chunks(8)
+            .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+            .collect()
+    }
+
+    fn ascii(tags: &BTreeMap<u16, (u16, Vec<u8>)>, tag: u16) -> String {
+        let (field_type, bytes) = &tags[&tag];
+        assert_eq!(*field_type, 2, "{tag}");
+        assert_eq!(bytes.last(), Some(&0));
+        String::from_utf8(bytes[..bytes.len() - 1].to_vec()).unwrap()
+    }
+
+    /// GeoKeys of the directory as `(key, location, count, value or index)`
+    fn geo_keys(tags: &BTreeMap<u16, (u16, Vec<u8>)>) -> BTreeMap<u16, (u16, u16, u16)> {
+        let directory = shorts(tags, 34735);
+        assert_eq!(directory[..3], [1, 1, 0]);
+        let keys: BTreeMap<u16, (u16, u16, u16)> = directory[4..]
+            .chunks(4)
+            .map(|k| (k[0], (k[1], k[2], k[3])))
+            .collect();
+        assert_eq!(keys.len(), directory[3] as usize);
+        keys
+    }
+
+    fn write(field: &Field, options: &GeoTiffOptions) -> Vec<u8> {
+        let mut tiff = vec![];
+        write_geotiff(&mut tiff, field, options).unwrap();
+        tiff
+    }
+
+    /// 6-hour accumulation on a global 10° grid, with its metadata read from a GRIB2 message
+    fn precipitation() -> Field {
+        let template = product_definition_template((1, 8), 6, (1, 0), Some(6));
+        let [metadata] = read_metadata(&grib_message(0, 36 * 19, 8, &template))
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let mut values: Vec<f32> = (0..36 * 19).map(|v| v as f32).collect();
+        values[40] = f32::NAN;
+        Field::new(Arc::new(global_grid(36, 19, 10_000_000)), values)
+            .unwrap()
+            .with_metadata(metadata)
+    }
+
+    #[test]
+    fn writes_geographic_images() {
+        let field = precipitation();
+        let options = GeoTiffOptions {
+            nodata: -9999.0,
+            ..Default::default()
+        };
+        let tiff = write(&field, &options);
+        let tags = read_tags(&tiff);
+        assert_eq!(longs(&tags, 256), [36]);
+        assert_eq!(longs(&tags, 257), [19]);
+        assert_eq!(shorts(&tags, 258), [32]);
+        assert_eq!(shorts(&tags, 259), [1]);
+        assert_eq!(shorts(&tags, 339), [3]);
+        assert_eq!(doubles(&tags, 33550), [10.0, 10.0, 0.0]);
+        // Upper-left corner of the cell of the first point, at 90N 0E
+        assert_eq!(doubles(&tags, 33922), [0.0, 0.0, 0.0, -5.0, 95.0, 0.0]);
+        assert_eq!(ascii(&tags, 42113), "-9999");
+
+        let keys = geo_keys(&tags);
+        assert_eq!(keys[&1024], (0, 1, 2));
+        assert_eq!(keys[&1025], (0, 1, 1));
+        assert_eq!(keys[&2048], (0, 1, 32767));
+        let params = doubles(&tags, 34736);
+        for key in [2057, 2058] {
+            let (location, count, index) = keys[&key];
+            assert_eq!((location, count), (34736, 1));
+            assert_eq!(params[index as usize], 6_371_229.0);
+        }
+        let (location, count, offset) = keys[&2049];
+        assert_eq!(location, 34737);
+        let names = ascii(&tags, 34737);
+        let name = &names[offset as usize..(offset + count) as usize];
+        assert!(name.ends_with('|'), "{name}");
+
+        let metadata = ascii(&tags, 42112);
+        for item in [
+            "<Item name=\"GRIB_ELEMENT\" sample=\"0\">APCP</Item>",
+            "<Item name=\"GRIB_PDS_PDTN\" sample=\"0\">8</Item>",
+            "<Item name=\"GRIB_SHORT_NAME\" sample=\"0\">0-SFC</Item>",
+            "<Item name=\"GRIB_REF_TIME\" sample=\"0\">2024-06-01T12:00:00Z</Item>",
+            "<Item name=\"GRIB_VALID_TIME\" sample=\"0\">2024-06-01T18:00:00Z</Item>",
+        ] {
+            assert!(metadata.contains(item), "{metadata}");
+        }
+
+        // Strips of whole rows, in the order of the grid as it scans from the north
+        let rows = longs(&tags, 278)[0] as usize;
+        let (offsets, counts) = (longs(&tags, 273), longs(&tags, 279));
+        assert_eq!(offsets.len(), 19usize.div_ceil(rows));
+        let pixels: Vec<f32> = offsets
+            .iter()
+            .zip(&counts)
+            .flat_map(|(&offset, &count)| {
+                tiff[offset as usize..(offset + count) as usize].chunks(4)
+            })
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        assert_eq!(pixels.len(), 36 * 19);
+        assert_eq!(pixels[40], -9999.0);
+        assert!(
+            pixels
+                .iter()
+                .zip(&field.values)
+                .all(|(p, v)| p == v || v.is_nan())
+        );
+    }
+
+    #[test]
+    fn writes_tiles() {
+        let field = precipitation();
+        let options = GeoTiffOptions {
+            tile_size: Some(16),
+            ..Default::default()
+        };
+        let tiff = write(&field, &options);
+        let tags = read_tags(&tiff);
+        assert!(!tags.contains_key(&273));
+        assert_eq!(ascii(&tags, 42113), "nan");
+        assert_eq!((longs(&tags, 322), longs(&tags, 323)), (vec![16], vec![16]));
+        let (offsets, counts) = (longs(&tags, 324), longs(&tags, 325));
+        // 3 tiles across and 2 down, padded with nodata
+        assert_eq!(offsets.len(), 6);
+        assert!(counts.iter().all(|&count| count == 16 * 16 * 4));
+        let pixel = |tile: usize, i: usize, j: usize| {
+            let at = offsets[tile] as usize + 4 * (j * 16 + i);
+            f32::from_le_bytes(tiff[at..at + 4].try_into().unwrap())
+        };
+        // Point (20, 17) is at (4, 1) in the fifth tile
+        assert_eq!(pixel(4, 4, 1), field.values[17 * 36 + 20]);
+        assert!(pixel(5, 15, 15).is_nan());
+
+        let options = GeoTiffOptions {
+            tile_size: Some(20),
+            ..Default::default()
+        };
+        assert!(matches!(
+            write_geotiff(vec![], &field, &options),
+            Err(Error::InvalidData(_))
+        ));
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn compresses_blocks() {
+        use std::io::Read;
+
+        let field = precipitation();
+        let options = GeoTiffOptions {
+            compression: Compression::Deflate,
+            ..Default::default()
+        };
+        let tiff = write(&field, &options);
+        let tags = read_tags(&tiff);
+        assert_eq!(shorts(&tags, 259), [8]);
+        let mut raw = vec![];
+        for (&offset, &count) in longs(&tags, 273).iter().zip(&longs(&tags, 279)) {
+            let block = &tiff[offset as usize..(offset + count) as usize];
+            flate2::read::ZlibDecoder::new(block)
+                .read_to_end(&mut raw)
+                .unwrap();
+        }
+        let pixels: Vec<f32> = raw
+            .chunks(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        assert_eq!(pixels.len(), field.values.len());
+        assert!(pixels[40].is_nan());
+        assert!(
+            pixels
+                .iter()
+                .zip(&field.values)
+                .all(|(p, v)| p == v || v.is_nan())
+        );
+    }
+
+    #[test]
+    fn writes_projected_images() {
+        let grid = Arc::new(predefined_grid(211));
+        let field = Field::new(grid, vec![0.0; 93 * 65]).unwrap();
+        let tiff = write(&field, &Default::default());
+        let tags = read_tags(&tiff);
+        assert!(!tags.contains_key(&42112));
+        let step = 81_270.5;
+        let scale = doubles(&tags, 33550);
+        assert!((scale[0] - step).abs() < 1e-3 && (scale[1] - step).abs() < 1e-3);
+        // The grid scans from the south: the image starts at its last row
+        let tie_point = doubles(&tags, 33922);
+        let (x, y) = (-4_226_106.997, -832_698.261);
+        assert!(
+            (tie_point[3] - (x - step / 2.0)).abs() < 1.0,
+            "{tie_point:?}"
+        );
+        assert!(
+            (tie_point[4] - (y + 64.5 * step)).abs() < 1.0,
+            "{tie_point:?}"
+        );
+
+        let keys = geo_keys(&tags);
+        assert_eq!(keys[&1024], (0, 1, 1));
+        assert_eq!(keys[&3072], (0, 1, 32767));
+        assert_eq!(keys[&3075], (0, 1, 8));
+        assert_eq!(keys[&3076], (0, 1, 9001));
+        let params = doubles(&tags, 34736);
+        let param = |key: u16| params[keys[&key].2 as usize];
+        assert_eq!((param(3078), param(3079)), (25.0, 25.0));
+        assert_eq!((param(3084), param(3085)), (265.0, 25.0));
+        assert_eq!((param(3086), param(3087)), (0.0, 0.0));
+    }
+}
@@ -0,0 +1,19 @@
+//! Writers of decoded fields in formats read by GIS and analysis tools
+
+mod geotiff;
+mod netcdf;
+
+pub use geotiff::*;
+pub use netcdf::*;
+
+use crate::grid::StructuredGrid;
+
+/// Offsets from the first grid point of the north-west point, the first one in the canonical
+/// layout
+fn north_west(grid: &dyn StructuredGrid) -> (f64, f64) {
+    let (ni, nj) = grid.dimensions();
+    let scan = grid.scanning_mode();
+    let (west, _) = scan.xy(if scan.i_negative() { ni - 1 } else { 0 } as f64, 0.0);
+    let (_, north) = scan.xy(0.0, if scan.j_positive() { nj - 1 } else { 0 } as f64);
+    (west, north)
+}
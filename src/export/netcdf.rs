@@ -0,0 +1,1010 @@
+use std::io::Write;
+use std::sync::Arc;
+
+use super::north_west;
+use crate::field::{DateTime, Field, FieldMetadata, FixedSurface, StatisticalProcess};
+use crate::grid::GridDefinition;
+use crate::grid::crs::Projection;
+use crate::{Error, Result};
+
+/// Types of the first and second fixed surfaces of a vertical axis
+type SurfaceTypes = (u8, Option<u8>);
+
+/// Values of the first and second fixed surfaces of a level, the second one for layers
+type Level = (f64, Option<f64>);
+
+/// Discipline, category and number of a parameter, product definition template number, types
+/// of the fixed surfaces and statistical processing of the fields making up a variable
+type VariableKey = (
+    u8,
+    u8,
+    u8,
+    u16,
+    Option<u8>,
+    Option<u8>,
+    Option<StatisticalProcess>,
+);
+
+/// PROJ string, dimensions, coordinates of the north-west point and step of a grid
+type Extent = (String, (usize, usize), (f64, f64), (f64, f64));
+
+/// Values of a variable or an attribute
+enum Values {
+    Text(String),
+    Int(Vec<i32>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+}
+
+impl Values {
+    fn text(s: impl Into<String>) -> Self {
+        Self::Text(s.into())
+    }
+
+    fn nc_type(&self) -> u32 {
+        match self {
+            Self::Text(_) => 2,
+            Self::Int(_) => 4,
+            Self::Float(_) => 5,
+            Self::Double(_) => 6,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Text(s) => s.len(),
+            Self::Int(v) => v.len(),
+            Self::Float(v) => v.len(),
+            Self::Double(v) => v.len(),
+        }
+    }
+
+    /// Number of octets of the encoded values, padding included
+    fn size(&self) -> usize {
+        let element = match self {
+            Self::Text(_) => 1,
+            Self::Int(_) | Self::Float(_) => 4,
+            Self::Double(_) => 8,
+        };
+        (self.len() * element).next_multiple_of(4)
+    }
+
+    /// Big-endian bytes, padded to a multiple of 4
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = match self {
+            Self::Text(s) => s.as_bytes().to_vec(),
+            Self::Int(v) => v.iter().flat_map(|x| x.to_be_bytes()).collect(),
+            Self::Float(v) => v.iter().flat_map(|x| x.to_be_bytes()).collect(),
+            Self::Double(v) => v.iter().flat_map(|x| x.to_be_bytes()).collect(),
+        };
+        bytes.resize(bytes.len().next_multiple_of(4), 0);
+        bytes
+    }
+}
+
+struct Variable {
+    name: String,
+    /// Indices of the dimensions, slowest varying first
+    dimensions: Vec<usize>,
+    attributes: Vec<(&'static str, Values)>,
+    values: Values,
+}
+
+impl Variable {
+    fn new(name: impl Into<String>, dimensions: Vec<usize>, values: Values) -> Self {
+        Self {
+            name: name.into(),
+            dimensions,
+            attributes: vec![],
+            values,
+        }
+    }
+
+    fn attribute(mut self, name: &'static str, value: Values) -> Self {
+        self.attributes.push((name, value));
+        self
+    }
+}
+
+/// Contents of a NetCDF file with fixed-size dimensions only
+#[derive(Default)]
+struct Dataset {
+    dimensions: Vec<(String, usize)>,
+    attributes: Vec<(&'static str, Values)>,
+    variables: Vec<Variable>,
+}
+
+impl Dataset {
+    /// Adds a dimension and returns its index.
+    fn dimension(&mut self, name: impl Into<String>, len: usize) -> usize {
+        self.dimensions.push((name.into(), len));
+        self.dimensions.len() - 1
+    }
+
+    /// Encodes the dataset in the classic format, or in its 64-bit offset variant if the file is
+    /// too large for 32-bit offsets.
+    fn encode(&self) -> Result<Vec<u8>> {
+        let sizes: Vec<usize> = self.variables.iter().map(|v| v.values.size()).collect();
+        let data_len: usize = sizes.iter().sum();
+        let mut version = 1;
+        let mut begins = vec![0; sizes.len()];
+        if self.header(version, &begins)?.len() + data_len > i32::MAX as usize {
+            version = 2;
+        }
+        let mut offset = self.header(version, &begins)?.len();
+        for (begin, size) in begins.iter_mut().zip(&sizes) {
+            *begin = offset;
+            offset += size;
+        }
+        let mut bytes = self.header(version, &begins)?;
+        bytes.reserve(data_len);
+        for variable in &self.variables {
+            bytes.extend(variable.values.encode());
+        }
+        Ok(bytes)
+    }
+
+    fn header(&self, version: u8, begins: &[usize]) -> Result<Vec<u8>> {
+        const NC_DIMENSION: u32 = 0x0A;
+        const NC_VARIABLE: u32 = 0x0B;
+        const NC_ATTRIBUTE: u32 = 0x0C;
+
+        let mut bytes = b"CDF".to_vec();
+        bytes.push(version);
+        // Number of records: there is no record dimension
+        bytes.extend(0u32.to_be_bytes());
+        put_list(&mut bytes, NC_DIMENSION, self.dimensions.len());
+        for (name, len) in &self.dimensions {
+            put_name(&mut bytes, name);
+            bytes.extend((*len as u32).to_be_bytes());
+        }
+        put_attributes(&mut bytes, NC_ATTRIBUTE, &self.attributes);
+        put_list(&mut bytes, NC_VARIABLE, self.variables.len());
+        for (variable, &begin) in self.variables.iter().zip(begins) {
+            put_name(&mut bytes, &variable.name);
+            bytes.extend((variable.dimensions.len() as u32).to_be_bytes());
+            for &dimension in &variable.dimensions {
+                bytes.extend((dimension as u32).to_be_bytes());
+            }
+            put_attributes(&mut bytes, NC_ATTRIBUTE, &variable.attributes);
+            bytes.extend(variable.values.nc_type().to_be_bytes());
+            let size = variable.values.size();
+            bytes.extend(u32::try_from(size).map_err(|_| too_large())?.to_be_bytes());
+            match version {
+                1 => bytes.extend(u32::try_from(begin).map_err(|_| too_large())?.to_be_bytes()),
+                _ => bytes.extend((begin as u64).to_be_bytes()),
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+/// Tag and number of elements of a list, or ABSENT for empty lists
+fn put_list(bytes: &mut Vec<u8>, tag: u32, len: usize) {
+    let tag = if len == 0 { 0 } else { tag };
+    bytes.extend(tag.to_be_bytes());
+    bytes.extend((len as u32).to_be_bytes());
+}
+
+fn put_name(bytes: &mut Vec<u8>, name: &str) {
+    let name = Values::text(name);
+    bytes.extend((name.len() as u32).to_be_bytes());
+    bytes.extend(name.encode());
+}
+
+fn put_attributes(bytes: &mut Vec<u8>, tag: u32, attributes: &[(&'static str, Values)]) {
+    put_list(bytes, tag, attributes.len());
+    for (name, value) in attributes {
+        put_name(bytes, name);
+        bytes.extend(value.nc_type().to_be_bytes());
+        bytes.extend((value.len() as u32).to_be_bytes());
+        bytes.extend(value.encode());
+    }
+}
+
+fn too_large() -> Error {
+    Error::UnsupportedData("NetCDF variables larger than 4 GiB are not supported".to_string())
+}
+
+/// Writes fields as a NetCDF-3 file following the CF conventions.
+///
+/// All fields must be on the same grid and carry their [`FieldMetadata`]. Fields of the same
+/// parameter on the same type of surface, with the same statistical processing over time ranges
+/// of the same length, make up one variable. Variables have a `time` dimension over the valid
+/// times of all the fields and, for surfaces with a vertical coordinate such as pressure or
+/// height, a dimension over the levels of the surface type. Combinations of time and level
+/// without a field are filled with NaN. Values are laid out north-up, with the coordinates of
+/// the projection and, on projected and rotated grids, the latitude and longitude of every
+/// point.
+pub fn write_netcdf<W: Write>(mut writer: W, fields: &[Field]) -> Result<()> {
+    let first = fields
+        .first()
+        .ok_or_else(|| Error::InvalidData("no fields to write".to_string()))?;
+    let grid = &first.grid;
+    let unsupported = || {
+        Error::UnsupportedData(format!(
+            "grids of template 3.{} can't be written as NetCDF",
+            grid.template.template_number()
+        ))
+    };
+    let extent = canonical_extent(grid).ok_or_else(unsupported)?;
+    let structured = grid.as_structured().ok_or_else(unsupported)?;
+    let transform = grid.template.crs_transform().ok_or_else(unsupported)?;
+    let projection = grid.template.projection().ok_or_else(unsupported)?;
+
+    let mut entries = vec![];
+    for field in fields {
+        if !Arc::ptr_eq(&field.grid, grid)
+            && canonical_extent(&field.grid).as_ref() != Some(&extent)
+        {
+            return Err(Error::InvalidData(
+                "fields on different grids can't be written to the same file".to_string(),
+            ));
+        }
+        let metadata = field.metadata.as_ref().ok_or_else(|| {
+            Error::InvalidData("fields without metadata can't be written as NetCDF".to_string())
+        })?;
+        let valid_time = metadata.valid_time().ok_or_else(|| {
+            Error::UnsupportedData(format!(
+                "forecast times in units of code {} are not supported",
+                metadata.indicator_of_unit_of_time_range
+            ))
+        })?;
+        entries.push((field, metadata, valid_time));
+    }
+
+    let mut dataset = Dataset::default();
+    dataset
+        .attributes
+        .push(("Conventions", Values::text("CF-1.8")));
+
+    // Time
+    let mut times: Vec<DateTime> = entries.iter().map(|&(_, _, time)| time).collect();
+    times.sort();
+    times.dedup();
+    let reference = entries
+        .iter()
+        .map(|(_, metadata, _)| metadata.reference_time)
+        .min()
+        .unwrap_or(times[0]);
+    let time_units = format!(
+        "seconds since {:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        reference.year,
+        reference.month,
+        reference.day,
+        reference.hour,
+        reference.minute,
+        reference.second
+    );
+    let time_dimension = dataset.dimension("time", times.len());
+    let seconds = times
+        .iter()
+        .map(|time| (time.unix_seconds() - reference.unix_seconds()) as f64)
+        .collect();
+    dataset.variables.push(
+        Variable::new("time", vec![time_dimension], Values::Double(seconds))
+            .attribute("standard_name", Values::text("time"))
+            .attribute("units", Values::text(&time_units))
+            .attribute("calendar", Values::text("proleptic_gregorian"))
+            .attribute("axis", Values::text("T")),
+    );
+    if entries
+        .iter()
+        .all(|(_, metadata, _)| metadata.reference_time == reference)
+    {
+        dataset.variables.push(
+            Variable::new("forecast_reference_time", vec![], Values::Double(vec![0.0]))
+                .attribute("standard_name", Values::text("forecast_reference_time"))
+                .attribute("units", Values::text(&time_units)),
+        );
+    }
+
+    // Vertical axes, one per type of surface
+    let mut axes: Vec<(SurfaceTypes, Vec<Level>)> = vec![];
+    for (_, metadata, _) in &entries {
+        let Some((types, level)) = level(metadata) else {
+            continue;
+        };
+        match axes.iter_mut().find(|(t, _)| *t == types) {
+            Some((_, levels)) => levels.push(level),
+            None => axes.push((types, vec![level])),
+        }
+    }
+    let mut axis_dimensions = vec![];
+    let mut bounds_dimension = None;
+    for (types, levels) in &mut axes {
+        levels.sort_by(|a, b| {
+            a.0.total_cmp(&b.0)
+                .then(a.1.unwrap_or(0.0).total_cmp(&b.1.unwrap_or(0.0)))
+        });
+        levels.dedup();
+        let surface = FixedSurface {
+            surface_type: types.0,
+            value: None,
+        };
+        let mut name = match surface.abbreviation() {
+            Some(abbreviation) => format!("level_{}", abbreviation.to_lowercase()),
+            None => format!("level_{}", types.0),
+        };
+        if types.1.is_some() {
+            name.push_str("_layer");
+        }
+        let dimension = dataset.dimension(&name, levels.len());
+        axis_dimensions.push(dimension);
+        let mut variable = Variable::new(
+            &name,
+            vec![dimension],
+            Values::Double(levels.iter().map(|level| level.0).collect()),
+        );
+        if let Some(standard_name) = surface.standard_name() {
+            variable = variable.attribute("standard_name", Values::text(standard_name));
+        }
+        if let Some(units) = surface.units() {
+            variable = variable.attribute("units", Values::text(units));
+        }
+        if let Some(positive) = positive(types.0) {
+            variable = variable
+                .attribute("positive", Values::text(positive))
+                .attribute("axis", Values::text("Z"));
+        }
+        if types.1.is_some() {
+            let nv = *bounds_dimension.get_or_insert_with(|| dataset.dimension("nv", 2));
+            let bounds = format!("{}_bounds", name);
+            variable = variable.attribute("bounds", Values::text(&bounds));
+            dataset.variables.push(variable);
+            variable = Variable::new(
+                bounds,
+                vec![dimension, nv],
+                Values::Double(
+                    levels
+                        .iter()
+                        .flat_map(|&(first, second)| [first, second.unwrap_or(first)])
+                        .collect(),
+                ),
+            );
+        }
+        dataset.variables.push(variable);
+    }
+
+    // Horizontal coordinates
+    let (ni, nj) = structured.dimensions();
+    let (west, north) = north_west(structured);
+    // Names, standard names and units of the x and y coordinates
+    let (x, y) = match projection {
+        Projection::LatLon => (
+            ("lon", "longitude", "degrees_east"),
+            ("lat", "latitude", "degrees_north"),
+        ),
+        Projection::Rotated { .. } => (
+            ("rlon", "grid_longitude", "degrees"),
+            ("rlat", "grid_latitude", "degrees"),
+        ),
+        _ => (
+            ("x", "projection_x_coordinate", "m"),
+            ("y", "projection_y_coordinate", "m"),
+        ),
+    };
+    let y_dimension = dataset.dimension(y.0, nj);
+    let x_dimension = dataset.dimension(x.0, ni);
+    let y_values = (0..nj)
+        .map(|row| transform.apply(west, north - row as f64).1)
+        .collect();
+    let x_values = (0..ni)
+        .map(|column| transform.apply(west + column as f64, north).0)
+        .collect();
+    for ((name, standard_name, units), dimension, axis, values) in [
+        (y, y_dimension, "Y", y_values),
+        (x, x_dimension, "X", x_values),
+    ] {
+        dataset.variables.push(
+            Variable::new(name, vec![dimension], Values::Double(values))
+                .attribute("standard_name", Values::text(standard_name))
+                .attribute("units", Values::text(units))
+                .attribute("axis", Values::text(axis)),
+        );
+    }
+    let auxiliary = projection != Projection::LatLon;
+    if auxiliary {
+        let geometry = first.geometry()?;
+        let latlon: Vec<(f64, f64)> = (0..ni * nj)
+            .map(|index| geometry.latlon(index).unwrap_or((f64::NAN, f64::NAN)))
+            .collect();
+        let (latlon, _) = grid.to_canonical(&latlon)?;
+        for (name, standard_name, units, values) in [
+            (
+                "lat",
+                "latitude",
+                "degrees_north",
+                latlon.iter().map(|p| p.0).collect(),
+            ),
+            (
+                "lon",
+                "longitude",
+                "degrees_east",
+                latlon.iter().map(|p| p.1).collect(),
+            ),
+        ] {
+            dataset.variables.push(
+                Variable::new(name, vec![y_dimension, x_dimension], Values::Double(values))
+                    .attribute("standard_name", Values::text(standard_name))
+                    .attribute("units", Values::text(units)),
+            );
+        }
+    }
+    dataset
+        .variables
+        .push(grid_mapping(grid, projection).ok_or_else(unsupported)?);
+
+    // Data variables, one per parameter and type of surface
+    let mut groups: Vec<(VariableKey, Vec<usize>)> = vec![];
+    for (k, (_, metadata, _)) in entries.iter().enumerate() {
+        let key = (
+            metadata.discipline,
+            metadata.parameter_category,
+            metadata.parameter_number,
+            metadata.product_definition_template_number,
+            metadata.first_fixed_surface.map(|s| s.surface_type),
+            metadata.second_fixed_surface.map(|s| s.surface_type),
+            metadata.statistical_process,
+        );
+        match groups.iter_mut().find(|(g, _)| *g == key) {
+            Some((_, members)) => members.push(k),
+            None => groups.push((key, vec![k])),
+        }
+    }
+    let uv_relative = first.geometry()?.uv_relative_to_grid();
+    for (_, members) in groups {
+        let (_, metadata, _) = entries[members[0]];
+        let parameter = metadata.parameter();
+        let mut name = match parameter {
+            Some(parameter) => parameter.short_name.to_string(),
+            None => format!(
+                "VAR{}_{}_{}",
+                metadata.discipline, metadata.parameter_category, metadata.parameter_number
+            ),
+        };
+        if let Some(surface) = metadata.first_fixed_surface {
+            match surface.abbreviation() {
+                Some(abbreviation) => name = format!("{}_{}", name, abbreviation),
+                None => name = format!("{}_L{}", name, surface.surface_type),
+            }
+        }
+        if let Some(process) = metadata.statistical_process {
+            match process.abbreviation() {
+                Some(abbreviation) => name = format!("{}_{}", name, abbreviation),
+                None => name = format!("{}_S{}", name, process.process),
+            }
+            name = format!("{}_{}", name, process.length_name());
+        }
+        if dataset.variables.iter().any(|v| v.name == name) {
+            name = (2..)
+                .map(|k| format!("{}_{}", name, k))
+                .find(|candidate| dataset.variables.iter().all(|v| &v.name != candidate))
+                .unwrap_or(name);
+        }
+
+        let axis =
+            level(metadata).and_then(|(types, _)| axes.iter().position(|(t, _)| *t == types));
+        let levels: &[Level] = axis.map_or(&[(0.0, None)], |axis| &axes[axis].1);
+        let mut dimensions = vec![time_dimension];
+        dimensions.extend(axis.map(|axis| axis_dimensions[axis]));
+        dimensions.extend([y_dimension, x_dimension]);
+
+        let size = ni * nj;
+        let mut values = vec![f32::NAN; times.len() * levels.len() * size];
+        let mut filled = vec![false; times.len() * levels.len()];
+        for k in members {
+            let (field, metadata, valid_time) = entries[k];
+            let t = times.binary_search(&valid_time).unwrap_or_default();
+            let l = level(metadata)
+                .and_then(|(_, level)| levels.iter().position(|&l| l == level))
+                .unwrap_or_default();
+            let slot = t * levels.len() + l;
+            if std::mem::replace(&mut filled[slot], true) {
+                return Err(Error::InvalidData(format!(
+                    "several fields of {} are valid at {} on the same level",
+                    name, valid_time
+                )));
+            }
+            let (canonical, _) = field.grid.to_canonical(&field.values)?;
+            values[slot * size..(slot + 1) * size].copy_from_slice(&canonical);
+        }
+
+        let mut variable = Variable::new(&name, dimensions, Values::Float(values));
+        if let Some(parameter) = parameter {
+            let standard_name = match (parameter.standard_name, uv_relative, projection) {
+                (Some("eastward_wind"), true, Projection::Rotated { .. }) => {
+                    Some("grid_eastward_wind")
+                }
+                (Some("northward_wind"), true, Projection::Rotated { .. }) => {
+                    Some("grid_northward_wind")
+                }
+                (Some("eastward_wind"), true, _) => Some("x_wind"),
+                (Some("northward_wind"), true, _) => Some("y_wind"),
+                (standard_name, _, _) => standard_name,
+            };
+            variable = variable
+                .attribute("long_name", Values::text(parameter.name))
+                .attribute("units", Values::text(parameter.units));
+            if let Some(standard_name) = standard_name {
+                variable = variable.attribute("standard_name", Values::text(standard_name));
+            }
+        }
+        if let Some(method) = metadata.statistical_process.and_then(cell_method) {
+            variable =
+                variable.attribute("cell_methods", Values::text(format!("time: {}", method)));
+        }
+        variable = variable
+            .attribute("_FillValue", Values::Float(vec![f32::NAN]))
+            .attribute("grid_mapping", Values::text("crs"));
+        if auxiliary {
+            variable = variable.attribute("coordinates", Values::text("lat lon"));
+        }
+        dataset.variables.push(variable);
+    }
+
+    writer.write_all(&dataset.encode()?)?;
+    Ok(())
+}
+
+/// Vertical axis and level of a field, None for surfaces without a vertical coordinate such as
+/// the ground or the mean sea level
+fn level(metadata: &FieldMetadata) -> Option<(SurfaceTypes, Level)> {
+    let first = metadata
+        .first_fixed_surface
+        .filter(|s| s.units().is_some())?;
+    let second = metadata.second_fixed_surface.filter(|s| s.value.is_some());
+    Some((
+        (first.surface_type, second.map(|s| s.surface_type)),
+        (first.value?, second.and_then(|s| s.value)),
+    ))
+}
+
+/// CF cell method of a statistical process over time
+fn cell_method(process: StatisticalProcess) -> Option<&'static str> {
+    Some(match process.process {
+        0 => "mean",
+        1 => "sum",
+        2 => "maximum",
+        3 => "minimum",
+        6 => "standard_deviation",
+        _ => return None,
+    })
+}
+
+/// Direction of increasing values of the vertical coordinate of a type of surface
+fn positive(surface_type: u8) -> Option<&'static str> {
+    Some(match surface_type {
+        100 | 104 | 105 | 106 | 160 => "down",
+        102 | 103 | 107 => "up",
+        _ => return None,
+    })
+}
+
+/// Projection, size and position of the canonical layout of a grid, equal for grids whose fields
+/// can share variables
+fn canonical_extent(grid: &GridDefinition) -> Option<Extent> {
+    let structured = grid.as_structured()?;
+    let transform = grid.template.crs_transform()?;
+    let (west, north) = north_west(structured);
+    Some((
+        grid.template.proj_string()?,
+        structured.dimensions(),
+        transform.apply(west, north),
+        transform.step,
+    ))
+}
+
+/// Grid mapping variable `crs` describing the projection and the shape of the earth
+fn grid_mapping(grid: &GridDefinition, projection: Projection) -> Option<Variable> {
+    let double = |value: f64| Values::Double(vec![value]);
+    let mut variable = Variable::new("crs", vec![], Values::Int(vec![0]));
+    let parameters: Vec<(&'static str, Values)> = match projection {
+        Projection::LatLon => vec![("grid_mapping_name", Values::text("latitude_longitude"))],
+        Projection::Rotated {
+            pole_lat,
+            pole_lon,
+            angle,
+        } => vec![
+            (
+                "grid_mapping_name",
+                Values::text("rotated_latitude_longitude"),
+            ),
+            ("grid_north_pole_latitude", double(-pole_lat)),
+            (
+                "grid_north_pole_longitude",
+                double(pole_lon.rem_euclid(360.0) - 180.0),
+            ),
+            ("north_pole_grid_longitude", double(-angle)),
+        ],
+        Projection::Mercator { lat_ts } => vec![
+            ("grid_mapping_name", Values::text("mercator")),
+            ("standard_parallel", double(lat_ts)),
+            ("longitude_of_projection_origin", double(0.0)),
+        ],
+        Projection::PolarStereographic {
+            south,
+            lat_ts,
+            lon_0,
+        } => vec![
+            ("grid_mapping_name", Values::text("polar_stereographic")),
+            (
+                "latitude_of_projection_origin",
+                double(if south { -90.0 } else { 90.0 }),
+            ),
+            ("straight_vertical_longitude_from_pole", double(lon_0)),
+            ("standard_parallel", double(lat_ts)),
+        ],
+        Projection::LambertConformal {
+            lat_0,
+            lon_0,
+            lat_1,
+            lat_2,
+        } => vec![
+            ("grid_mapping_name", Values::text("lambert_conformal_conic")),
+            (
+                "standard_parallel",
+                Values::Double(match lat_1 == lat_2 {
+                    true => vec![lat_1],
+                    false => vec![lat_1, lat_2],
+                }),
+            ),
+            ("longitude_of_central_meridian", double(lon_0)),
+            ("latitude_of_projection_origin", double(lat_0)),
+        ],
+        Projection::LambertAzimuthal { lat_0, lon_0 } => vec![
+            (
+                "grid_mapping_name",
+                Values::text("lambert_azimuthal_equal_area"),
+            ),
+            ("longitude_of_projection_origin", double(lon_0)),
+            ("latitude_of_projection_origin", double(lat_0)),
+        ],
+        Projection::Geostationary { .. } => return None,
+    };
+    let projected = !matches!(projection, Projection::LatLon | Projection::Rotated { .. });
+    variable.attributes.extend(parameters);
+    if projected {
+        variable = variable
+            .attribute("false_easting", double(0.0))
+            .attribute("false_northing", double(0.0));
+    }
+
+    let earth = grid.template.earth_shape()?;
+    let ellipsoid = earth.ellipsoid();
+    variable = match ellipsoid.is_sphere() {
+        true => variable.attribute("earth_radius", double(ellipsoid.a)),
+        false => variable
+            .attribute("semi_major_axis", double(ellipsoid.a))
+            .attribute("semi_minor_axis", double(ellipsoid.b)),
+    };
+    variable = variable.attribute("longitude_of_prime_meridian", double(0.0));
+    if let Some(wkt) = grid.template.wkt2() {
+        variable = variable.attribute("crs_wkt", Values::text(wkt));
+    }
+    Some(variable)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::field::{grib_message, product_definition_template, read_metadata};
+    use crate::grid::{global_grid, predefined_grid};
+
+    /// Attribute or variable values as their type and big-endian bytes
+    type Raw = (u32, Vec<u8>);
+
+    struct ParsedVariable {
+        dimensions: Vec<usize>,
+        attributes: BTreeMap<String, Raw>,
+        values: Raw,
+    }
+
+    /// Header of a classic NetCDF file parsed back, with the values of its variables
+    struct Parsed {
+        dimensions: Vec<(String, usize)>,
+        attributes: BTreeMap<String, Raw>,
+        variables: BTreeMap<String, ParsedVariable>,
+    }
+
+    impl Parsed {
+        fn new(file: &[u8]) -> Self {
+            assert_eq!(&file[..4], b"CDF\x01");
+            let mut parser = Parser { file, at: 4 };
+            // No records
+            assert_eq!(parser.u32(), 0);
+            let dimensions = (0..parser.list(0x0A))
+                .map(|_| (parser.name(), parser.u32() as usize))
+                .collect();
+            let attributes = parser.attributes();
+            let mut variables = BTreeMap::new();
+            for _ in 0..parser.list(0x0B) {
+                let name = parser.name();
+                let dimensions = (0..parser.u32()).map(|_| parser.u32() as usize).collect();
+                let attributes = parser.attributes();
+                let nc_type = parser.u32();
+                let size = parser.u32() as usize;
+                let begin = parser.u32() as usize;
+                let values = (nc_type, file[begin..begin + size].to_vec());
+                variables.insert(
+                    name,
+                    ParsedVariable {
+                        dimensions,
+                        attributes,
+                        values,
+                    },
+                );
+            }
+            Self {
+                dimensions,
+                attributes,
+                variables,
+            }
+        }
+
+        fn variable(&self, name: &str) -> &ParsedVariable {
+            self.variables
+                .get(name)
+                .unwrap_or_else(|| panic!("no variable {name} in {:?}", self.variables.keys()))
+        }
+
+        /// Names of the dimensions of a variable
+        fn dimensions_of(&self, name: &str) -> Vec<&str> {
+            self.variable(name)
+                .dimensions
+                .iter()
+                .map(|&d| self.dimensions[d].0.as_str())
+                .collect()
+        }
+    }
+
+    struct Parser<'a> {
+        file: &'a [u8],
+        at: usize,
+    }
+
+    impl Parser<'_> {
+        fn u32(&mut self) -> u32 {
+            let value = u32::from_be_bytes(self.file[self.at..self.at + 4].try_into().unwrap());
+            self.at += 4;
+            value
+        }
+
+        fn bytes(&mut self, len: usize) -> Vec<u8> {
+            let bytes = self.file[self.at..self.at + len].to_vec();
+            self.at += len.next_multiple_of(4);
+            bytes
+        }
+
+        /// Number of elements of a list with the given tag
+        fn list(&mut self, tag: u32) -> u32 {
+            let (found, len) = (self.u32(), self.u32());
+            assert_eq!(found, if len == 0 { 0 } else { tag });
+            len
+        }
+
+        fn name(&mut self) -> String {
+            let len = self.u32() as usize;
+            String::from_utf8(self.bytes(len)).unwrap()
+        }
+
+        fn attributes(&mut self) -> BTreeMap<String, Raw> {
+            (0..self.list(0x0C))
+                .map(|_| {
+                    let name = self.name();
+                    let nc_type = self.u32();
+                    let len = self.u32() as usize;
+                    let size = match nc_type {
+                        2 => 1,
+                        4 | 5 => 4,
+                        _ => 8,
+                    };
+                    (name, (nc_type, self.bytes(len * size)))
+                })
+                .collect()
+        }
+    }
+
+    fn text((nc_type, bytes): &Raw) -> &str {
+        assert_eq!(*nc_type, 2);
+        std::str::from_utf8(bytes).unwrap()
+    }
+
+    fn floats((nc_type, bytes): &Raw) -> Vec<f32> {
+        assert_eq!(*nc_type, 5);
+        bytes
+            .chunks(4)
+            .map(|b| f32::from_be_bytes(b.try_into().unwrap()))
+            .collect()
+    }
+
+    fn doubles((nc_type, bytes): &Raw) -> Vec<f64> {
+        assert_eq!(*nc_type, 6);
+        bytes
+            .chunks(8)
+            .map(|b| f64::from_be_bytes(b.try_into().unwrap()))
+            .collect()
+    }
+
+    /// Field on `grid` with the metadata of a template 4.0 or 4.8 read from a GRIB2 message
+    fn field(
+        grid: &Arc<GridDefinition>,
+        parameter: (u8, u8),
+        hours: i32,
+        surface: (u8, u32),
+        accumulation: Option<u32>,
+        value: f32,
+    ) -> Field {
+        let n = grid.header.number_of_data_points;
+        let template = product_definition_template(parameter, hours, surface, accumulation);
+        let number = if accumulation.is_some() { 8 } else { 0 };
+        let [metadata] = read_metadata(&grib_message(0, n, number, &template))
+            .unwrap()
+            .try_into()
+            .unwrap();
+        Field::new(grid.clone(), vec![value; n as usize])
+            .unwrap()
+            .with_metadata(metadata)
+    }
+
+    fn write(fields: &[Field]) -> Result<Parsed> {
+        let mut file = vec![];
+        write_netcdf(&mut file, fields)?;
+        Ok(Parsed::new(&file))
+    }
+
+    #[test]
+    fn sizes_match_the_encoded_values() {
+        for values in [
+            Values::text("crs"),
+            Values::text("CF-1.8"),
+            Values::Int(vec![1, 2, 3]),
+            Values::Float(vec![1.0]),
+            Values::Double(vec![1.0, 2.0]),
+            Values::Double(vec![]),
+        ] {
+            assert_eq!(values.size(), values.encode().len());
+            assert_eq!(values.size() % 4, 0);
+        }
+    }
+
+    #[test]
+    fn writes_variables_over_time_and_levels() {
+        let grid = Arc::new(global_grid(36, 19, 10_000_000));
+        let mut fields = vec![
+            field(&grid, (0, 0), 0, (100, 50_000), None, 250.0),
+            field(&grid, (0, 0), 6, (100, 50_000), None, 251.0),
+            field(&grid, (0, 0), 6, (100, 85_000), None, 280.0),
+            field(&grid, (0, 0), 6, (103, 2), None, 290.0),
+        ];
+        // Point at 80N 20E
+        fields[1].values[36 + 2] = f32::NAN;
+        let parsed = write(&fields).unwrap();
+        assert_eq!(text(&parsed.attributes["Conventions"]), "CF-1.8");
+        assert_eq!(
+            parsed.dimensions,
+            [
+                ("time".to_string(), 2),
+                ("level_isbl".to_string(), 2),
+                ("level_htgl".to_string(), 1),
+                ("lat".to_string(), 19),
+                ("lon".to_string(), 36),
+            ]
+        );
+
+        let time = parsed.variable("time");
+        assert_eq!(doubles(&time.values), [0.0, 21_600.0]);
+        assert_eq!(
+            text(&time.attributes["units"]),
+            "seconds since 2024-06-01 12:00:00"
+        );
+        assert!(parsed.variables.contains_key("forecast_reference_time"));
+        let level = parsed.variable("level_isbl");
+        assert_eq!(doubles(&level.values), [50_000.0, 85_000.0]);
+        assert_eq!(text(&level.attributes["units"]), "Pa");
+        assert_eq!(text(&level.attributes["positive"]), "down");
+        let lat = doubles(&parsed.variable("lat").values);
+        assert_eq!((lat[0], lat[18]), (90.0, -90.0));
+        let lon = doubles(&parsed.variable("lon").values);
+        assert_eq!((lon[0], lon[35]), (0.0, 350.0));
+        let crs = &parsed.variable("crs").attributes;
+        assert_eq!(text(&crs["grid_mapping_name"]), "latitude_longitude");
+        assert_eq!(doubles(&crs["earth_radius"]), [6_371_229.0]);
+
+        assert_eq!(
+            parsed.dimensions_of("TMP_ISBL"),
+            ["time", "level_isbl", "lat", "lon"]
+        );
+        let temperature = parsed.variable("TMP_ISBL");
+        assert_eq!(text(&temperature.attributes["units"]), "K");
+        assert_eq!(text(&temperature.attributes["grid_mapping"]), "crs");
+        assert!(!temperature.attributes.contains_key("coordinates"));
+        let values = floats(&temperature.values);
+        let size = 36 * 19;
+        assert_eq!(values.len(), 2 * 2 * size);
+        // 850 hPa is missing at the first time
+        let slot = |time: usize, level: usize| &values[(time * 2 + level) * size..][..size];
+        assert!(slot(0, 0).iter().all(|&v| v == 250.0));
+        assert!(slot(0, 1).iter().all(|v| v.is_nan()));
+        assert!(slot(1, 0)[38].is_nan());
+        assert_eq!(slot(1, 0)[39], 251.0);
+        assert!(slot(1, 1).iter().all(|&v| v == 280.0));
+        assert_eq!(
+            parsed.dimensions_of("TMP_HTGL"),
+            ["time", "level_htgl", "lat", "lon"]
+        );
+    }
+
+    #[test]
+    fn separates_statistical_processing() {
+        let grid = Arc::new(global_grid(36, 19, 10_000_000));
+        // Accumulations over the last hour and the last 6 hours, both valid at 18:00
+        let fields = [
+            field(&grid, (1, 8), 6, (1, 0), Some(1), 1.0),
+            field(&grid, (1, 8), 6, (1, 0), Some(6), 5.0),
+            field(&grid, (1, 8), 12, (1, 0), Some(6), 7.0),
+        ];
+        let parsed = write(&fields).unwrap();
+        let hourly = parsed.variable("APCP_SFC_acc_1h");
+        assert_eq!(
+            parsed.dimensions_of("APCP_SFC_acc_1h"),
+            ["time", "lat", "lon"]
+        );
+        assert_eq!(text(&hourly.attributes["cell_methods"]), "time: sum");
+        let values = floats(&hourly.values);
+        assert!(values[..36 * 19].iter().all(|&v| v == 1.0));
+        assert!(values[36 * 19..].iter().all(|v| v.is_nan()));
+        let six_hourly = parsed.variable("APCP_SFC_acc_6h");
+        let values = floats(&six_hourly.values);
+        assert_eq!((values[0], values[36 * 19]), (5.0, 7.0));
+        assert_eq!(
+            doubles(&parsed.variable("time").values),
+            [21_600.0, 43_200.0]
+        );
+
+        // The same accumulation twice at the same time is an error
+        let fields = [fields[1].clone(), fields[1].clone()];
+        assert!(matches!(write(&fields), Err(Error::InvalidData(_))));
+    }
+
+    #[test]
+    fn writes_projected_grids() {
+        let grid = Arc::new(predefined_grid(211));
+        let parsed = write(&[field(&grid, (0, 0), 0, (103, 2), None, 290.0)]).unwrap();
+        let crs = &parsed.variable("crs").attributes;
+        assert_eq!(text(&crs["grid_mapping_name"]), "lambert_conformal_conic");
+        assert_eq!(doubles(&crs["standard_parallel"]), [25.0]);
+        assert_eq!(doubles(&crs["longitude_of_central_meridian"]), [265.0]);
+        assert_eq!(parsed.dimensions_of("lat"), ["y", "x"]);
+        let temperature = parsed.variable("TMP_HTGL");
+        assert_eq!(text(&temperature.attributes["coordinates"]), "lat lon");
+        assert_eq!(
+            parsed.dimensions_of("TMP_HTGL"),
+            ["time", "level_htgl", "y", "x"]
+        );
+
+        // North-up: the first row is the last one of the grid, which scans from the south
+        let y = doubles(&parsed.variable("y").values);
+        let x = doubles(&parsed.variable("x").values);
+        assert!((x[0] - -4_226_106.997).abs() < 1.0, "{}", x[0]);
+        assert!((y[64] - -832_698.261).abs() < 1.0, "{}", y[64]);
+        assert!(y[0] > y[64]);
+    }
+
+    #[test]
+    fn rejects_fields_that_dont_fit() {
+        assert!(matches!(write(&[]), Err(Error::InvalidData(_))));
+        let grid = Arc::new(global_grid(36, 19, 10_000_000));
+        let mut bare = field(&grid, (0, 0), 0, (103, 2), None, 290.0);
+        bare.metadata = None;
+        assert!(matches!(write(&[bare]), Err(Error::InvalidData(_))));
+        let other = Arc::new(global_grid(72, 37, 5_000_000));
+        let fields = [
+            field(&grid, (0, 0), 0, (103, 2), None, 290.0),
+            field(&other, (0, 0), 0, (103, 2), None, 290.0),
+        ];
+        assert!(matches!(write(&fields), Err(Error::InvalidData(_))));
+    }
+}
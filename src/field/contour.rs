@@ -0,0 +1,632 @@
+use std::cmp::Ordering;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::Field;
+use crate::grid::{Polygon, StructuredGrid, ring_contains, unwrap_ring};
+use crate::{Error, Result};
+
+/// Line along which the field equals `level`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContourLine {
+    pub level: f64,
+    /// `(lat, lon)` vertices in degrees, with longitudes continuous along the line
+    pub points: Vec<(f64, f64)>,
+    /// Whether the last point connects back to the first
+    pub closed: bool,
+}
+
+impl ContourLine {
+    /// Vertices as GeoJSON coordinates (`[lon, lat]`), repeating the first at the end if the
+    /// line is closed
+    pub fn geojson_coordinates(&self) -> Vec<[f64; 2]> {
+        let mut coordinates: Vec<[f64; 2]> =
+            self.points.iter().map(|&(lat, lon)| [lon, lat]).collect();
+        if let (true, Some(&first)) = (self.closed, coordinates.first()) {
+            coordinates.push(first);
+        }
+        coordinates
+    }
+}
+
+/// Area where the field is between `lower` and `upper`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Isoband {
+    pub lower: f64,
+    pub upper: f64,
+    pub polygons: Vec<Polygon>,
+}
+
+impl Field {
+    /// Contour lines of the field at every level
+    ///
+    /// Lines stop at missing values and at the edges of the grid.
+    pub fn contours(&self, levels: &[f64]) -> Result<Vec<ContourLine>> {
+        let surface = Surface::new(self)?;
+        let mut lines = vec![];
+        for (k, &level) in levels.iter().enumerate() {
+            let mut segments = vec![];
+            for triangle in surface.triangles() {
+                let above = triangle.map(|node| surface.value(node) >= level);
+                let (mut entry, mut exit) = (None, None);
+                for e in 0..3 {
+                    let (p, q) = (triangle[e], triangle[(e + 1) % 3]);
+                    match (above[e], above[(e + 1) % 3]) {
+                        (false, true) => entry = Some(Vertex::cut(p, q, k)),
+                        (true, false) => exit = Some(Vertex::cut(p, q, k)),
+                        _ => {}
+                    }
+                }
+                if let (Some(entry), Some(exit)) = (entry, exit) {
+                    segments.push((surface.seam(entry), surface.seam(exit)));
+                }
+            }
+            for (ring, closed) in chain(segments) {
+                let (positions, _) = surface.positions(&ring, levels);
+                let Some(points) = surface.latlons(&positions) else {
+                    continue;
+                };
+                lines.push(ContourLine {
+                    level,
+                    points: unwrap_ring(points, None),
+                    closed,
+                });
+            }
+        }
+        Ok(lines)
+    }
+
+    /// Filled areas between consecutive `bounds`, which must be increasing
+    ///
+    /// Each band includes its lower bound and its upper bound. Missing values make holes in
+    /// the bands.
+    pub fn isobands(&self, bounds: &[f64]) -> Result<Vec<Isoband>> {
+        if bounds
+            .windows(2)
+            .any(|w| w[0].partial_cmp(&w[1]) != Some(Ordering::Less))
+        {
+            return Err(Error::InvalidData(
+                "bounds of isobands must be increasing".to_string(),
+            ));
+        }
+        let surface = Surface::new(self)?;
+        let triangles: Vec<[Node; 3]> = surface.triangles().collect();
+        let mut bands = vec![];
+        for (k, pair) in bounds.windows(2).enumerate() {
+            let pieces: Vec<Vec<Vertex>> = triangles
+                .iter()
+                .map(|triangle| {
+                    let piece = triangle.map(Vertex::Node).to_vec();
+                    let piece = surface.clip(piece, bounds, k, |v| v >= pair[0]);
+                    surface.clip(piece, bounds, k + 1, |v| v <= pair[1])
+                })
+                .filter(|piece| !piece.is_empty())
+                .collect();
+
+            // Pieces joined over the seam of periodic grids make one polygon, unless they go
+            // around the globe: such areas keep the seam as part of their boundary
+            let mut rings = vec![];
+            for component in surface.components(&pieces) {
+                let component: Vec<&[Vertex]> =
+                    component.iter().map(|&p| pieces[p].as_slice()).collect();
+                let merged: Vec<(Vec<(f64, f64)>, isize)> =
+                    chain(boundary(&component, |v| surface.seam(v)))
+                        .into_iter()
+                        .map(|(ring, _)| surface.positions(&ring, bounds))
+                        .collect();
+                match merged.iter().all(|&(_, winding)| winding == 0) {
+                    true => rings.extend(merged.into_iter().map(|(positions, _)| positions)),
+                    false => rings.extend(
+                        chain(boundary(&component, |v| v))
+                            .into_iter()
+                            .map(|(ring, _)| surface.positions(&ring, bounds).0),
+                    ),
+                }
+            }
+
+            // Exteriors run counterclockwise on the (i, j) plane, and holes clockwise
+            let (mut exteriors, mut holes) = (vec![], vec![]);
+            for positions in rings {
+                match signed_area(&positions) {
+                    a if a > 0.0 => exteriors.push((positions, a)),
+                    a if a < 0.0 => holes.push(positions),
+                    _ => {}
+                }
+            }
+            let shifts = match surface.columns > surface.ni {
+                true => vec![0.0, surface.ni as f64, -(surface.ni as f64)],
+                false => vec![0.0],
+            };
+            let mut polygon_holes: Vec<Vec<Vec<(f64, f64)>>> = vec![vec![]; exteriors.len()];
+            for positions in holes {
+                let (a, b) = (positions[0], positions[1]);
+                let inside = ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+                let parent = exteriors
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (exterior, _))| {
+                        shifts
+                            .iter()
+                            .any(|shift| ring_contains(exterior, inside.0 + shift, inside.1))
+                    })
+                    .min_by(|(_, x), (_, y)| x.1.total_cmp(&y.1))
+                    .map(|(e, _)| e);
+                if let Some(e) = parent {
+                    polygon_holes[e].push(positions);
+                }
+            }
+            let polygons = exteriors
+                .into_iter()
+                .zip(polygon_holes)
+                .filter_map(|((exterior, _), holes)| {
+                    let holes = holes
+                        .iter()
+                        .map(|hole| surface.latlons(hole))
+                        .collect::<Option<Vec<_>>>()?;
+                    Some(Polygon::new(surface.latlons(&exterior)?, holes))
+                })
+                .collect();
+            bands.push(Isoband {
+                lower: pair[0],
+                upper: pair[1],
+                polygons,
+            });
+        }
+        Ok(bands)
+    }
+}
+
+/// Grid point `(i, j)`, or centre of the cell whose lower corner is `(i, j)`
+///
+/// On grids going around the globe, column `ni` stands for column 0, so that the cells over
+/// the seam have their own points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Node {
+    Point(usize, usize),
+    Centre(usize, usize),
+}
+
+/// Vertex of a contour: a node, or the point at a level on the edge between two nodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Vertex {
+    Node(Node),
+    Cut(Node, Node, usize),
+}
+
+impl Vertex {
+    /// Point at the level of index `level` on the edge between `a` and `b`
+    fn cut(a: Node, b: Node, level: usize) -> Self {
+        match a < b {
+            true => Self::Cut(a, b, level),
+            false => Self::Cut(b, a, level),
+        }
+    }
+}
+
+/// Field seen as a surface linear on four triangles in every grid cell, meeting at the centre
+/// of the cell
+struct Surface<'a> {
+    values: &'a [f32],
+    grid: &'a dyn StructuredGrid,
+    ni: usize,
+    nj: usize,
+    /// Number of columns of points, one more than `ni` on periodic grids
+    columns: usize,
+}
+
+impl<'a> Surface<'a> {
+    fn new(field: &'a Field) -> Result<Self> {
+        let grid = field.grid.as_structured().ok_or_else(|| {
+            Error::UnsupportedData(format!(
+                "contours of fields on grids of template 3.{} are not supported",
+                field.grid.template.template_number()
+            ))
+        })?;
+        let (ni, nj) = grid.dimensions();
+        let columns = if grid.is_periodic() { ni + 1 } else { ni };
+        Ok(Self {
+            values: &field.values,
+            grid,
+            ni,
+            nj,
+            columns,
+        })
+    }
+
+    /// Triangles of the cells with no missing corner, counterclockwise on the (i, j) plane
+    fn triangles(&self) -> impl Iterator<Item = [Node; 3]> + '_ {
+        (0..self.nj.saturating_sub(1))
+            .flat_map(|j| (0..self.columns.saturating_sub(1)).map(move |i| (i, j)))
+            .filter(|&(i, j)| !self.value(Node::Centre(i, j)).is_nan())
+            .flat_map(|(i, j)| {
+                let c = Node::Centre(i, j);
+                let [p00, p10, p11, p01] = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)]
+                    .map(|(i, j)| Node::Point(i, j));
+                [[p00, p10, c], [p10, p11, c], [p11, p01, c], [p01, p00, c]]
+            })
+    }
+
+    fn value(&self, node: Node) -> f64 {
+        match node {
+            Node::Point(i, j) => {
+                let scan = self.grid.scanning_mode();
+                self.values[scan.index(i % self.ni, j, self.ni, self.nj)] as f64
+            }
+            Node::Centre(i, j) => {
+                [(i, j), (i + 1, j), (i, j + 1), (i + 1, j + 1)]
+                    .into_iter()
+                    .map(|(i, j)| self.value(Node::Point(i, j)))
+                    .sum::<f64>()
+                    / 4.0
+            }
+        }
+    }
+
+    fn vertex_value(&self, vertex: Vertex, levels: &[f64]) -> f64 {
+        match vertex {
+            Vertex::Node(node) => self.value(node),
+            Vertex::Cut(_, _, k) => levels[k],
+        }
+    }
+
+    /// Position `(i, j)` of a vertex
+    fn position(&self, vertex: Vertex, levels: &[f64]) -> (f64, f64) {
+        let node_position = |node| match node {
+            Node::Point(i, j) => (i as f64, j as f64),
+            Node::Centre(i, j) => (i as f64 + 0.5, j as f64 + 0.5),
+        };
+        match vertex {
+            Vertex::Node(node) => node_position(node),
+            Vertex::Cut(a, b, k) => {
+                let ((ia, ja), (ib, jb)) = (node_position(a), node_position(b));
+                // Nodes on either side of the seam
+                let ib = ia + self.wrap(ib - ia);
+                let (va, vb) = (self.value(a), self.value(b));
+                let t = ((levels[k] - va) / (vb - va)).clamp(0.0, 1.0);
+                (ia + (ib - ia) * t, ja + (jb - ja) * t)
+            }
+        }
+    }
+
+    /// Difference of columns taken the short way around periodic grids
+    fn wrap(&self, di: f64) -> f64 {
+        let period = self.ni as f64;
+        match self.columns > self.ni {
+            true => di - period * (di / period).round(),
+            false => di,
+        }
+    }
+
+    /// Vertex with the points of column `ni` of periodic grids moved to column 0, which they
+    /// stand for
+    fn seam(&self, vertex: Vertex) -> Vertex {
+        let node = |node| match node {
+            Node::Point(i, j) if i == self.ni => Node::Point(0, j),
+            _ => node,
+        };
+        match vertex {
+            Vertex::Node(n) => Vertex::Node(node(n)),
+            Vertex::Cut(a, b, k) => Vertex::cut(node(a), node(b), k),
+        }
+    }
+
+    /// Positions `(i, j)` of the vertices of a line, continuous over the seam of periodic
+    /// grids, with the number of times the line goes around the globe if it is a ring
+    fn positions(&self, vertices: &[Vertex], levels: &[f64]) -> (Vec<(f64, f64)>, isize) {
+        let mut positions: Vec<(f64, f64)> = Vec::with_capacity(vertices.len());
+        for &vertex in vertices {
+            let (i, j) = self.position(vertex, levels);
+            let i = match positions.last() {
+                Some(&(last, _)) => last + self.wrap(i - last),
+                None => i,
+            };
+            positions.push((i, j));
+        }
+        let winding = match (positions.first(), positions.last()) {
+            (Some(&(first, _)), Some(&(last, _))) => {
+                ((last + self.wrap(first - last) - first) / self.ni as f64).round() as isize
+            }
+            _ => 0,
+        };
+        (positions, winding)
+    }
+
+    /// Latitudes and longitudes of positions `(i, j)`, or None if one can't be located
+    fn latlons(&self, positions: &[(f64, f64)]) -> Option<Vec<(f64, f64)>> {
+        let scan = self.grid.scanning_mode();
+        positions
+            .iter()
+            .map(|&(i, j)| {
+                let (x, y) = scan.xy(i, j);
+                self.grid.xy_to_latlon(x, y)
+            })
+            .collect()
+    }
+
+    /// Indices of the pieces grouped by the edges they share, over the seam of periodic grids
+    fn components(&self, pieces: &[Vec<Vertex>]) -> Vec<Vec<usize>> {
+        fn root(parents: &mut [usize], mut p: usize) -> usize {
+            while parents[p] != p {
+                parents[p] = parents[parents[p]];
+                p = parents[p];
+            }
+            p
+        }
+        let mut parents: Vec<usize> = (0..pieces.len()).collect();
+        let mut owners: BTreeMap<(Vertex, Vertex), usize> = BTreeMap::new();
+        for (p, piece) in pieces.iter().enumerate() {
+            for (e, &a) in piece.iter().enumerate() {
+                let (a, b) = (self.seam(a), self.seam(piece[(e + 1) % piece.len()]));
+                match owners.entry((a.min(b), a.max(b))) {
+                    Entry::Vacant(entry) => {
+                        entry.insert(p);
+                    }
+                    Entry::Occupied(entry) => {
+                        let (x, y) = (root(&mut parents, *entry.get()), root(&mut parents, p));
+                        parents[x] = y;
+                    }
+                }
+            }
+        }
+        let mut components: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for p in 0..pieces.len() {
+            components.entry(root(&mut parents, p)).or_default().push(p);
+        }
+        components.into_values().collect()
+    }
+
+    /// Part of a piece where the values pass `keep`, cutting its edges at the level of index
+    /// `level` (Sutherland–Hodgman)
+    fn clip(
+        &self,
+        piece: Vec<Vertex>,
+        levels: &[f64],
+        level: usize,
+        keep: impl Fn(f64) -> bool,
+    ) -> Vec<Vertex> {
+        let mut clipped = Vec::with_capacity(piece.len() + 2);
+        for (e, &p) in piece.iter().enumerate() {
+            let q = piece[(e + 1) % piece.len()];
+            let (keep_p, keep_q) = (
+                keep(self.vertex_value(p, levels)),
+                keep(self.vertex_value(q, levels)),
+            );
+            if keep_p {
+                clipped.push(p);
+            }
+            if keep_p != keep_q
+                && let Some((a, b)) = common_edge(p, q)
+            {
+                clipped.push(Vertex::cut(a, b, level));
+            }
+        }
+        clipped.dedup();
+        if clipped.len() > 1 && clipped.first() == clipped.last() {
+            clipped.pop();
+        }
+        match clipped.len() {
+            0..3 => vec![],
+            _ => clipped,
+        }
+    }
+}
+
+/// Edge of a triangle that the segment between two vertices of a piece lies on, if any
+fn common_edge(p: Vertex, q: Vertex) -> Option<(Node, Node)> {
+    match (p, q) {
+        (Vertex::Node(a), Vertex::Node(b)) => Some((a, b)),
+        (Vertex::Node(_), Vertex::Cut(a, b, _)) | (Vertex::Cut(a, b, _), Vertex::Node(_)) => {
+            Some((a, b))
+        }
+        (Vertex::Cut(a, b, _), Vertex::Cut(c, d, _)) if (a, b) == (c, d) => Some((a, b)),
+        _ => None,
+    }
+}
+
+/// Directed edges of pieces, with `vertex` applied to their vertices, without the edges shared
+/// by two pieces
+fn boundary(pieces: &[&[Vertex]], vertex: impl Fn(Vertex) -> Vertex) -> Vec<(Vertex, Vertex)> {
+    let mut edges: BTreeSet<(Vertex, Vertex)> = BTreeSet::new();
+    for piece in pieces {
+        for (e, &p) in piece.iter().enumerate() {
+            let (p, q) = (vertex(p), vertex(piece[(e + 1) % piece.len()]));
+            if p != q && !edges.remove(&(q, p)) {
+                edges.insert((p, q));
+            }
+        }
+    }
+    edges.into_iter().collect()
+}
+
+/// Joins directed segments into lines, each with whether it is closed
+fn chain(segments: Vec<(Vertex, Vertex)>) -> Vec<(Vec<Vertex>, bool)> {
+    let mut next: BTreeMap<Vertex, Vec<Vertex>> = BTreeMap::new();
+    let mut incoming: BTreeSet<Vertex> = BTreeSet::new();
+    for (p, q) in segments {
+        next.entry(p).or_default().push(q);
+        incoming.insert(q);
+    }
+    // Open lines begin where no segment comes in; the segments left over then form rings
+    let (starts, others): (Vec<Vertex>, Vec<Vertex>) =
+        next.keys().partition(|v| !incoming.contains(v));
+
+    let follow = |start: Vertex, next: &mut BTreeMap<Vertex, Vec<Vertex>>| {
+        let mut line = vec![start];
+        let mut current = start;
+        while let Some(to) = next.get_mut(&current).and_then(|to| to.pop()) {
+            if to == start {
+                return (line, true);
+            }
+            line.push(to);
+            current = to;
+        }
+        (line, false)
+    };
+    let mut lines = vec![];
+    for start in starts.into_iter().chain(others) {
+        while next.get(&start).is_some_and(|to| !to.is_empty()) {
+            lines.push(follow(start, &mut next));
+        }
+    }
+    lines
+}
+
+/// Twice the signed area of a ring, positive if counterclockwise
+fn signed_area(ring: &[(f64, f64)]) -> f64 {
+    (0..ring.len())
+        .map(|k| {
+            let ((x1, y1), (x2, y2)) = (ring[k], ring[(k + 1) % ring.len()]);
+            x1 * y2 - x2 * y1
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::grid::global_grid;
+
+    /// Field on a global 10° grid with the value of `value(lat, lon)`
+    fn field(value: impl Fn(f64, f64) -> f64) -> Field {
+        let values = (0..36 * 19)
+            .map(|index| {
+                let (lat, lon) = (90 - 10 * (index / 36), 10 * (index % 36));
+                value(lat as f64, lon as f64) as f32
+            })
+            .collect();
+        Field::new(Arc::new(global_grid(36, 19, 10_000_000)), values).unwrap()
+    }
+
+    /// Cone of height 1 and radius 30° around 0N 0E, over the seam of the grid
+    fn cone(lat: f64, lon: f64) -> f64 {
+        let lon = (lon + 180.0).rem_euclid(360.0) - 180.0;
+        (1.0 - lat.hypot(lon) / 30.0).max(0.0)
+    }
+
+    #[test]
+    fn chains_segments() {
+        let v = |i| Vertex::Node(Node::Point(i, 0));
+        let lines = chain(vec![
+            (v(2), v(3)),
+            (v(5), v(6)),
+            (v(1), v(2)),
+            (v(6), v(4)),
+            (v(4), v(5)),
+        ]);
+        assert_eq!(
+            lines,
+            vec![
+                (vec![v(1), v(2), v(3)], false),
+                (vec![v(4), v(5), v(6)], true)
+            ]
+        );
+
+        // Rings through the same vertex
+        let lines = chain(vec![(v(0), v(1)), (v(1), v(0)), (v(0), v(2)), (v(2), v(0))]);
+        assert_eq!(lines.len(), 2);
+        assert!(
+            lines
+                .iter()
+                .all(|(line, closed)| line.len() == 2 && *closed)
+        );
+    }
+
+    #[test]
+    fn contours_cross_the_seam() {
+        let lines = field(cone).contours(&[0.5, 2.0]).unwrap();
+        assert_eq!(lines.len(), 1);
+        let line = &lines[0];
+        assert!(line.closed);
+        assert_eq!(line.level, 0.5);
+        for &(lat, lon) in &line.points {
+            // Longitudes continue over the seam instead of jumping by 360°
+            let lon = if line.points[0].1 > 180.0 {
+                lon - 360.0
+            } else {
+                lon
+            };
+            let radius = lat.hypot(lon);
+            assert!((10.0..=20.0).contains(&radius), "{lat} {lon}");
+        }
+        let coordinates = line.geojson_coordinates();
+        assert_eq!(coordinates.len(), line.points.len() + 1);
+        assert_eq!(coordinates.first(), coordinates.last());
+    }
+
+    #[test]
+    fn contours_go_around_the_globe() {
+        let lines = field(|lat, _| lat).contours(&[5.0]).unwrap();
+        assert_eq!(lines.len(), 1);
+        let line = &lines[0];
+        assert!(line.closed);
+        assert!(line.points.iter().all(|&(lat, _)| (lat - 5.0).abs() < 1e-9));
+        let (west, east) = line
+            .points
+            .iter()
+            .fold((f64::MAX, f64::MIN), |(w, e), p| (w.min(p.1), e.max(p.1)));
+        assert!(east - west >= 350.0, "{west} {east}");
+    }
+
+    #[test]
+    fn contours_stop_at_missing_values() {
+        let mut field = field(|lat, _| lat);
+        // Point at 0N 180E
+        field.values[9 * 36 + 18] = f32::NAN;
+        let lines = field.contours(&[5.0]).unwrap();
+        assert_eq!(lines.len(), 1);
+        assert!(!lines[0].closed);
+    }
+
+    #[test]
+    fn isobands_cross_the_seam() {
+        let bands = field(cone).isobands(&[0.0, 0.5, 2.0]).unwrap();
+        assert_eq!(bands.len(), 2);
+        assert_eq!((bands[1].lower, bands[1].upper), (0.5, 2.0));
+        let [polygon] = bands[1].polygons.as_slice() else {
+            panic!("{:?}", bands[1].polygons);
+        };
+        assert!(polygon.holes.is_empty());
+        for (lat, lon, inside) in [(0.0, 0.0, true), (0.0, 355.0, true), (5.0, -5.0, true)] {
+            assert_eq!(polygon.contains(lat, lon), inside, "{lat} {lon}");
+        }
+        assert!(!polygon.contains(0.0, 180.0));
+        assert!(!polygon.contains(0.0, 30.0));
+
+        // The area outside the cone goes around the globe, with the cone as a hole
+        let [outside] = bands[0].polygons.as_slice() else {
+            panic!("{:?}", bands[0].polygons);
+        };
+        assert!(outside.contains(0.0, 180.0));
+        assert!(outside.contains(60.0, 90.0));
+        assert!(!outside.contains(0.0, 0.0));
+        assert!(!outside.contains(0.0, 355.0));
+    }
+
+    #[test]
+    fn isobands_go_around_the_globe() {
+        let bands = field(|lat, _| lat).isobands(&[-25.0, 25.0]).unwrap();
+        let polygons = &bands[0].polygons;
+        assert!(!polygons.is_empty());
+        let contains = |lat, lon| polygons.iter().any(|p: &Polygon| p.contains(lat, lon));
+        for lon in [0.0, 5.0, 90.0, 180.0, 355.0] {
+            assert!(contains(0.0, lon), "{lon}");
+            assert!(contains(-20.0, lon), "{lon}");
+            assert!(!contains(30.0, lon), "{lon}");
+            assert!(!contains(-30.0, lon), "{lon}");
+        }
+        let area: f64 = polygons.iter().map(|p| p.area(1.0)).sum();
+        // Band of the sphere between 25S and 25N
+        let expected = 4.0 * std::f64::consts::PI * 25f64.to_radians().sin();
+        assert!((area - expected).abs() < 1e-9, "{area} {expected}");
+    }
+
+    #[test]
+    fn rejects_bounds_not_increasing() {
+        assert!(matches!(
+            field(cone).isobands(&[1.0, 0.5]),
+            Err(Error::InvalidData(_))
+        ));
+    }
+}
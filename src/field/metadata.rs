@@ -0,0 +1,571 @@
+use std::fmt;
+use std::io::Read;
+
+use super::{Parameter, parameter};
+use crate::message::{IdentificationSectionHeader, ProductDefinitionSectionHeader};
+use crate::templates::{
+    ProductDefinitionTemplate4_0, ProductDefinitionTemplate4_1, ProductDefinitionTemplate4_8,
+    ProductDefinitionTemplate4_11, TimeInterval,
+};
+use crate::{Error, Result};
+
+/// Date and time in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl DateTime {
+    /// Seconds since 1970-01-01T00:00:00Z
+    pub fn unix_seconds(&self) -> i64 {
+        // Days from the civil calendar (Hinnant), with years starting in March
+        let (month, day) = (self.month as i64, self.day as i64);
+        let year = self.year as i64 - i64::from(month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146_097 + day_of_era - 719_468;
+        days * 86_400 + self.hour as i64 * 3_600 + self.minute as i64 * 60 + self.second as i64
+    }
+
+    /// Date and time `seconds` after 1970-01-01T00:00:00Z
+    pub fn from_unix_seconds(seconds: i64) -> Self {
+        let (days, time) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days - era * 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        Self {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (time / 3_600) as u8,
+            minute: (time / 60 % 60) as u8,
+            second: (time % 60) as u8,
+        }
+    }
+
+    /// Date and time `seconds` later
+    pub fn add_seconds(&self, seconds: i64) -> Self {
+        Self::from_unix_seconds(self.unix_seconds() + seconds)
+    }
+}
+
+impl fmt::Display for DateTime {
+    /// ISO 8601, e.g. `2024-06-01T12:00:00Z`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// Fixed surface (Code Table 4.5) with its value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedSurface {
+    pub surface_type: u8,
+    /// Value in the units of the surface type, None if the surface has no value (e.g. the
+    /// ground)
+    pub value: Option<f64>,
+}
+
+impl FixedSurface {
+    /// Surface from the fields of a product definition template, None if it is missing
+    pub fn new(surface_type: u8, scale_factor: i8, scaled_value: u32) -> Option<Self> {
+        if surface_type == 255 {
+            return None;
+        }
+        let value = match (scale_factor, scaled_value) {
+            (-127, _) | (_, u32::MAX) => None,
+            (factor, value) => Some(value as f64 * 10f64.powi(-(factor as i32))),
+        };
+        Some(Self {
+            surface_type,
+            value,
+        })
+    }
+
+    /// Abbreviation of the surface type used by NCEP and GDAL, e.g. `ISBL` for isobaric
+    /// surfaces
+    pub fn abbreviation(&self) -> Option<&'static str> {
+        Some(match self.surface_type {
+            1 => "SFC",
+            2 => "CBL",
+            3 => "CTL",
+            4 => "0DEG",
+            6 => "MWSL",
+            7 => "TRO",
+            8 => "NTAT",
+            10 => "EATM",
+            100 => "ISBL",
+            101 => "MSL",
+            102 => "GPML",
+            103 => "HTGL",
+            104 => "SIGL",
+            105 => "HYBL",
+            106 => "DBLL",
+            107 => "THEL",
+            108 => "SPDL",
+            160 => "DBSL",
+            200 => "EATM",
+            _ => return None,
+        })
+    }
+
+    /// Units of the value, in UDUNITS notation
+    pub fn units(&self) -> Option<&'static str> {
+        Some(match self.surface_type {
+            20 | 107 => "K",
+            100 | 108 => "Pa",
+            102 | 103 | 106 | 160 => "m",
+            104 | 105 | 111 => "1",
+            _ => return None,
+        })
+    }
+
+    /// CF standard name of the vertical coordinate of the surface type
+    pub fn standard_name(&self) -> Option<&'static str> {
+        Some(match self.surface_type {
+            100 => "air_pressure",
+            102 => "altitude",
+            103 => "height",
+            104 => "atmosphere_sigma_coordinate",
+            106 | 160 => "depth",
+            107 => "air_potential_temperature",
+            _ => return None,
+        })
+    }
+}
+
+/// Statistical processing of a field over a time range, e.g. a 6-hour accumulation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatisticalProcess {
+    /// Code Table 4.10
+    pub process: u8,
+    /// Length of the time range in units of `indicator_of_unit_of_time_range`
+    pub length: u32,
+    /// Code Table 4.4
+    pub indicator_of_unit_of_time_range: u8,
+}
+
+impl StatisticalProcess {
+    /// Abbreviation of the process used by NCEP, e.g. `acc` for accumulations
+    pub fn abbreviation(&self) -> Option<&'static str> {
+        Some(match self.process {
+            0 => "avg",
+            1 => "acc",
+            2 => "max",
+            3 => "min",
+            4 => "diff",
+            6 => "stddev",
+            _ => return None,
+        })
+    }
+
+    /// Length of the time range with the abbreviation of its unit, e.g. `6h`
+    pub fn length_name(&self) -> String {
+        let unit = match self.indicator_of_unit_of_time_range {
+            0 => "min",
+            1 => "h",
+            2 => "d",
+            3 => "mo",
+            4 => "y",
+            13 => "s",
+            code => return format!("{}u{}", self.length, code),
+        };
+        format!("{}{}", self.length, unit)
+    }
+}
+
+/// Product information of a field, from sections 0, 1 and 4
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldMetadata {
+    /// Discipline (Code Table 0.0)
+    pub discipline: u8,
+    pub parameter_category: u8,
+    pub parameter_number: u8,
+    pub product_definition_template_number: u16,
+    pub reference_time: DateTime,
+    /// Forecast time in units of `indicator_of_unit_of_time_range` (Code Table 4.4)
+    pub forecast_time: i32,
+    pub indicator_of_unit_of_time_range: u8,
+    /// End of the overall time interval of statistically processed fields
+    pub end_of_interval: Option<DateTime>,
+    /// Processing over the outermost time range of statistically processed fields
+    pub statistical_process: Option<StatisticalProcess>,
+    pub first_fixed_surface: Option<FixedSurface>,
+    pub second_fixed_surface: Option<FixedSurface>,
+}
+
+impl FieldMetadata {
+    pub fn new(
+        discipline: u8,
+        identification: &IdentificationSectionHeader,
+        product_definition_template_number: u16,
+        template: &ProductDefinitionTemplate4_0,
+    ) -> Self {
+        Self {
+            discipline,
+            parameter_category: template.parameter_category,
+            parameter_number: template.parameter_number,
+            product_definition_template_number,
+            reference_time: DateTime {
+                year: identification.year,
+                month: identification.month,
+                day: identification.day,
+                hour: identification.hour,
+                minute: identification.minute,
+                second: identification.second,
+            },
+            forecast_time: template.forecast_time,
+            indicator_of_unit_of_time_range: template.indicator_of_unit_of_time_range,
+            end_of_interval: None,
+            statistical_process: None,
+            first_fixed_surface: FixedSurface::new(
+                template.type_of_first_fixed_surface,
+                template.scale_factor_of_first_fixed_surface,
+                template.scaled_value_of_first_fixed_surface,
+            ),
+            second_fixed_surface: FixedSurface::new(
+                template.type_of_second_fixed_surface,
+                template.scale_factor_of_second_fixed_surface,
+                template.scaled_value_of_second_fixed_surface,
+            ),
+        }
+    }
+
+    /// Metadata of a field read from the template of its product definition section, right
+    /// after the section header.
+    ///
+    /// `discipline` comes from the indicator section. Templates 4.0 and 4.1 and their
+    /// statistically processed variants 4.8 and 4.11 are supported.
+    pub fn read<R: Read>(
+        discipline: u8,
+        identification: &IdentificationSectionHeader,
+        product: &ProductDefinitionSectionHeader,
+        reader: &mut R,
+    ) -> Result<Self> {
+        let number = product.template_number;
+        Ok(match number {
+            0 => {
+                let template = ProductDefinitionTemplate4_0::read(reader)?;
+                Self::new(discipline, identification, number, &template)
+            }
+            1 => {
+                let template = ProductDefinitionTemplate4_1::read(reader)?;
+                Self::new(discipline, identification, number, &template.template_0)
+            }
+            8 => {
+                let template = ProductDefinitionTemplate4_8::read(reader)?;
+                Self::new(discipline, identification, number, &template.template_0)
+                    .with_interval(&template.interval)
+            }
+            11 => {
+                let template = ProductDefinitionTemplate4_11::read(reader)?;
+                Self::new(
+                    discipline,
+                    identification,
+                    number,
+                    &template.template_1.template_0,
+                )
+                .with_interval(&template.interval)
+            }
+            _ => {
+                return Err(Error::UnsupportedData(format!(
+                    "metadata of product definition template 4.{} is not supported",
+                    number
+                )));
+            }
+        })
+    }
+
+    /// Sets the end and the processing of the time interval of a statistically processed field
+    /// (templates 4.8, 4.11 and the like).
+    pub fn with_interval(mut self, interval: &TimeInterval) -> Self {
+        self.statistical_process = interval
+            .time_ranges
+            .first()
+            .map(|range| StatisticalProcess {
+                process: range.statistical_process,
+                length: range.length_of_the_time_range,
+                indicator_of_unit_of_time_range: range.indicator_of_unit_of_length_of_time_range,
+            });
+        self.end_of_interval = Some(DateTime {
+            year: interval.year,
+            month: interval.month,
+            day: interval.day,
+            hour: interval.hour,
+            minute: interval.minute,
+            second: interval.second,
+        });
+        self
+    }
+
+    /// Forecast time in seconds, None for units of varying length (months and longer)
+    pub fn forecast_seconds(&self) -> Option<i64> {
+        let unit: i64 = match self.indicator_of_unit_of_time_range {
+            0 => 60,
+            1 => 3_600,
+            2 => 86_400,
+            10 => 3 * 3_600,
+            11 => 6 * 3_600,
+            12 => 12 * 3_600,
+            13 => 1,
+            _ => return None,
+        };
+        Some(self.forecast_time as i64 * unit)
+    }
+
+    /// Time the values are valid at: the end of the time interval for statistically processed
+    /// fields, the reference time plus the forecast time otherwise
+    pub fn valid_time(&self) -> Option<DateTime> {
+        self.end_of_interval.or_else(|| {
+            self.forecast_seconds()
+                .map(|seconds| self.reference_time.add_seconds(seconds))
+        })
+    }
+
+    /// Entry of the parameter in the built-in parameter table
+    pub fn parameter(&self) -> Option<&'static Parameter> {
+        parameter(
+            self.discipline,
+            self.parameter_category,
+            self.parameter_number,
+        )
+    }
+}
+
+/// GRIB2 message of a field on a grid of `number_of_points` points, with the given product
+/// definition template, a reference time of 2024-06-01T12:00:00Z and no data
+#[cfg(test)]
+pub(crate) fn grib_message(
+    discipline: u8,
+    number_of_points: u32,
+    template_number: u16,
+    template: &[u8],
+) -> Vec<u8> {
+    let section = |number: u8, body: &[u8]| {
+        let mut section = ((body.len() + 5) as u32).to_be_bytes().to_vec();
+        section.push(number);
+        section.extend(body);
+        section
+    };
+    let points = number_of_points.to_be_bytes();
+    let mut sections = [
+        section(1, &[0, 7, 0, 0, 2, 1, 1, 0x07, 0xe8, 6, 1, 12, 0, 0, 0, 1]),
+        section(3, &[&[0][..], &points, &[0, 0, 0, 0]].concat()),
+        section(
+            4,
+            &[&[0, 0][..], &template_number.to_be_bytes(), template].concat(),
+        ),
+        section(5, &[&points[..], &[0, 0]].concat()),
+        section(6, &[255]),
+        section(7, &[]),
+        b"7777".to_vec(),
+    ]
+    .concat();
+    let total_length = (16 + sections.len()) as u64;
+    let mut message = b"GRIB\0\0".to_vec();
+    message.extend([discipline, 2]);
+    message.extend(total_length.to_be_bytes());
+    message.append(&mut sections);
+    message
+}
+
+/// Template 4.0 of a forecast `hours` ahead on a surface `(type, value)`, or 4.8 of an
+/// accumulation over the `accumulation` hours ending then
+#[cfg(test)]
+pub(crate) fn product_definition_template(
+    (category, number): (u8, u8),
+    hours: i32,
+    (surface_type, value): (u8, u32),
+    accumulation: Option<u32>,
+) -> Vec<u8> {
+    let start = hours - accumulation.unwrap_or(0) as i32;
+    let mut template = vec![category, number, 2, 0, 96, 0, 0, 0, 1];
+    template.extend(start.to_be_bytes());
+    template.extend([surface_type, 0]);
+    template.extend(value.to_be_bytes());
+    template.extend([255, 0, 0, 0, 0, 0]);
+    if let Some(length) = accumulation {
+        let end = DateTime {
+            year: 2024,
+            month: 6,
+            day: 1,
+            hour: 12,
+            minute: 0,
+            second: 0,
+        }
+        .add_seconds(hours as i64 * 3_600);
+        template.extend(end.year.to_be_bytes());
+        template.extend([end.month, end.day, end.hour, end.minute, end.second, 1]);
+        template.extend([0, 0, 0, 0, 1, 2, 1]);
+        template.extend(length.to_be_bytes());
+        template.extend([1, 0, 0, 0, 0]);
+    }
+    template
+}
+
+/// Metadata of the fields of a GRIB2 message
+#[cfg(test)]
+pub(crate) fn read_metadata(message: &[u8]) -> Result<Vec<FieldMetadata>> {
+    use crate::message::IndicatorSectionHeader;
+    use crate::reader::MessageReader;
+
+    #[derive(Default)]
+    struct Reader {
+        discipline: u8,
+        identification: Option<IdentificationSectionHeader>,
+        fields: Vec<FieldMetadata>,
+    }
+
+    impl<R: Read> MessageReader<R> for Reader {
+        fn handle_indicator(&mut self, is: IndicatorSectionHeader) -> Result<()> {
+            self.discipline = is.discipline;
+            Ok(())
+        }
+
+        fn handle_identification(
+            &mut self,
+            ids: IdentificationSectionHeader,
+            _reader: &mut std::io::Take<&mut R>,
+        ) -> Result<()> {
+            self.identification = Some(ids);
+            Ok(())
+        }
+
+        fn handle_product_definition(
+            &mut self,
+            pds: ProductDefinitionSectionHeader,
+            reader: &mut std::io::Take<&mut R>,
+        ) -> Result<()> {
+            let identification = self.identification.as_ref().ok_or_else(|| {
+                Error::InvalidData("section 4 comes before section 1".to_string())
+            })?;
+            let metadata = FieldMetadata::read(self.discipline, identification, &pds, reader)?;
+            self.fields.push(metadata);
+            Ok(())
+        }
+    }
+
+    let mut reader = Reader::default();
+    reader.read_next_message(&mut &message[..])?;
+    Ok(reader.fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(year: u16, month: u8, day: u8, hour: u8) -> DateTime {
+        DateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute: 0,
+            second: 0,
+        }
+    }
+
+    #[test]
+    fn dates_and_times() {
+        assert_eq!(time(1970, 1, 1, 0).unix_seconds(), 0);
+        assert_eq!(time(2000, 3, 1, 0).unix_seconds(), 951_868_800);
+        assert_eq!(
+            time(2024, 2, 29, 18).add_seconds(6 * 3_600),
+            time(2024, 3, 1, 0)
+        );
+        assert_eq!(time(1969, 12, 31, 23).unix_seconds(), -3_600);
+        for seconds in [-86_400_i64, 0, 951_868_800, 1_717_243_200, 4_102_444_800] {
+            assert_eq!(DateTime::from_unix_seconds(seconds).unix_seconds(), seconds);
+        }
+        assert_eq!(time(2024, 6, 1, 12).to_string(), "2024-06-01T12:00:00Z");
+    }
+
+    #[test]
+    fn fixed_surfaces() {
+        let surface = FixedSurface::new(100, 0, 50_000).unwrap();
+        assert_eq!(surface.value, Some(50_000.0));
+        assert_eq!(surface.abbreviation(), Some("ISBL"));
+        assert_eq!(FixedSurface::new(106, 1, 1).unwrap().value, Some(0.1));
+        assert_eq!(FixedSurface::new(1, -127, u32::MAX).unwrap().value, None);
+        assert_eq!(FixedSurface::new(255, 0, 0), None);
+    }
+
+    #[test]
+    fn reads_forecasts() {
+        let template = product_definition_template((0, 0), 6, (103, 2), None);
+        let message = grib_message(0, 4, 0, &template);
+        let [metadata] = read_metadata(&message).unwrap().try_into().unwrap();
+        assert_eq!(
+            metadata,
+            FieldMetadata {
+                discipline: 0,
+                parameter_category: 0,
+                parameter_number: 0,
+                product_definition_template_number: 0,
+                reference_time: time(2024, 6, 1, 12),
+                forecast_time: 6,
+                indicator_of_unit_of_time_range: 1,
+                end_of_interval: None,
+                statistical_process: None,
+                first_fixed_surface: Some(FixedSurface {
+                    surface_type: 103,
+                    value: Some(2.0),
+                }),
+                second_fixed_surface: None,
+            }
+        );
+        assert_eq!(metadata.forecast_seconds(), Some(21_600));
+        assert_eq!(metadata.valid_time(), Some(time(2024, 6, 1, 18)));
+        assert_eq!(metadata.parameter().unwrap().short_name, "TMP");
+    }
+
+    #[test]
+    fn reads_statistically_processed_fields() {
+        let template = product_definition_template((1, 8), 18, (1, 0), Some(6));
+        let message = grib_message(0, 4, 8, &template);
+        let [metadata] = read_metadata(&message).unwrap().try_into().unwrap();
+        assert_eq!(metadata.product_definition_template_number, 8);
+        assert_eq!(metadata.forecast_time, 12);
+        assert_eq!(metadata.end_of_interval, Some(time(2024, 6, 2, 6)));
+        assert_eq!(metadata.valid_time(), Some(time(2024, 6, 2, 6)));
+        let process = metadata.statistical_process.unwrap();
+        assert_eq!(
+            process,
+            StatisticalProcess {
+                process: 1,
+                length: 6,
+                indicator_of_unit_of_time_range: 1,
+            }
+        );
+        assert_eq!(process.abbreviation(), Some("acc"));
+        assert_eq!(process.length_name(), "6h");
+        assert_eq!(metadata.parameter().unwrap().short_name, "APCP");
+    }
+
+    #[test]
+    fn rejects_unsupported_templates() {
+        let template = product_definition_template((0, 0), 6, (103, 2), None);
+        let message = grib_message(0, 4, 40, &template);
+        assert!(matches!(
+            read_metadata(&message),
+            Err(Error::UnsupportedData(_))
+        ));
+    }
+}
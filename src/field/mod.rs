@@ -0,0 +1,65 @@
+//! Decoded values together with the grid they are on
+
+mod contour;
+mod metadata;
+mod parameter;
+mod regrid;
+mod sample;
+mod tile;
+mod zonal;
+
+pub use contour::*;
+pub use metadata::*;
+pub use parameter::*;
+pub use regrid::*;
+pub use sample::*;
+pub use tile::*;
+pub use zonal::*;
+
+use std::sync::Arc;
+
+use crate::grid::{GridDefinition, GridGeometry};
+use crate::{Error, Result};
+
+/// Values of a field in data order, with missing values as NAN, and the grid they are on
+///
+/// The grid is shared, as all the fields of a file are usually on the same few grids.
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub grid: Arc<GridDefinition>,
+    pub values: Vec<f32>,
+    /// Parameter, level and times of the values, if known
+    pub metadata: Option<FieldMetadata>,
+}
+
+impl Field {
+    pub fn new(grid: Arc<GridDefinition>, values: Vec<f32>) -> Result<Self> {
+        if values.len() != grid.header.number_of_data_points as usize {
+            return Err(Error::InvalidData(format!(
+                "{} values were given, but the grid has {} data points",
+                values.len(),
+                grid.header.number_of_data_points
+            )));
+        }
+        Ok(Self {
+            grid,
+            values,
+            metadata: None,
+        })
+    }
+
+    pub fn with_metadata(mut self, metadata: FieldMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Geometry of the grid, or an error if its points can't be located
+    pub fn geometry(&self) -> Result<&dyn GridGeometry> {
+        self.grid.as_geometry().ok_or_else(|| {
+            Error::UnsupportedData(format!(
+                "points of grids of template 3.{} can't be located",
+                self.grid.template.template_number()
+            ))
+        })
+    }
+}
@@ -0,0 +1,77 @@
+/// Parameter of Code Table 4.2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parameter {
+    pub discipline: u8,
+    pub category: u8,
+    pub number: u8,
+    /// Abbreviation used by NCEP and GDAL, e.g. `TMP`
+    pub short_name: &'static str,
+    pub name: &'static str,
+    /// Units in UDUNITS notation
+    pub units: &'static str,
+    /// CF standard name, if there is one
+    pub standard_name: Option<&'static str>,
+}
+
+macro_rules! parameters {
+    ($(($discipline:expr, $category:expr, $number:expr, $short_name:expr, $name:expr, $units:expr, $standard_name:expr)),* $(,)?) => {
+        &[$(Parameter {
+            discipline: $discipline,
+            category: $category,
+            number: $number,
+            short_name: $short_name,
+            name: $name,
+            units: $units,
+            standard_name: $standard_name,
+        }),*]
+    };
+}
+
+/// Common parameters of the WMO tables
+#[rustfmt::skip]
+const PARAMETERS: &[Parameter] = parameters![
+    (0, 0, 0, "TMP", "Temperature", "K", Some("air_temperature")),
+    (0, 0, 2, "POT", "Potential temperature", "K", Some("air_potential_temperature")),
+    (0, 0, 4, "TMAX", "Maximum temperature", "K", Some("air_temperature")),
+    (0, 0, 5, "TMIN", "Minimum temperature", "K", Some("air_temperature")),
+    (0, 0, 6, "DPT", "Dew point temperature", "K", Some("dew_point_temperature")),
+    (0, 1, 0, "SPFH", "Specific humidity", "kg kg-1", Some("specific_humidity")),
+    (0, 1, 1, "RH", "Relative humidity", "%", Some("relative_humidity")),
+    (0, 1, 3, "PWAT", "Precipitable water", "kg m-2", Some("atmosphere_mass_content_of_water_vapor")),
+    (0, 1, 7, "PRATE", "Precipitation rate", "kg m-2 s-1", Some("precipitation_flux")),
+    (0, 1, 8, "APCP", "Total precipitation", "kg m-2", Some("precipitation_amount")),
+    (0, 1, 11, "SNOD", "Snow depth", "m", Some("surface_snow_thickness")),
+    (0, 1, 13, "WEASD", "Water equivalent of accumulated snow depth", "kg m-2", Some("surface_snow_amount")),
+    (0, 1, 22, "CLMR", "Cloud mixing ratio", "kg kg-1", None),
+    (0, 1, 52, "TPRATE", "Total precipitation rate", "kg m-2 s-1", Some("precipitation_flux")),
+    (0, 2, 0, "WDIR", "Wind direction", "degree", Some("wind_from_direction")),
+    (0, 2, 1, "WIND", "Wind speed", "m s-1", Some("wind_speed")),
+    (0, 2, 2, "UGRD", "u-component of wind", "m s-1", Some("eastward_wind")),
+    (0, 2, 3, "VGRD", "v-component of wind", "m s-1", Some("northward_wind")),
+    (0, 2, 8, "VVEL", "Vertical velocity (pressure)", "Pa s-1", Some("lagrangian_tendency_of_air_pressure")),
+    (0, 2, 9, "DZDT", "Vertical velocity (geometric)", "m s-1", Some("upward_air_velocity")),
+    (0, 2, 10, "ABSV", "Absolute vorticity", "s-1", Some("atmosphere_absolute_vorticity")),
+    (0, 2, 22, "GUST", "Wind speed (gust)", "m s-1", Some("wind_speed_of_gust")),
+    (0, 3, 0, "PRES", "Pressure", "Pa", Some("air_pressure")),
+    (0, 3, 1, "PRMSL", "Pressure reduced to MSL", "Pa", Some("air_pressure_at_mean_sea_level")),
+    (0, 3, 4, "GP", "Geopotential", "m2 s-2", Some("geopotential")),
+    (0, 3, 5, "HGT", "Geopotential height", "m", Some("geopotential_height")),
+    (0, 4, 7, "DSWRF", "Downward short-wave radiation flux", "W m-2", Some("downwelling_shortwave_flux_in_air")),
+    (0, 5, 3, "DLWRF", "Downward long-wave radiation flux", "W m-2", Some("downwelling_longwave_flux_in_air")),
+    (0, 6, 1, "TCDC", "Total cloud cover", "%", Some("cloud_area_fraction")),
+    (0, 6, 3, "LCDC", "Low cloud cover", "%", Some("low_type_cloud_area_fraction")),
+    (0, 6, 4, "MCDC", "Medium cloud cover", "%", Some("medium_type_cloud_area_fraction")),
+    (0, 6, 5, "HCDC", "High cloud cover", "%", Some("high_type_cloud_area_fraction")),
+    (0, 7, 6, "CAPE", "Convective available potential energy", "J kg-1", Some("atmosphere_convective_available_potential_energy")),
+    (0, 7, 7, "CIN", "Convective inhibition", "J kg-1", Some("atmosphere_convective_inhibition")),
+    (0, 19, 0, "VIS", "Visibility", "m", Some("visibility_in_air")),
+    (2, 0, 0, "LAND", "Land cover (1 = land, 0 = sea)", "1", Some("land_binary_mask")),
+    (10, 3, 0, "WTMP", "Water temperature", "K", Some("sea_water_temperature")),
+];
+
+/// Entry of a parameter in the built-in table of common parameters
+pub fn parameter(discipline: u8, category: u8, number: u8) -> Option<&'static Parameter> {
+    PARAMETERS
+        .iter()
+        .find(|p| (p.discipline, p.category, p.number) == (discipline, category, number))
+}
@@ -0,0 +1,357 @@
+use std::sync::Arc;
+
+use super::Field;
+use crate::grid::{
+    GridDefinition, StructuredGrid, cell_ring, index_range, intersection_area,
+    periodic_index_range, unwrap_ring,
+};
+use crate::{Error, Result};
+
+/// How the values of a target point are computed from the source grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegridMethod {
+    /// Value of the source point whose cell contains the target point
+    Nearest,
+    /// Bilinear interpolation in the source cell enclosing the target point
+    Bilinear,
+    /// First-order conservative remapping: average of the source cells overlapping the target
+    /// cell, weighted by the overlapping area
+    ///
+    /// Cells extend halfway to the neighbouring points, with edges taken as straight lines on
+    /// an equal-area cylindrical projection, which is exact for latitude/longitude cells. The
+    /// area-weighted integral of a field is kept wherever the target cells are covered by the
+    /// source grid.
+    Conservative,
+}
+
+/// Interpolation weights from one grid to another, computed once and applied to any number of
+/// fields on the source grid
+///
+/// Missing values are handled as follows:
+/// - nearest and bilinear: the target value is NAN if a source point it depends on is missing
+/// - conservative: missing source cells are left out and the other weights rescaled, so the
+///   target value is NAN only if all the overlapping cells are missing
+///
+/// Target points outside the source grid are NAN.
+#[derive(Debug, Clone)]
+pub struct Regridder {
+    source: Arc<GridDefinition>,
+    target: Arc<GridDefinition>,
+    method: RegridMethod,
+    /// Start of the weights of every target point in `weights`, followed by their end
+    offsets: Vec<usize>,
+    /// Pairs of source index and weight
+    weights: Vec<(usize, f64)>,
+}
+
+impl Regridder {
+    /// Computes the weights from `source` to `target`.
+    ///
+    /// Bilinear interpolation needs a source grid with a regular layout (structured or
+    /// quasi-regular), and conservative remapping needs structured grids. On source
+    /// grids without a regular layout, points are located by checking the whole grid, which is
+    /// slow for large grids.
+    pub fn new(
+        source: Arc<GridDefinition>,
+        target: Arc<GridDefinition>,
+        method: RegridMethod,
+    ) -> Result<Self> {
+        let regular = source.as_structured().is_some() || source.quasi_regular().is_some();
+        let supported = match method {
+            RegridMethod::Nearest => source.as_geometry().is_some(),
+            RegridMethod::Bilinear => regular,
+            RegridMethod::Conservative => source.as_structured().is_some(),
+        };
+        if !supported {
+            return Err(Error::UnsupportedData(format!(
+                "{:?} regridding from grids of template 3.{} is not supported",
+                method,
+                source.template.template_number()
+            )));
+        }
+        let target_geometry = target.as_geometry().ok_or_else(|| {
+            Error::UnsupportedData(format!(
+                "points of grids of template 3.{} can't be located",
+                target.template.template_number()
+            ))
+        })?;
+
+        let n = target_geometry.number_of_points();
+        let mut offsets = Vec::with_capacity(n + 1);
+        let mut weights = Vec::with_capacity(n);
+        match method {
+            RegridMethod::Nearest | RegridMethod::Bilinear => {
+                for index in 0..n {
+                    offsets.push(weights.len());
+                    let Some((lat, lon)) = target_geometry.latlon(index) else {
+                        continue;
+                    };
+                    if method == RegridMethod::Nearest {
+                        weights.extend(source.nearest_point(lat, lon).map(|p| (p, 1.0)));
+                    } else if let Some(points) = source.surrounding_points(lat, lon) {
+                        weights.extend(points.into_iter().filter(|&(_, w)| w != 0.0));
+                    }
+                }
+            }
+            RegridMethod::Conservative => {
+                let source = source.as_structured().unwrap();
+                let grid = target.as_structured().ok_or_else(|| {
+                    Error::UnsupportedData(format!(
+                        "conservative regridding to grids of template 3.{} is not supported",
+                        target.template.template_number()
+                    ))
+                })?;
+                for index in 0..n {
+                    offsets.push(weights.len());
+                    weights.extend(cell_overlaps(source, grid, index));
+                }
+            }
+        }
+        offsets.push(weights.len());
+
+        Ok(Self {
+            source,
+            target,
+            method,
+            offsets,
+            weights,
+        })
+    }
+
+    pub fn source(&self) -> &Arc<GridDefinition> {
+        &self.source
+    }
+
+    pub fn target(&self) -> &Arc<GridDefinition> {
+        &self.target
+    }
+
+    pub fn method(&self) -> RegridMethod {
+        self.method
+    }
+
+    /// Source points and weights of the target point at `index`; empty if it is outside the
+    /// source grid
+    pub fn weights(&self, index: usize) -> &[(usize, f64)] {
+        &self.weights[self.offsets[index]..self.offsets[index + 1]]
+    }
+
+    /// Values on the target grid of a field on the source grid
+    pub fn apply(&self, field: &Field) -> Result<Field> {
+        let expected = self.source.header.number_of_data_points as usize;
+        if field.values.len() != expected {
+            return Err(Error::InvalidData(format!(
+                "the field has {} values, but the source grid has {} data points",
+                field.values.len(),
+                expected
+            )));
+        }
+        let values = self
+            .offsets
+            .windows(2)
+            .map(|range| {
+                let weights = &self.weights[range[0]..range[1]];
+                match self.method {
+                    RegridMethod::Conservative => {
+                        let (sum, total) = weights
+                            .iter()
+                            .map(|&(p, w)| (field.values[p] as f64, w))
+                            .filter(|(v, _)| !v.is_nan())
+                            .fold((0.0, 0.0), |(sum, total), (v, w)| (sum + v * w, total + w));
+                        match total > 0.0 {
+                            true => (sum / total) as f32,
+                            false => f32::NAN,
+                        }
+                    }
+                    _ if weights.is_empty() => f32::NAN,
+                    _ => weights
+                        .iter()
+                        .map(|&(p, w)| field.values[p] as f64 * w)
+                        .sum::<f64>() as f32,
+                }
+            })
+            .collect();
+        Ok(Field {
+            metadata: field.metadata.clone(),
+            ..Field::new(self.target.clone(), values)?
+        })
+    }
+}
+
+impl Field {
+    /// Values of the field on another grid
+    ///
+    /// To regrid several fields on the same grid, build a [`Regridder`] once instead.
+    pub fn regrid(&self, target: Arc<GridDefinition>, method: RegridMethod) -> Result<Field> {
+        Regridder::new(self.grid.clone(), target, method)?.apply(self)
+    }
+}
+
+/// Source points whose cells overlap the target cell at `index`, with the fraction of the
+/// covered part of the target cell they take
+fn cell_overlaps(
+    source: &dyn StructuredGrid,
+    target: &dyn StructuredGrid,
+    index: usize,
+) -> Vec<(usize, f64)> {
+    let (x, y) = target.xy_of_index(index);
+    let Some(cell) = cell_ring(target, x, y) else {
+        return vec![];
+    };
+    let cell = unwrap_ring(cell, None);
+    let reference = cell[0].1;
+
+    // Source points whose cells may hold a corner of the target cell, and those between them
+    let (ni, nj) = source.dimensions();
+    let scan = source.scanning_mode();
+    let (is, js): (Vec<f64>, Vec<f64>) = cell
+        .iter()
+        .filter_map(|&(lat, lon)| source.latlon_to_xy(lat, lon))
+        .map(|(x, y)| scan.ij_from_xy(x, y))
+        .filter(|(i, j)| i.is_finite() && j.is_finite())
+        .flat_map(|(i, j)| [(i - 0.5, j - 0.5), (i + 0.5, j + 0.5)])
+        .unzip();
+    let Some((j0, j1)) = index_range(&js, nj) else {
+        return vec![];
+    };
+    let Some((i0, i1)) = (match source.is_periodic() {
+        true => periodic_index_range(&is, ni),
+        false => index_range(&is, ni),
+    }) else {
+        return vec![];
+    };
+
+    let mut overlaps: Vec<(usize, f64)> = vec![];
+    for j in j0..=j1 {
+        for i in (i0..=i1).map(|i| i % ni) {
+            let (sx, sy) = scan.xy(i as f64, j as f64);
+            let Some(source_cell) = cell_ring(source, sx, sy) else {
+                continue;
+            };
+            let source_cell = unwrap_ring(source_cell, Some(reference));
+            let area = intersection_area(&source_cell, &cell, 1.0);
+            if area > 0.0 {
+                overlaps.push((scan.index(i, j, ni, nj), area));
+            }
+        }
+    }
+    let total: f64 = overlaps.iter().map(|(_, w)| w).sum();
+    if total <= 0.0 {
+        return vec![];
+    }
+    overlaps.iter_mut().for_each(|(_, w)| *w /= total);
+    overlaps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::{global_grid, predefined_grid, unstructured_grid};
+    use crate::xorshift;
+
+    /// Pseudo-random values in 0..1
+    /// `n` pseudo-random values in [0, 1)
+    fn random_values(n: usize) -> Vec<f32> {
+        xorshift(0x9e37_79b9_7f4a_7c15)
+            .take(n)
+            .map(|state| (state >> 40) as f32 / (1u32 << 24) as f32)
+            .collect()
+    }
+
+    /// Area-weighted integral of a field over its grid
+    fn integral(field: &Field) -> f64 {
+        field
+            .values
+            .iter()
+            .enumerate()
+            .map(|(index, &v)| v as f64 * field.grid.cell_area(index).unwrap())
+            .sum()
+    }
+
+    #[test]
+    fn conservative_regridding_keeps_the_integral() {
+        // 2° grid to a 5° grid
+        let field = Field::new(
+            Arc::new(global_grid(180, 91, 2_000_000)),
+            random_values(180 * 91),
+        )
+        .unwrap();
+        let target = Arc::new(global_grid(72, 37, 5_000_000));
+        let regridder =
+            Regridder::new(field.grid.clone(), target, RegridMethod::Conservative).unwrap();
+        let regridded = regridder.apply(&field).unwrap();
+
+        let (before, after) = (integral(&field), integral(&regridded));
+        assert!(
+            ((after - before) / before).abs() < 1e-9,
+            "{} {}",
+            before,
+            after
+        );
+
+        // The cell at 45N 10E spans 42.5N to 47.5N and 7.5E to 12.5E: whole 2° cells in the
+        // middle and parts of the cells around them, 6.25 cells in all
+        let weights = regridder.weights(9 * 72 + 2);
+        assert_eq!(weights.len(), 12);
+        assert!((weights.iter().map(|w| w.1).sum::<f64>() - 1.0).abs() < 1e-12);
+        // The cell from 43N to 45N and 9E to 11E
+        let sin = |lat: f64| lat.to_radians().sin();
+        let expected = (sin(45.0) - sin(43.0)) / (sin(47.5) - sin(42.5)) * 2.0 / 5.0;
+        let whole = weights.iter().find(|w| w.0 == 23 * 180 + 5).unwrap();
+        assert!((whole.1 - expected).abs() < 1e-12, "{}", whole.1);
+        // The cell at 0E takes cells on both sides of the last column
+        let weights = regridder.weights(9 * 72);
+        assert!(weights.iter().any(|w| w.0 == 23 * 180 + 179));
+    }
+
+    #[test]
+    fn conservative_regridding_to_a_finer_grid() {
+        let field = Field::new(
+            Arc::new(global_grid(72, 37, 5_000_000)),
+            random_values(72 * 37),
+        )
+        .unwrap();
+        let regridded = field
+            .regrid(
+                Arc::new(global_grid(360, 181, 1_000_000)),
+                RegridMethod::Conservative,
+            )
+            .unwrap();
+        let (before, after) = (integral(&field), integral(&regridded));
+        assert!(
+            ((after - before) / before).abs() < 1e-9,
+            "{} {}",
+            before,
+            after
+        );
+        // A 1° cell on a 5° point takes its value alone
+        assert_eq!(regridded.values[45 * 360 + 10], field.values[9 * 72 + 2]);
+    }
+
+    #[test]
+    fn point_methods() {
+        let values: Vec<f32> = (0..360 * 181).map(|v| v as f32).collect();
+        let field = Field::new(Arc::new(predefined_grid(3)), values).unwrap();
+        let target = Arc::new(predefined_grid(4));
+        let nearest = field.regrid(target.clone(), RegridMethod::Nearest).unwrap();
+        assert_eq!(nearest.values[90 * 720 + 20], (45 * 360 + 10) as f32);
+        let bilinear = field.regrid(target, RegridMethod::Bilinear).unwrap();
+        let expected = (45 * 360 + 10) as f32 + 0.5;
+        assert_eq!(bilinear.values[90 * 720 + 21], expected);
+    }
+
+    #[test]
+    fn conservative_regridding_needs_structured_grids() {
+        let source = Arc::new(unstructured_grid(vec![0.0, 1.0], vec![0.0, 1.0]));
+        let target = Arc::new(predefined_grid(3));
+        assert!(matches!(
+            Regridder::new(source.clone(), target.clone(), RegridMethod::Conservative),
+            Err(Error::UnsupportedData(_))
+        ));
+        assert!(matches!(
+            Regridder::new(target, source.clone(), RegridMethod::Conservative),
+            Err(Error::UnsupportedData(_))
+        ));
+        assert!(Regridder::new(source.clone(), source, RegridMethod::Nearest).is_ok());
+    }
+}
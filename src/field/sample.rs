@@ -0,0 +1,210 @@
+use super::Field;
+use crate::grid::great_circle_distance;
+
+/// Number of neighbours used by inverse distance weighting, and by nearest-neighbour lookup on
+/// grids without a regular layout
+const NEIGHBOURS: usize = 4;
+
+/// How a value is computed from the grid points around a location
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    /// Value of the closest grid point
+    Nearest,
+    /// Bilinear interpolation in the enclosing grid cell
+    Bilinear,
+    /// Average of the neighbouring points weighted by the inverse of their distance to the
+    /// power `power`
+    InverseDistance { power: f64 },
+}
+
+/// Grid point used to compute a [`Sample`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Neighbour {
+    /// Index of the point in data order
+    pub index: usize,
+    pub lat: f64,
+    pub lon: f64,
+    /// Great-circle distance in metres from the sampled location
+    pub distance: f64,
+    pub value: f32,
+    /// Weight of the point in the sampled value
+    pub weight: f64,
+}
+
+/// Value of a field at a location, with the grid points it was computed from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    /// Sampled value, NAN if a point it depends on is missing
+    pub value: f32,
+    /// Points around the location, closest first
+    pub neighbours: Vec<Neighbour>,
+}
+
+impl Field {
+    /// Value of the field at the latitude and longitude in degrees
+    ///
+    /// Works on every grid whose points can be located. Bilinear interpolation needs a
+    /// structured or quasi-regular grid; on other grids, the neighbours are the closest points
+    /// (see [`nearest_points`](crate::grid::GridDefinition::nearest_points)). The nearest value
+    /// is that of the point [`nearest_point`](crate::grid::GridDefinition::nearest_point) finds,
+    /// so it is also given in the border half-cells of structured grids. None if the location
+    /// is outside the grid or the method isn't available for it.
+    pub fn value_at(&self, lat: f64, lon: f64, method: Interpolation) -> Option<Sample> {
+        let geometry = self.geometry().ok()?;
+        let radius = self.grid.earth_radius();
+        let nearest = match method {
+            Interpolation::Nearest => Some(self.grid.nearest_point(lat, lon)?),
+            _ => None,
+        };
+        let has_cells = self.grid.as_structured().is_some() || self.grid.quasi_regular().is_some();
+        let mut candidates = match (self.grid.surrounding_points(lat, lon), method) {
+            (Some(points), _) => points,
+            (None, Interpolation::Bilinear) => return None,
+            // Border half-cells only have their nearest point
+            (None, Interpolation::Nearest) if has_cells => vec![],
+            (None, _) => self
+                .grid
+                .nearest_points(lat, lon, NEIGHBOURS)
+                .into_iter()
+                .map(|(index, _)| (index, 0.0))
+                .collect(),
+        };
+        if let Some(index) = nearest {
+            candidates.push((index, 0.0));
+        }
+
+        let mut neighbours: Vec<Neighbour> = Vec::with_capacity(candidates.len());
+        for (index, weight) in candidates {
+            // The same point may surround the location from two sides
+            if let Some(n) = neighbours.iter_mut().find(|n| n.index == index) {
+                n.weight += weight;
+                continue;
+            }
+            let (plat, plon) = geometry.latlon(index)?;
+            neighbours.push(Neighbour {
+                index,
+                lat: plat,
+                lon: plon,
+                distance: great_circle_distance(lat, lon, plat, plon, radius),
+                value: self.values[index],
+                weight,
+            });
+        }
+        neighbours.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        if neighbours.is_empty() {
+            return None;
+        }
+
+        match method {
+            Interpolation::Nearest => {
+                for n in neighbours.iter_mut() {
+                    n.weight = if Some(n.index) == nearest { 1.0 } else { 0.0 };
+                }
+            }
+            Interpolation::Bilinear => {}
+            Interpolation::InverseDistance { power } => {
+                let exact = neighbours[0].distance < 1e-6;
+                let weights: Vec<f64> = neighbours
+                    .iter()
+                    .enumerate()
+                    .map(|(k, n)| match exact {
+                        true if k == 0 => 1.0,
+                        true => 0.0,
+                        false => n.distance.powf(-power),
+                    })
+                    .collect();
+                let total: f64 = weights.iter().sum();
+                for (n, w) in neighbours.iter_mut().zip(weights) {
+                    n.weight = w / total;
+                }
+            }
+        }
+        let value = neighbours
+            .iter()
+            .filter(|n| n.weight != 0.0)
+            .map(|n| n.value as f64 * n.weight)
+            .sum::<f64>() as f32;
+        Some(Sample { value, neighbours })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::grid::{predefined_grid, unstructured_grid};
+
+    fn field(grid: crate::grid::GridDefinition) -> Field {
+        let n = grid.header.number_of_data_points as usize;
+        Field::new(Arc::new(grid), (0..n).map(|v| v as f32).collect()).unwrap()
+    }
+
+    #[test]
+    fn interpolates_inside_cells() {
+        let field = field(predefined_grid(3));
+        let sample = field
+            .value_at(45.25, 10.75, Interpolation::Bilinear)
+            .unwrap();
+        let expected = (44.0 * 360.0 + 10.75) * 0.25 + (45.0 * 360.0 + 10.75) * 0.75;
+        assert!((sample.value as f64 - expected).abs() < 1e-2);
+        assert_eq!(sample.neighbours.len(), 4);
+        assert!((sample.neighbours.iter().map(|n| n.weight).sum::<f64>() - 1.0).abs() < 1e-12);
+
+        let sample = field
+            .value_at(45.25, 10.75, Interpolation::Nearest)
+            .unwrap();
+        assert_eq!(sample.value, (45 * 360 + 11) as f32);
+        assert_eq!(sample.neighbours[0].index, 45 * 360 + 11);
+
+        let sample = field
+            .value_at(45.0, 11.0, Interpolation::InverseDistance { power: 2.0 })
+            .unwrap();
+        assert_eq!(sample.value, (45 * 360 + 11) as f32);
+    }
+
+    #[test]
+    fn nearest_values_agree_with_nearest_points() {
+        let field = field(predefined_grid(211));
+        let structured = field.grid.as_structured().unwrap();
+        for (x, y) in [
+            (-0.3, 10.0),
+            (3.2, 64.4),
+            (92.4, 0.1),
+            (40.6, 30.3),
+            (92.45, -0.45),
+        ] {
+            let (lat, lon) = structured.xy_to_latlon(x, y).unwrap();
+            let index = field.grid.nearest_point(lat, lon).unwrap();
+            let sample = field.value_at(lat, lon, Interpolation::Nearest).unwrap();
+            assert_eq!(sample.value, index as f32, "({}, {})", x, y);
+            let chosen: Vec<_> = sample
+                .neighbours
+                .iter()
+                .filter(|n| n.weight == 1.0)
+                .collect();
+            assert_eq!(chosen.len(), 1);
+            assert_eq!(chosen[0].index, index);
+        }
+        let (lat, lon) = structured.xy_to_latlon(-0.7, 10.0).unwrap();
+        assert_eq!(field.value_at(lat, lon, Interpolation::Nearest), None);
+        assert_eq!(field.value_at(lat, lon, Interpolation::Bilinear), None);
+    }
+
+    #[test]
+    fn samples_unstructured_grids() {
+        let field = field(unstructured_grid(
+            vec![0.0, 0.0, 10.0, 10.0, 50.0],
+            vec![0.0, 10.0, 0.0, 10.0, 50.0],
+        ));
+        let sample = field.value_at(1.0, 9.0, Interpolation::Nearest).unwrap();
+        assert_eq!(sample.value, 1.0);
+        assert_eq!(sample.neighbours.len(), 4);
+        assert_eq!(field.value_at(1.0, 9.0, Interpolation::Bilinear), None);
+        let sample = field
+            .value_at(5.0, 5.0, Interpolation::InverseDistance { power: 1.0 })
+            .unwrap();
+        assert!(sample.neighbours.iter().all(|n| n.index != 4));
+        assert!((sample.value - 1.5).abs() < 1e-2, "{}", sample.value);
+    }
+}
@@ -0,0 +1,215 @@
+use std::cmp::Ordering;
+use std::f64::consts::PI;
+
+use super::{Field, Interpolation};
+use crate::grid::BoundingBox;
+use crate::{Error, Result};
+
+/// Width and height in pixels of a tile
+pub const TILE_SIZE: usize = 256;
+
+/// Highest zoom level accepted
+const MAX_ZOOM: u8 = 30;
+
+/// Raster tile of the XYZ scheme in Web Mercator (EPSG:3857)
+#[derive(Debug, Clone)]
+pub struct Tile {
+    pub z: u8,
+    pub x: u32,
+    pub y: u32,
+    /// [`TILE_SIZE`]² values in rows from the north-west corner, NAN where the field has no
+    /// value
+    pub values: Vec<f32>,
+}
+
+impl Tile {
+    /// Latitude and longitude in degrees of the centre of the pixel at column `px` and row
+    /// `py` of the tile `z/x/y`
+    pub fn pixel_latlon(z: u8, x: u32, y: u32, px: usize, py: usize) -> (f64, f64) {
+        let n = (1u64 << z) as f64;
+        let size = TILE_SIZE as f64;
+        let lon = (x as f64 + (px as f64 + 0.5) / size) / n * 360.0 - 180.0;
+        let my = (y as f64 + (py as f64 + 0.5) / size) / n;
+        let lat = (PI * (1.0 - 2.0 * my)).sinh().atan().to_degrees();
+        (lat, lon)
+    }
+
+    /// Area covered by the tile `z/x/y`
+    pub fn bounds(z: u8, x: u32, y: u32) -> BoundingBox {
+        let n = (1u64 << z) as f64;
+        let lat = |y: f64| (PI * (1.0 - 2.0 * y / n)).sinh().atan().to_degrees();
+        let lon = |x: f64| x / n * 360.0 - 180.0;
+        BoundingBox::new(
+            lat(y as f64 + 1.0),
+            lat(y as f64),
+            lon(x as f64),
+            lon(x as f64 + 1.0),
+        )
+    }
+
+    /// Colours of the pixels as RGBA bytes, in the same order as the values
+    pub fn to_rgba(&self, colormap: &ColorMap) -> Vec<u8> {
+        self.values
+            .iter()
+            .flat_map(|&value| colormap.color(value))
+            .collect()
+    }
+}
+
+impl Field {
+    /// Renders the field on the XYZ tile `z/x/y`, sampling the field at the centre of every
+    /// pixel.
+    pub fn render_tile(&self, z: u8, x: u32, y: u32, method: Interpolation) -> Result<Tile> {
+        if z > MAX_ZOOM || x as u64 >= 1 << z || y as u64 >= 1 << z {
+            return Err(Error::InvalidData(format!(
+                "tile {}/{}/{} doesn't exist",
+                z, x, y
+            )));
+        }
+        self.geometry()?;
+        let values = (0..TILE_SIZE)
+            .flat_map(|py| (0..TILE_SIZE).map(move |px| (px, py)))
+            .map(|(px, py)| {
+                let (lat, lon) = Tile::pixel_latlon(z, x, y, px, py);
+                self.value_at(lat, lon, method)
+                    .map_or(f32::NAN, |sample| sample.value)
+            })
+            .collect();
+        Ok(Tile { z, x, y, values })
+    }
+}
+
+/// Colours of values, interpolated linearly between stops
+#[derive(Debug, Clone)]
+pub struct ColorMap {
+    stops: Vec<(f32, [u8; 4])>,
+    /// Colour of missing values
+    missing: [u8; 4],
+}
+
+impl ColorMap {
+    /// Colour map from stops of values and RGBA colours, in increasing order of value
+    ///
+    /// Values beyond the first and last stops take their colours, and missing values are
+    /// transparent.
+    pub fn new(stops: Vec<(f32, [u8; 4])>) -> Result<Self> {
+        if stops.is_empty() {
+            return Err(Error::InvalidData(
+                "a colour map needs at least one stop".to_string(),
+            ));
+        }
+        if stops
+            .windows(2)
+            .any(|w| w[0].0.partial_cmp(&w[1].0) != Some(Ordering::Less))
+        {
+            return Err(Error::InvalidData(
+                "stops of a colour map must be in increasing order of value".to_string(),
+            ));
+        }
+        Ok(Self {
+            stops,
+            missing: [0, 0, 0, 0],
+        })
+    }
+
+    /// Opaque black to white between `min` and `max`
+    pub fn grayscale(min: f32, max: f32) -> Result<Self> {
+        Self::new(vec![(min, [0, 0, 0, 255]), (max, [255, 255, 255, 255])])
+    }
+
+    /// Sets the colour of missing values.
+    pub fn with_missing(mut self, color: [u8; 4]) -> Self {
+        self.missing = color;
+        self
+    }
+
+    pub fn color(&self, value: f32) -> [u8; 4] {
+        if value.is_nan() {
+            return self.missing;
+        }
+        let upper = self.stops.partition_point(|&(v, _)| v <= value);
+        let (v1, c1) = match upper {
+            0 => return self.stops[0].1,
+            n if n == self.stops.len() => return self.stops[n - 1].1,
+            n => self.stops[n],
+        };
+        let (v0, c0) = self.stops[upper - 1];
+        let t = (value - v0) / (v1 - v0);
+        std::array::from_fn(|k| (c0[k] as f32 + (c1[k] as f32 - c0[k] as f32) * t).round() as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::grid::predefined_grid;
+
+    /// Latitude of the Web Mercator square's northern edge
+    const MAX_LAT: f64 = 85.051_128_779_806_59;
+
+    #[test]
+    fn tile_geometry() {
+        let bounds = Tile::bounds(0, 0, 0);
+        assert!((bounds.north - MAX_LAT).abs() < 1e-12 && (bounds.south + MAX_LAT).abs() < 1e-12);
+        assert_eq!((bounds.west, bounds.east), (-180.0, 180.0));
+        let bounds = Tile::bounds(1, 1, 0);
+        assert!(bounds.south.abs() < 1e-12 && (bounds.north - MAX_LAT).abs() < 1e-12);
+        assert_eq!((bounds.west, bounds.east), (0.0, 180.0));
+
+        let (lat, lon) = Tile::pixel_latlon(0, 0, 0, 128, 128);
+        assert_eq!(lon, 0.703_125);
+        assert!(lat < 0.0 && lat > -0.71);
+        // Pixels of a tile are inside its bounds
+        let bounds = Tile::bounds(5, 17, 11);
+        for (px, py) in [(0, 0), (255, 255), (0, 255)] {
+            let (lat, lon) = Tile::pixel_latlon(5, 17, 11, px, py);
+            assert!(bounds.contains(lat, lon), "{:?}", (lat, lon));
+        }
+    }
+
+    #[test]
+    fn renders_tiles() {
+        let grid = Arc::new(predefined_grid(3));
+        let values = (0..360 * 181).map(|v| (v / 360) as f32).collect();
+        let field = Field::new(grid, values).unwrap();
+        let tile = field.render_tile(2, 1, 1, Interpolation::Nearest).unwrap();
+        assert_eq!(tile.values.len(), TILE_SIZE * TILE_SIZE);
+        for (px, py) in [(0, 0), (100, 200), (255, 255)] {
+            let (lat, lon) = Tile::pixel_latlon(2, 1, 1, px, py);
+            let expected = field.value_at(lat, lon, Interpolation::Nearest).unwrap();
+            assert_eq!(tile.values[py * TILE_SIZE + px], expected.value);
+            assert_eq!(expected.value, (90.0 - lat).round() as f32);
+        }
+        // Rows go from north to south
+        assert!(tile.values[0] < tile.values[255 * TILE_SIZE]);
+
+        assert!(field.render_tile(2, 4, 0, Interpolation::Nearest).is_err());
+        assert!(field.render_tile(31, 0, 0, Interpolation::Nearest).is_err());
+    }
+
+    #[test]
+    fn colour_maps() {
+        let colormap = ColorMap::grayscale(0.0, 10.0)
+            .unwrap()
+            .with_missing([255, 0, 0, 128]);
+        assert_eq!(colormap.color(-5.0), [0, 0, 0, 255]);
+        assert_eq!(colormap.color(5.0), [128, 128, 128, 255]);
+        assert_eq!(colormap.color(10.0), [255, 255, 255, 255]);
+        assert_eq!(colormap.color(20.0), [255, 255, 255, 255]);
+        assert_eq!(colormap.color(f32::NAN), [255, 0, 0, 128]);
+
+        let tile = Tile {
+            z: 0,
+            x: 0,
+            y: 0,
+            values: vec![0.0, f32::NAN],
+        };
+        assert_eq!(tile.to_rgba(&colormap), [0, 0, 0, 255, 255, 0, 0, 128]);
+
+        assert!(ColorMap::new(vec![]).is_err());
+        assert!(ColorMap::new(vec![(1.0, [0; 4]), (1.0, [0; 4])]).is_err());
+        assert!(ColorMap::new(vec![(f32::NAN, [0; 4]), (1.0, [0; 4])]).is_err());
+    }
+}
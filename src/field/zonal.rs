@@ -0,0 +1,293 @@
+use super::Field;
+use crate::grid::{GridWindow, Polygon, cell_ring};
+use crate::{Error, Result};
+
+/// Weight given to each grid cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AreaWeighting {
+    /// Cosine of the latitude of the point, proportional to the cell area on lat/lon grids
+    CosLatitude,
+    /// Area of the cell computed from the grid definition; needs a structured grid
+    CellArea,
+}
+
+/// Statistics of the values of a field over an area
+///
+/// Missing values, whether from the bitmap or level 0 of run length packing, are left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZonalStatistics {
+    /// Area-weighted mean, NAN if no cell with a value overlaps the area
+    pub mean: f64,
+    pub min: f32,
+    pub max: f32,
+    /// Sum of the values, each counted in proportion to the part of its cell inside the area
+    pub sum: f64,
+    /// Fraction of the area on the grid that is covered by cells with a value
+    pub coverage: f64,
+    /// Number of cells with a value overlapping the area
+    pub count: usize,
+}
+
+/// Running sums of [`ZonalStatistics`]
+struct Accumulator {
+    weighted_sum: f64,
+    valid_weight: f64,
+    total_weight: f64,
+    sum: f64,
+    min: f32,
+    max: f32,
+    count: usize,
+}
+
+impl Accumulator {
+    fn new() -> Self {
+        Self {
+            weighted_sum: 0.0,
+            valid_weight: 0.0,
+            total_weight: 0.0,
+            sum: 0.0,
+            min: f32::NAN,
+            max: f32::NAN,
+            count: 0,
+        }
+    }
+
+    /// Adds a cell of value `value` and weight `weight`, of which `fraction` is in the area.
+    fn add(&mut self, value: f32, weight: f64, fraction: f64) {
+        self.total_weight += weight * fraction;
+        if value.is_nan() || fraction <= 0.0 {
+            return;
+        }
+        self.weighted_sum += value as f64 * weight * fraction;
+        self.valid_weight += weight * fraction;
+        self.sum += value as f64 * fraction;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.count += 1;
+    }
+
+    fn finish(self) -> ZonalStatistics {
+        let ratio = |a: f64, b: f64| if b > 0.0 { a / b } else { f64::NAN };
+        ZonalStatistics {
+            mean: ratio(self.weighted_sum, self.valid_weight),
+            min: self.min,
+            max: self.max,
+            sum: self.sum,
+            coverage: ratio(self.valid_weight, self.total_weight).max(0.0),
+            count: self.count,
+        }
+    }
+}
+
+impl Field {
+    /// Statistics over the whole grid
+    pub fn statistics(&self, weighting: AreaWeighting) -> Result<ZonalStatistics> {
+        let weight = self.cell_weights(weighting)?;
+        let mut acc = Accumulator::new();
+        for (index, &value) in self.values.iter().enumerate() {
+            if let Some(weight) = weight(index) {
+                acc.add(value, weight, 1.0);
+            }
+        }
+        Ok(acc.finish())
+    }
+
+    /// Statistics over every polygon
+    ///
+    /// On structured grids, cells extend halfway to the neighbouring points and count in
+    /// proportion to the area of them inside the polygon. The areas are exact for edges that
+    /// are straight lines on an equal-area cylindrical projection, as are meridians and
+    /// parallels; other edges are taken as such lines between their vertices. A polygon without
+    /// area takes the cell containing it. On other grids, points count fully if they are inside
+    /// the polygon.
+    pub fn zonal_statistics(
+        &self,
+        polygons: &[Polygon],
+        weighting: AreaWeighting,
+    ) -> Result<Vec<ZonalStatistics>> {
+        let weight = self.cell_weights(weighting)?;
+        let geometry = self.geometry()?;
+        Ok(polygons
+            .iter()
+            .map(|polygon| {
+                let mut acc = Accumulator::new();
+                let Some(grid) = self.grid.as_structured() else {
+                    for (index, &value) in self.values.iter().enumerate() {
+                        let inside = geometry
+                            .latlon(index)
+                            .is_some_and(|(lat, lon)| polygon.contains(lat, lon));
+                        if let (true, Some(weight)) = (inside, weight(index)) {
+                            acc.add(value, weight, 1.0);
+                        }
+                    }
+                    return acc.finish();
+                };
+
+                let window = GridWindow::covering(grid, &polygon.bounding_box());
+                for index in window
+                    .iter()
+                    .flat_map(|w| (0..w.ni * w.nj).map(|k| w.source_index(k)))
+                {
+                    let (x, y) = grid.xy_of_index(index);
+                    let Some(cell) = cell_ring(grid, x, y) else {
+                        continue;
+                    };
+                    let fraction = polygon.fraction_of(&cell);
+                    if let (true, Some(weight)) = (fraction > 0.0, weight(index)) {
+                        acc.add(self.values[index], weight, fraction);
+                    }
+                }
+                if acc.total_weight == 0.0 {
+                    let n = polygon.exterior.len() as f64;
+                    let lat = polygon.exterior.iter().map(|p| p.0).sum::<f64>() / n;
+                    let lon = polygon.exterior.iter().map(|p| p.1).sum::<f64>() / n;
+                    let nearest = self.grid.nearest_point(lat, lon);
+                    if let Some((index, weight)) = nearest.and_then(|i| Some((i, weight(i)?))) {
+                        acc.add(self.values[index], weight, 1.0);
+                    }
+                }
+                acc.finish()
+            })
+            .collect())
+    }
+
+    /// Weight of the cell of every point, None for points that can't be located
+    fn cell_weights(&self, weighting: AreaWeighting) -> Result<impl Fn(usize) -> Option<f64> + '_> {
+        let geometry = self.geometry()?;
+        if weighting == AreaWeighting::CellArea && self.grid.as_structured().is_none() {
+            return Err(Error::UnsupportedData(format!(
+                "cell areas of grids of template 3.{} are not supported",
+                self.grid.template.template_number()
+            )));
+        }
+        Ok(move |index| match weighting {
+            AreaWeighting::CosLatitude => geometry
+                .latlon(index)
+                .map(|(lat, _)| lat.to_radians().cos().max(0.0)),
+            AreaWeighting::CellArea => self.grid.cell_area(index),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::grid::{global_grid, unstructured_grid};
+
+    /// Field on a global 10° grid whose value at `lat`, `lon` is `lat * 1000 + lon`
+    fn field() -> Field {
+        let values = (0..36 * 19)
+            .map(|index| {
+                let (lat, lon) = (90 - 10 * (index / 36), 10 * (index % 36));
+                (lat * 1000 + lon) as f32
+            })
+            .collect();
+        Field::new(Arc::new(global_grid(36, 19, 10_000_000)), values).unwrap()
+    }
+
+    fn rectangle(south: f64, north: f64, west: f64, east: f64) -> Vec<(f64, f64)> {
+        vec![(south, west), (south, east), (north, east), (north, west)]
+    }
+
+    fn zonal(field: &Field, polygon: Polygon) -> ZonalStatistics {
+        field
+            .zonal_statistics(&[polygon], AreaWeighting::CellArea)
+            .unwrap()
+            .remove(0)
+    }
+
+    #[test]
+    fn whole_grid_statistics() {
+        let mut field = field();
+        let stats = field.statistics(AreaWeighting::CellArea).unwrap();
+        assert_eq!(stats.count, 36 * 19);
+        assert_eq!((stats.min, stats.max), (-90_000.0, 90_350.0));
+        assert_eq!(stats.coverage, 1.0);
+        // Latitudes cancel out, leaving the mean longitude
+        assert!((stats.mean - 175.0).abs() < 1e-6, "{}", stats.mean);
+
+        field.values[0] = f32::NAN;
+        let stats = field.statistics(AreaWeighting::CosLatitude).unwrap();
+        assert_eq!(stats.count, 36 * 19 - 1);
+        assert_eq!(stats.coverage, 1.0);
+        field.values[18 * 36] = f32::NAN;
+        let stats = field.statistics(AreaWeighting::CellArea).unwrap();
+        assert!(stats.coverage < 1.0 && stats.coverage > 0.99);
+    }
+
+    #[test]
+    fn cells_count_by_the_area_inside_polygons() {
+        let field = field();
+        // Six whole cells, at 0N and 10N and 0E to 20E
+        let stats = zonal(
+            &field,
+            Polygon::new(rectangle(-5.0, 15.0, -5.0, 25.0), vec![]),
+        );
+        assert_eq!(stats.count, 6);
+        assert_eq!(stats.sum, 3.0 * 10_000.0 + 2.0 * 30.0);
+        assert_eq!((stats.min, stats.max), (0.0, 10_020.0));
+
+        // Halves of the cells at 0E and 20E
+        let stats = zonal(
+            &field,
+            Polygon::new(rectangle(-5.0, 5.0, 0.0, 20.0), vec![]),
+        );
+        assert_eq!(stats.count, 3);
+        assert!((stats.sum - (0.5 * 0.0 + 10.0 + 0.5 * 20.0)).abs() < 1e-9);
+        assert!((stats.mean - 10.0).abs() < 1e-9);
+
+        // A hole over the cell at 10N 10E
+        let hole = rectangle(5.0, 15.0, 5.0, 15.0);
+        let stats = zonal(
+            &field,
+            Polygon::new(rectangle(-5.0, 15.0, -5.0, 25.0), vec![hole]),
+        );
+        assert_eq!(stats.count, 5);
+        assert!((stats.sum - (3.0 * 10_000.0 + 2.0 * 30.0 - 10_010.0)).abs() < 1e-9);
+
+        // The cells at 350E and 0E, across the prime meridian
+        let stats = zonal(
+            &field,
+            Polygon::new(rectangle(-5.0, 5.0, 345.0, 5.0), vec![]),
+        );
+        assert_eq!(stats.count, 2);
+        assert!((stats.sum - 350.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn small_polygons_take_their_cell() {
+        let field = field();
+        let stats = zonal(
+            &field,
+            Polygon::new(rectangle(19.0, 21.0, 29.0, 31.0), vec![]),
+        );
+        assert_eq!(stats.count, 1);
+        assert_eq!(stats.mean, 20_030.0);
+        assert!(stats.sum > 0.0 && stats.sum < 20_030.0 * 0.05);
+        // Without area
+        let stats = zonal(
+            &field,
+            Polygon::new(vec![(20.0, 30.0), (20.0, 30.0)], vec![]),
+        );
+        assert_eq!((stats.count, stats.mean), (1, 20_030.0));
+    }
+
+    #[test]
+    fn points_of_unstructured_grids_count_whole() {
+        let grid = unstructured_grid(vec![0.0, 1.0, 5.0], vec![0.0, 1.0, 5.0]);
+        let field = Field::new(Arc::new(grid), vec![1.0, 2.0, 3.0]).unwrap();
+        let stats = field
+            .zonal_statistics(
+                &[Polygon::new(rectangle(-0.5, 1.5, -0.5, 1.5), vec![])],
+                AreaWeighting::CosLatitude,
+            )
+            .unwrap();
+        assert_eq!((stats[0].count, stats[0].sum), (2, 3.0));
+        assert!(matches!(
+            field.statistics(AreaWeighting::CellArea),
+            Err(Error::UnsupportedData(_))
+        ));
+    }
+}
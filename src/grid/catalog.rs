@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+#[cfg(test)]
+use super::GridDefinition;
+use crate::message::GridDefinitionSectionHeader;
+use crate::templates::{
+    GridDefinitionTemplate, GridDefinitionTemplate3_0, GridDefinitionTemplate3_30,
+};
+
+/// Predefined grids, looked up by grid number
+///
+/// Sections with source of grid definition 1 (Code Table 3.0) send no template: the grid is one
+/// of the originating centre's predefined grids. WMO sets the template number of such sections
+/// to 65535 and leaves the grid number to the conventions of the centre, so the catalog finds it
+/// with a key function of the section header. The default key, [`template_number_key`], reads
+/// the number from the template number field as some centres do; [`GridCatalog::with_key`]
+/// supplies another convention.
+#[derive(Debug, Clone)]
+pub struct GridCatalog {
+    grids: HashMap<u16, GridDefinitionTemplate>,
+    key: fn(&GridDefinitionSectionHeader) -> Option<u16>,
+}
+
+impl Default for GridCatalog {
+    fn default() -> Self {
+        Self {
+            grids: HashMap::new(),
+            key: template_number_key,
+        }
+    }
+}
+
+/// Grid number carried in the template number field of section 3, None when it is missing
+/// (65535)
+pub fn template_number_key(header: &GridDefinitionSectionHeader) -> Option<u16> {
+    match header.template_number {
+        u16::MAX => None,
+        number => Some(number),
+    }
+}
+
+impl GridCatalog {
+    /// Empty catalog
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Finds the number of predefined grids in section 3 headers with `key` rather than
+    /// [`template_number_key`].
+    pub fn with_key(mut self, key: fn(&GridDefinitionSectionHeader) -> Option<u16>) -> Self {
+        self.key = key;
+        self
+    }
+
+    /// Number of the predefined grid of a section 3 header, as given by the key of the catalog
+    pub fn grid_number(&self, header: &GridDefinitionSectionHeader) -> Option<u16> {
+        (self.key)(header)
+    }
+
+    /// Catalog of common NCEP predefined grids (3, 4, 211, 212, 218 and 221)
+    pub fn ncep() -> Self {
+        let mut catalog = Self::new();
+        catalog.register(3, lat_lon(360, 181, 1_000_000));
+        catalog.register(4, lat_lon(720, 361, 500_000));
+        // Lambert conformal grids over the CONUS, sharing their first point
+        for (number, n_x, n_y, d) in [
+            (211, 93, 65, 81_270_500),
+            (212, 185, 129, 40_635_250),
+            (218, 614, 428, 12_190_580),
+        ] {
+            catalog.register(
+                number,
+                lambert(
+                    n_x,
+                    n_y,
+                    (12_190_000, 226_541_000),
+                    d,
+                    265_000_000,
+                    25_000_000,
+                ),
+            );
+        }
+        // North American regional reanalysis
+        catalog.register(
+            221,
+            lambert(
+                349,
+                277,
+                (1_000_000, 214_500_000),
+                32_463_410,
+                253_000_000,
+                50_000_000,
+            ),
+        );
+        catalog
+    }
+
+    /// The built-in catalog used when reading section 3, shared by all readers
+    pub fn builtin() -> &'static Self {
+        static BUILTIN: OnceLock<GridCatalog> = OnceLock::new();
+        BUILTIN.get_or_init(Self::ncep)
+    }
+
+    /// Adds a grid, replacing any previous grid with the same number.
+    pub fn register(&mut self, number: u16, template: GridDefinitionTemplate) {
+        self.grids.insert(number, template);
+    }
+
+    pub fn get(&self, number: u16) -> Option<&GridDefinitionTemplate> {
+        self.grids.get(&number)
+    }
+}
+
+/// Grid definition of the predefined grid `number` of the built-in catalog, as read from a
+/// section 3
+#[cfg(test)]
+pub(crate) fn predefined_grid(number: u16) -> GridDefinition {
+    grid_definition(GridCatalog::builtin().get(number).unwrap().clone())
+}
+
+/// Grid definition of a global latitude/longitude grid from 90N and 0E, with the increment in
+/// microdegrees
+#[cfg(test)]
+pub(crate) fn global_grid(n_i: u32, n_j: u32, increment: u32) -> GridDefinition {
+    grid_definition(lat_lon(n_i, n_j, increment))
+}
+
+#[cfg(test)]
+fn grid_definition(template: GridDefinitionTemplate) -> GridDefinition {
+    let header = GridDefinitionSectionHeader {
+        section_length: 14,
+        source_of_grid_definition: 1,
+        number_of_data_points: template.as_geometry().unwrap().number_of_points() as u32,
+        number_of_octects_for_number_of_points: 0,
+        interpretation_of_number_of_points: 0,
+        template_number: 0,
+    };
+    let mut catalog = GridCatalog::new();
+    catalog.register(0, template);
+    GridDefinition::read_with_catalog(header, &mut &[][..], &catalog).unwrap()
+}
+
+/// Global latitude/longitude grid from 90N and 0E, on a sphere of radius 6371229 m
+fn lat_lon(n_i: u32, n_j: u32, increment: u32) -> GridDefinitionTemplate {
+    GridDefinitionTemplate::Template3_0(GridDefinitionTemplate3_0 {
+        shape_of_earth: 6,
+        scale_factor_of_radius: 0,
+        scale_value_of_radius: 0,
+        scale_factor_of_major_axis: 0,
+        scale_value_of_major_axis: 0,
+        scale_factor_of_minor_axis: 0,
+        scale_value_of_minor_axis: 0,
+        n_i,
+        n_j,
+        basic_angle: 0,
+        subdivisions_of_basic_angle: u32::MAX,
+        la1: 90_000_000,
+        lo1: 0,
+        resolution_and_component_flags: 0x30,
+        la2: -90_000_000,
+        lo2: ((n_i - 1) * increment) as i32,
+        d_i: increment,
+        d_j: increment,
+        scanning_mode: 0,
+    })
+}
+
+/// Tangent Lambert conformal grid scanning northward, on a sphere of radius 6371229 m
+///
+/// Angles are in microdegrees and the grid length in millimetres.
+fn lambert(
+    n_x: u32,
+    n_y: u32,
+    (la1, lo1): (i32, i32),
+    d: u32,
+    lov: i32,
+    latin: i32,
+) -> GridDefinitionTemplate {
+    GridDefinitionTemplate::Template3_30(GridDefinitionTemplate3_30 {
+        shape_of_earth: 6,
+        scale_factor_of_radius: 0,
+        scale_value_of_radius: 0,
+        scale_factor_of_major_axis: 0,
+        scale_value_of_major_axis: 0,
+        scale_factor_of_minor_axis: 0,
+        scale_value_of_minor_axis: 0,
+        n_x,
+        n_y,
+        la1,
+        lo1,
+        resolution_and_component_flags: 0x08,
+        lad: latin,
+        lov,
+        d_x: d,
+        d_y: d,
+        projection_centre_flag: 0,
+        scanning_mode: 0x40,
+        latin1: latin,
+        latin2: latin,
+        latitude_of_southern_pole: -90_000_000,
+        longitude_of_southern_pole: 0,
+    })
+}
@@ -0,0 +1,444 @@
+//! Coordinate reference systems of the grids as PROJ strings and OGC WKT2 (2019)
+
+use super::{EarthShape, MICRO};
+use crate::templates::{GridDefinitionTemplate, GridDefinitionTemplate3_0};
+
+const DEGREE: &str = r#"ANGLEUNIT["degree",0.0174532925199433]"#;
+const METRE: &str = r#"LENGTHUNIT["metre",1]"#;
+
+/// Builds the [`EarthShape`] of a template from its shape of the earth fields.
+macro_rules! earth_shape {
+    ($tmpl:expr) => {
+        EarthShape::from_shape_of_earth(
+            $tmpl.shape_of_earth,
+            $tmpl.scale_factor_of_radius,
+            $tmpl.scale_value_of_radius,
+            $tmpl.scale_factor_of_major_axis,
+            $tmpl.scale_value_of_major_axis,
+            $tmpl.scale_factor_of_minor_axis,
+            $tmpl.scale_value_of_minor_axis,
+        )
+    };
+}
+
+/// Map projection of a grid, with angles in degrees and lengths in metres
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    LatLon,
+    /// Rotated latitude/longitude, with the southern pole and the angle of rotation
+    Rotated {
+        pole_lat: f64,
+        pole_lon: f64,
+        angle: f64,
+    },
+    Mercator {
+        lat_ts: f64,
+    },
+    PolarStereographic {
+        south: bool,
+        lat_ts: f64,
+        lon_0: f64,
+    },
+    LambertConformal {
+        lat_0: f64,
+        lon_0: f64,
+        lat_1: f64,
+        lat_2: f64,
+    },
+    LambertAzimuthal {
+        lat_0: f64,
+        lon_0: f64,
+    },
+    Geostationary {
+        lon_0: f64,
+        height: f64,
+    },
+}
+
+/// Transform from `(x, y)` offsets on a [`StructuredGrid`](super::StructuredGrid) to
+/// coordinates of its coordinate reference system
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridTransform {
+    /// Coordinates of the first grid point
+    pub origin: (f64, f64),
+    /// Distance between points along the x and y axes
+    pub step: (f64, f64),
+}
+
+impl GridTransform {
+    /// Coordinates of the offsets `(x, y)` from the first grid point
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.origin.0 + x * self.step.0,
+            self.origin.1 + y * self.step.1,
+        )
+    }
+}
+
+impl GridDefinitionTemplate {
+    /// Shape of the earth of the template (Code Table 3.2)
+    pub fn earth_shape(&self) -> Option<EarthShape> {
+        match self {
+            Self::Template3_0(tmpl) => earth_shape!(tmpl),
+            Self::Template3_1(tmpl) => earth_shape!(tmpl.template_0),
+            Self::Template3_10(tmpl) => earth_shape!(tmpl),
+            Self::Template3_20(tmpl) => earth_shape!(tmpl),
+            Self::Template3_30(tmpl) => earth_shape!(tmpl),
+            Self::Template3_40(tmpl) => earth_shape!(tmpl),
+            Self::Template3_90(tmpl) => earth_shape!(tmpl),
+            // The template only carries the code, so codes needing a radius or axes are unknown
+            Self::Template3_101(tmpl) => {
+                EarthShape::from_shape_of_earth(tmpl.shape_of_earth, 0, 0, 0, 0, 0, 0)
+            }
+            Self::Template3_140(tmpl) => earth_shape!(tmpl),
+            Self::Template3_204(tmpl) => earth_shape!(tmpl),
+        }
+    }
+
+    /// Map projection of the grid, None if it is not supported
+    pub fn projection(&self) -> Option<Projection> {
+        Some(match self {
+            Self::Template3_0(_)
+            | Self::Template3_40(_)
+            | Self::Template3_101(_)
+            | Self::Template3_204(_) => Projection::LatLon,
+            Self::Template3_1(tmpl) => {
+                let (pole_lat, pole_lon) = tmpl.southern_pole();
+                Projection::Rotated {
+                    pole_lat,
+                    pole_lon,
+                    angle: tmpl.angle_of_rotation as f64,
+                }
+            }
+            Self::Template3_10(tmpl) => Projection::Mercator {
+                lat_ts: tmpl.lad as f64 * MICRO,
+            },
+            // The hemisphere of the standard parallel is the one of the pole on the plane
+            Self::Template3_20(tmpl) => Projection::PolarStereographic {
+                south: tmpl.is_south_polar(),
+                lat_ts: (tmpl.lad as f64 * MICRO)
+                    .abs()
+                    .copysign(if tmpl.is_south_polar() { -1.0 } else { 1.0 }),
+                lon_0: tmpl.lov as f64 * MICRO,
+            },
+            Self::Template3_30(tmpl) => Projection::LambertConformal {
+                lat_0: tmpl.lad as f64 * MICRO,
+                lon_0: tmpl.lov as f64 * MICRO,
+                lat_1: tmpl.latin1 as f64 * MICRO,
+                lat_2: tmpl.latin2 as f64 * MICRO,
+            },
+            Self::Template3_90(tmpl) if !tmpl.is_orthographic() => Projection::Geostationary {
+                lon_0: tmpl.lop as f64 * MICRO,
+                height: (tmpl.n_r as f64 * MICRO - 1.0) * self.earth_shape()?.ellipsoid().a,
+            },
+            Self::Template3_140(tmpl) => Projection::LambertAzimuthal {
+                lat_0: tmpl.standard_parallel as f64 * MICRO,
+                lon_0: tmpl.central_longitude as f64 * MICRO,
+            },
+            _ => return None,
+        })
+    }
+
+    /// Transform from offsets on the grid to coordinates of the coordinate reference system
+    ///
+    /// Coordinates are longitudes and latitudes in degrees for latitude/longitude grids (in the
+    /// rotated system for rotated grids) and eastings and northings in metres for projected
+    /// grids. None for grids whose points aren't evenly spaced in their system.
+    pub fn crs_transform(&self) -> Option<GridTransform> {
+        let lat_lon = |tmpl: &GridDefinitionTemplate3_0| {
+            (
+                tmpl.first_point(),
+                (tmpl.i_increment(), tmpl.j_increment(tmpl.n_j as usize)),
+            )
+        };
+        let (origin, step) = match self {
+            Self::Template3_0(tmpl) if !tmpl.is_quasi_regular() => lat_lon(tmpl),
+            Self::Template3_1(tmpl) => lat_lon(&tmpl.template_0),
+            Self::Template3_10(tmpl) if tmpl.orientation_of_the_grid == 0 => (
+                tmpl.projected_first_point()?,
+                (tmpl.d_i as f64 * 1e-3, tmpl.d_j as f64 * 1e-3),
+            ),
+            Self::Template3_20(tmpl) => (
+                tmpl.projected_first_point()?,
+                (tmpl.d_x as f64 * 1e-3, tmpl.d_y as f64 * 1e-3),
+            ),
+            Self::Template3_30(tmpl) => (
+                tmpl.projected_first_point()?,
+                (tmpl.d_x as f64 * 1e-3, tmpl.d_y as f64 * 1e-3),
+            ),
+            Self::Template3_140(tmpl) => (
+                tmpl.projected_first_point()?,
+                (tmpl.d_x as f64 * 1e-3, tmpl.d_y as f64 * 1e-3),
+            ),
+            _ => return None,
+        };
+        Some(GridTransform { origin, step })
+    }
+
+    /// PROJ string of the coordinate reference system of the grid
+    ///
+    /// Projected systems are in metres. None if the projection or the earth shape is not
+    /// supported.
+    pub fn proj_string(&self) -> Option<String> {
+        let earth = self.earth_shape()?.proj_params();
+        Some(match self.projection()? {
+            Projection::LatLon => format!("+proj=longlat {} +no_defs", earth),
+            Projection::Rotated {
+                pole_lat,
+                pole_lon,
+                angle,
+            } => format!(
+                "+proj=ob_tran +o_proj=longlat +o_lat_p={} +o_lon_p={} +lon_0={} {} +no_defs",
+                -pole_lat, -angle, pole_lon, earth
+            ),
+            Projection::Mercator { lat_ts } => format!(
+                "+proj=merc +lat_ts={} +lon_0=0 +x_0=0 +y_0=0 {} +units=m +no_defs",
+                lat_ts, earth
+            ),
+            Projection::PolarStereographic {
+                south,
+                lat_ts,
+                lon_0,
+            } => format!(
+                "+proj=stere +lat_0={} +lat_ts={} +lon_0={} +x_0=0 +y_0=0 {} +units=m +no_defs",
+                if south { -90 } else { 90 },
+                lat_ts,
+                lon_0,
+                earth
+            ),
+            Projection::LambertConformal {
+                lat_0,
+                lon_0,
+                lat_1,
+                lat_2,
+            } => format!(
+                "+proj=lcc +lat_0={} +lon_0={} +lat_1={} +lat_2={} +x_0=0 +y_0=0 {} +units=m +no_defs",
+                lat_0, lon_0, lat_1, lat_2, earth
+            ),
+            Projection::LambertAzimuthal { lat_0, lon_0 } => format!(
+                "+proj=laea +lat_0={} +lon_0={} +x_0=0 +y_0=0 {} +units=m +no_defs",
+                lat_0, lon_0, earth
+            ),
+            Projection::Geostationary { lon_0, height } => format!(
+                "+proj=geos +lon_0={} +h={} +x_0=0 +y_0=0 {} +units=m +no_defs",
+                lon_0, height, earth
+            ),
+        })
+    }
+
+    /// OGC WKT2 (2019) of the coordinate reference system of the grid
+    ///
+    /// Projected systems are in metres. None if the projection or the earth shape is not
+    /// supported.
+    pub fn wkt2(&self) -> Option<String> {
+        let earth = self.earth_shape()?;
+        let (datum, geog_name) = wkt_datum(&earth);
+        let lat_lon_cs = format!(
+            r#"CS[ellipsoidal,2],AXIS["geodetic latitude (Lat)",north,ORDER[1],{DEGREE}],AXIS["geodetic longitude (Lon)",east,ORDER[2],{DEGREE}]"#
+        );
+        let (method, parameters) = match self.projection()? {
+            Projection::LatLon => {
+                let id = match earth {
+                    EarthShape::Wgs84 => r#",ID["EPSG",4326]"#,
+                    _ => "",
+                };
+                return Some(format!(
+                    r#"GEOGCRS["{geog_name}",{datum},{lat_lon_cs}{id}]"#
+                ));
+            }
+            Projection::Rotated {
+                pole_lat,
+                pole_lon,
+                angle,
+            } => {
+                let parameters = [
+                    angle_parameter("Latitude of the southern pole (GRIB convention)", pole_lat),
+                    angle_parameter("Longitude of the southern pole (GRIB convention)", pole_lon),
+                    angle_parameter("Axis rotation (GRIB convention)", angle),
+                ]
+                .join(",");
+                return Some(format!(
+                    r#"GEOGCRS["Rotated {geog_name}",BASEGEOGCRS["{geog_name}",{datum}],DERIVINGCONVERSION["Pole rotation (GRIB convention)",METHOD["Pole rotation (GRIB convention)"],{parameters}],{lat_lon_cs}]"#
+                ));
+            }
+            Projection::Mercator { lat_ts } => (
+                r#"METHOD["Mercator (variant B)",ID["EPSG",9805]]"#,
+                vec![
+                    epsg_angle("Latitude of 1st standard parallel", lat_ts, 8823),
+                    epsg_angle("Longitude of natural origin", 0.0, 8802),
+                    epsg_length("False easting", 8806),
+                    epsg_length("False northing", 8807),
+                ],
+            ),
+            Projection::PolarStereographic {
+                south: _,
+                lat_ts,
+                lon_0,
+            } => (
+                r#"METHOD["Polar Stereographic (variant B)",ID["EPSG",9829]]"#,
+                vec![
+                    epsg_angle("Latitude of standard parallel", lat_ts, 8832),
+                    epsg_angle("Longitude of origin", lon_0, 8833),
+                    epsg_length("False easting", 8806),
+                    epsg_length("False northing", 8807),
+                ],
+            ),
+            Projection::LambertConformal {
+                lat_0,
+                lon_0,
+                lat_1,
+                lat_2,
+            } => (
+                r#"METHOD["Lambert Conic Conformal (2SP)",ID["EPSG",9802]]"#,
+                vec![
+                    epsg_angle("Latitude of false origin", lat_0, 8821),
+                    epsg_angle("Longitude of false origin", lon_0, 8822),
+                    epsg_angle("Latitude of 1st standard parallel", lat_1, 8823),
+                    epsg_angle("Latitude of 2nd standard parallel", lat_2, 8824),
+                    epsg_length("Easting at false origin", 8826),
+                    epsg_length("Northing at false origin", 8827),
+                ],
+            ),
+            Projection::LambertAzimuthal { lat_0, lon_0 } => (
+                r#"METHOD["Lambert Azimuthal Equal Area",ID["EPSG",9820]]"#,
+                vec![
+                    epsg_angle("Latitude of natural origin", lat_0, 8801),
+                    epsg_angle("Longitude of natural origin", lon_0, 8802),
+                    epsg_length("False easting", 8806),
+                    epsg_length("False northing", 8807),
+                ],
+            ),
+            Projection::Geostationary { lon_0, height } => (
+                r#"METHOD["Geostationary Satellite (Sweep Y)"]"#,
+                vec![
+                    epsg_angle("Longitude of natural origin", lon_0, 8802),
+                    format!(r#"PARAMETER["Satellite Height",{height},{METRE}]"#),
+                    epsg_length("False easting", 8806),
+                    epsg_length("False northing", 8807),
+                ],
+            ),
+        };
+        let parameters = parameters.join(",");
+        Some(format!(
+            r#"PROJCRS["unknown",BASEGEOGCRS["{geog_name}",{datum}],CONVERSION["unknown",{method},{parameters}],CS[Cartesian,2],AXIS["(E)",east,ORDER[1],{METRE}],AXIS["(N)",north,ORDER[2],{METRE}]]"#
+        ))
+    }
+}
+
+/// WKT datum and prime meridian of an earth shape, and the name of its geographic system
+fn wkt_datum(earth: &EarthShape) -> (String, &'static str) {
+    let (a, rf) = earth.axis_and_inverse_flattening();
+    let (datum_name, geog_name) = match earth {
+        EarthShape::Wgs84 => ("World Geodetic System 1984", "WGS 84"),
+        EarthShape::Airy1830 => ("Ordnance Survey of Great Britain 1936", "OSGB36"),
+        _ => ("unknown", "unknown"),
+    };
+    let ellipsoid_name = earth.name();
+    (
+        format!(
+            r#"DATUM["{datum_name}",ELLIPSOID["{ellipsoid_name}",{a},{rf},{METRE}]],PRIMEM["Greenwich",0,{DEGREE}]"#
+        ),
+        geog_name,
+    )
+}
+
+fn angle_parameter(name: &str, value: f64) -> String {
+    format!(r#"PARAMETER["{name}",{value},{DEGREE}]"#)
+}
+
+fn epsg_angle(name: &str, value: f64, code: u32) -> String {
+    format!(r#"PARAMETER["{name}",{value},{DEGREE},ID["EPSG",{code}]]"#)
+}
+
+fn epsg_length(name: &str, code: u32) -> String {
+    format!(r#"PARAMETER["{name}",0,{METRE},ID["EPSG",{code}]]"#)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::GridCatalog;
+
+    #[test]
+    fn lat_lon_system() {
+        let grid = GridCatalog::builtin().get(3).unwrap();
+        assert_eq!(grid.projection(), Some(Projection::LatLon));
+        assert_eq!(
+            grid.proj_string().unwrap(),
+            "+proj=longlat +R=6371229 +no_defs"
+        );
+        assert!(grid.wkt2().unwrap().starts_with(
+            r#"GEOGCRS["unknown",DATUM["unknown",ELLIPSOID["Sphere",6371229,0,LENGTHUNIT["metre",1]]]"#
+        ));
+        let transform = grid.crs_transform().unwrap();
+        assert_eq!(transform.apply(0.0, 0.0), (0.0, 90.0));
+        assert_eq!(transform.apply(359.0, -180.0), (359.0, -90.0));
+    }
+
+    #[test]
+    fn lambert_conformal_system() {
+        // PROJ gives the first point of grid 211 with
+        // +proj=lcc +lat_0=25 +lon_0=265 +lat_1=25 +lat_2=25 +R=6371229
+        let grid = GridCatalog::builtin().get(211).unwrap();
+        assert_eq!(
+            grid.proj_string().unwrap(),
+            "+proj=lcc +lat_0=25 +lon_0=265 +lat_1=25 +lat_2=25 +x_0=0 +y_0=0 +R=6371229 \
+             +units=m +no_defs"
+        );
+        let wkt = grid.wkt2().unwrap();
+        assert!(wkt.starts_with("PROJCRS["));
+        assert!(wkt.contains(r#"METHOD["Lambert Conic Conformal (2SP)",ID["EPSG",9802]]"#));
+        assert!(wkt.contains(r#"PARAMETER["Longitude of false origin",265,"#));
+
+        let transform = grid.crs_transform().unwrap();
+        let (x, y) = transform.apply(0.0, 0.0);
+        assert!((x + 4_226_106.997).abs() < 1e-2 && (y + 832_698.261).abs() < 1e-2);
+        assert!((transform.step.0 - 81_270.5).abs() < 1e-9);
+
+        // Moving along the grid moves by whole steps in the projected system
+        let structured = grid.as_structured().unwrap();
+        let (lat, lon) = structured.xy_to_latlon(92.0, 64.0).unwrap();
+        assert_eq!(
+            structured
+                .latlon_to_xy(lat, lon)
+                .map(|(x, y)| (x.round(), y.round())),
+            Some((92.0, 64.0))
+        );
+        let (x, y) = transform.apply(92.0, 64.0);
+        assert!((x - (-4_226_106.997 + 92.0 * 81_270.5)).abs() < 1e-2);
+        assert!((y - (-832_698.261 + 64.0 * 81_270.5)).abs() < 1e-2);
+    }
+
+    #[test]
+    fn wgs84_systems_have_epsg_codes() {
+        let Some(GridDefinitionTemplate::Template3_0(tmpl)) = GridCatalog::builtin().get(3) else {
+            unreachable!()
+        };
+        let grid = GridDefinitionTemplate::Template3_0(GridDefinitionTemplate3_0 {
+            shape_of_earth: 5,
+            ..tmpl.clone()
+        });
+        assert_eq!(grid.earth_shape(), Some(EarthShape::Wgs84));
+        assert_eq!(
+            grid.proj_string().unwrap(),
+            "+proj=longlat +ellps=WGS84 +no_defs"
+        );
+        let wkt = grid.wkt2().unwrap();
+        assert!(wkt.starts_with(r#"GEOGCRS["WGS 84",DATUM["World Geodetic System 1984""#));
+        assert!(wkt.ends_with(r#"ID["EPSG",4326]]"#));
+    }
+
+    #[test]
+    fn unknown_shapes_have_no_system() {
+        let Some(GridDefinitionTemplate::Template3_0(tmpl)) = GridCatalog::builtin().get(3) else {
+            unreachable!()
+        };
+        let grid = GridDefinitionTemplate::Template3_0(GridDefinitionTemplate3_0 {
+            shape_of_earth: 200,
+            ..tmpl.clone()
+        });
+        assert_eq!(grid.earth_shape(), None);
+        assert_eq!(grid.proj_string(), None);
+        assert_eq!(grid.wkt2(), None);
+    }
+}
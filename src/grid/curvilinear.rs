@@ -0,0 +1,204 @@
+use super::{GridGeometry, ScanningMode, normalize_longitude};
+use crate::templates::GridDefinitionTemplate3_204;
+use crate::{Error, Result};
+
+/// Companion field carrying the coordinates of a curvilinear grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoordinateField {
+    Latitude,
+    Longitude,
+}
+
+impl CoordinateField {
+    /// Identifies the coordinate fields by their parameter (discipline 0, category 191,
+    /// parameters 1 and 2).
+    pub fn from_parameter(discipline: u8, category: u8, number: u8) -> Option<Self> {
+        match (discipline, category, number) {
+            (0, 191, 1) => Some(Self::Latitude),
+            (0, 191, 2) => Some(Self::Longitude),
+            _ => None,
+        }
+    }
+}
+
+/// Grid of `ni` x `nj` points whose coordinates are given point by point
+#[derive(Debug, Clone)]
+pub struct CurvilinearGrid {
+    ni: usize,
+    nj: usize,
+    scanning_mode: ScanningMode,
+    resolution_and_component_flags: u8,
+    latitudes: Vec<f64>,
+    longitudes: Vec<f64>,
+}
+
+impl CurvilinearGrid {
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.ni, self.nj)
+    }
+
+    pub fn scanning_mode(&self) -> ScanningMode {
+        self.scanning_mode
+    }
+
+    /// Latitudes in degrees of the points, in data order
+    pub fn latitudes(&self) -> &[f64] {
+        &self.latitudes
+    }
+
+    /// Longitudes in degrees of the points, in data order
+    pub fn longitudes(&self) -> &[f64] {
+        &self.longitudes
+    }
+}
+
+impl GridGeometry for CurvilinearGrid {
+    fn number_of_points(&self) -> usize {
+        self.latitudes.len()
+    }
+
+    fn latlon(&self, index: usize) -> Option<(f64, f64)> {
+        let (lat, lon) = (*self.latitudes.get(index)?, self.longitudes[index]);
+        if lat.is_nan() || lon.is_nan() {
+            return None;
+        }
+        Some((lat, normalize_longitude(lon)))
+    }
+
+    /// Angle of the +x axis estimated from the neighbouring points along the row
+    fn grid_rotation(&self, index: usize) -> Option<f64> {
+        let scan = self.scanning_mode;
+        let (i, j) = scan.ij(index, self.ni, self.nj);
+        let (prev, next) = (i.saturating_sub(1), (i + 1).min(self.ni - 1));
+        if prev == next {
+            return None;
+        }
+        let (lat0, lon0) = self.latlon(scan.index(prev, j, self.ni, self.nj))?;
+        let (lat1, lon1) = self.latlon(scan.index(next, j, self.ni, self.nj))?;
+        let dlon = (lon1 - lon0 + 540.0).rem_euclid(360.0) - 180.0;
+        let east = dlon * ((lat0 + lat1) / 2.0).to_radians().cos();
+        let north = lat1 - lat0;
+        if east == 0.0 && north == 0.0 {
+            return None;
+        }
+        // The +x axis points along -i when the rows scan westward
+        let (east, north) = match scan.i_negative() {
+            true => (-east, -north),
+            false => (east, north),
+        };
+        Some(north.atan2(east))
+    }
+
+    fn uv_relative_to_grid(&self) -> bool {
+        self.resolution_and_component_flags & 0x08 != 0
+    }
+}
+
+impl GridDefinitionTemplate3_204 {
+    /// Builds the geometry from the values of the companion latitude and longitude fields,
+    /// which share the data order of the grid.
+    pub fn curvilinear_grid(
+        &self,
+        latitudes: &[f32],
+        longitudes: &[f32],
+    ) -> Result<CurvilinearGrid> {
+        let (ni, nj) = (self.n_i as usize, self.n_j as usize);
+        for (name, values) in [("latitude", latitudes), ("longitude", longitudes)] {
+            if values.len() != ni * nj {
+                return Err(Error::InvalidData(format!(
+                    "the {} field has {} values, but the grid has {} x {} points",
+                    name,
+                    values.len(),
+                    ni,
+                    nj
+                )));
+            }
+        }
+        Ok(CurvilinearGrid {
+            ni,
+            nj,
+            scanning_mode: ScanningMode(self.scanning_mode),
+            resolution_and_component_flags: self.resolution_and_component_flags,
+            latitudes: latitudes.iter().map(|&v| v as f64).collect(),
+            longitudes: longitudes.iter().map(|&v| v as f64).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(scanning_mode: u8) -> GridDefinitionTemplate3_204 {
+        GridDefinitionTemplate3_204 {
+            shape_of_earth: 6,
+            scale_factor_of_radius: 0,
+            scale_value_of_radius: 0,
+            scale_factor_of_major_axis: 0,
+            scale_value_of_major_axis: 0,
+            scale_factor_of_minor_axis: 0,
+            scale_value_of_minor_axis: 0,
+            n_i: 5,
+            n_j: 4,
+            resolution_and_component_flags: 0x08,
+            scanning_mode,
+        }
+    }
+
+    /// Coordinates of a 5 x 4 grid near the equator whose +x axis points north-east and +y axis
+    /// north-west, in data order for the scanning mode
+    fn coordinates(scan: ScanningMode) -> (Vec<f32>, Vec<f32>) {
+        (0..20)
+            .map(|index| {
+                let (i, j) = scan.ij(index, 5, 4);
+                let (x, y) = scan.xy(i as f64, j as f64);
+                (((x + y) * 0.01) as f32, ((x - y) * 0.01) as f32)
+            })
+            .unzip()
+    }
+
+    #[test]
+    fn identifies_coordinate_fields() {
+        assert_eq!(
+            CoordinateField::from_parameter(0, 191, 1),
+            Some(CoordinateField::Latitude)
+        );
+        assert_eq!(
+            CoordinateField::from_parameter(0, 191, 2),
+            Some(CoordinateField::Longitude)
+        );
+        assert_eq!(CoordinateField::from_parameter(0, 0, 0), None);
+    }
+
+    #[test]
+    fn rotation_follows_the_rows() {
+        for scanning_mode in [0x40, 0xc0, 0x00, 0x50] {
+            let tmpl = template(scanning_mode);
+            let (latitudes, longitudes) = coordinates(ScanningMode(scanning_mode));
+            let grid = tmpl.curvilinear_grid(&latitudes, &longitudes).unwrap();
+            for index in 0..20 {
+                let angle = grid.grid_rotation(index).unwrap().to_degrees();
+                assert!(
+                    (angle - 45.0).abs() < 0.01,
+                    "mode {:#x}: {}",
+                    scanning_mode,
+                    angle
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_fields_of_the_wrong_size() {
+        let (latitudes, longitudes) = coordinates(ScanningMode(0x40));
+        assert!(
+            template(0x40)
+                .curvilinear_grid(&latitudes[1..], &longitudes)
+                .is_err()
+        );
+        let grid = template(0x40)
+            .curvilinear_grid(&latitudes, &[f32::NAN; 20])
+            .unwrap();
+        assert_eq!(grid.latlon(3), None);
+    }
+}
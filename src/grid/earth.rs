@@ -0,0 +1,367 @@
+use std::f64::consts::FRAC_PI_2;
+
+/// Shape of the earth (Code Table 3.2)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EarthShape {
+    /// Sphere with the radius in metres (codes 0, 1, 6 and 8)
+    Sphere { radius: f64 },
+    /// IAU 1965 oblate spheroid (code 2)
+    Iau1965,
+    /// IAG-GRS80 (code 4)
+    Grs80,
+    /// WGS84 (codes 5 and 10)
+    Wgs84,
+    /// Airy 1830 ellipsoid of the OSGB 1936 datum (code 9)
+    Airy1830,
+    /// Oblate spheroid with the axes in metres (codes 3 and 7)
+    Ellipsoid { major_axis: f64, minor_axis: f64 },
+}
+
+impl EarthShape {
+    /// Interprets the shape of the earth fields shared by the grid definition templates.
+    ///
+    /// Returns None when the code is unknown or the values it refers to are missing.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_shape_of_earth(
+        shape_of_earth: u8,
+        scale_factor_of_radius: u8,
+        scale_value_of_radius: u32,
+        scale_factor_of_major_axis: u8,
+        scale_value_of_major_axis: u32,
+        scale_factor_of_minor_axis: u8,
+        scale_value_of_minor_axis: u32,
+    ) -> Option<Self> {
+        let scaled = |factor: u8, value: u32| match (factor, value) {
+            (u8::MAX, _) | (_, u32::MAX) | (_, 0) => None,
+            (factor, value) => Some(value as f64 / 10f64.powi(factor as i32)),
+        };
+        Some(match shape_of_earth {
+            0 => Self::Sphere {
+                radius: 6_367_470.0,
+            },
+            1 => Self::Sphere {
+                radius: scaled(scale_factor_of_radius, scale_value_of_radius)?,
+            },
+            2 => Self::Iau1965,
+            3 | 7 => {
+                // 3 gives the axes in km, 7 in m
+                let unit = if shape_of_earth == 3 { 1000.0 } else { 1.0 };
+                Self::Ellipsoid {
+                    major_axis: scaled(scale_factor_of_major_axis, scale_value_of_major_axis)?
+                        * unit,
+                    minor_axis: scaled(scale_factor_of_minor_axis, scale_value_of_minor_axis)?
+                        * unit,
+                }
+            }
+            4 => Self::Grs80,
+            5 | 10 => Self::Wgs84,
+            6 => Self::Sphere {
+                radius: 6_371_229.0,
+            },
+            8 => Self::Sphere {
+                radius: 6_371_200.0,
+            },
+            9 => Self::Airy1830,
+            _ => return None,
+        })
+    }
+
+    pub fn ellipsoid(&self) -> Ellipsoid {
+        match *self {
+            Self::Sphere { radius } => Ellipsoid::sphere(radius),
+            Self::Iau1965 => Ellipsoid::from_flattening(6_378_160.0, 297.0),
+            Self::Grs80 => Ellipsoid::from_flattening(6_378_137.0, 298.257_222_101),
+            Self::Wgs84 => Ellipsoid::from_flattening(6_378_137.0, 298.257_223_563),
+            Self::Airy1830 => Ellipsoid {
+                a: 6_377_563.396,
+                b: 6_356_256.909,
+            },
+            Self::Ellipsoid {
+                major_axis,
+                minor_axis,
+            } => Ellipsoid {
+                a: major_axis,
+                b: minor_axis,
+            },
+        }
+    }
+
+    /// Semi-major axis in metres and inverse flattening (0 for a sphere)
+    pub fn axis_and_inverse_flattening(&self) -> (f64, f64) {
+        match *self {
+            Self::Iau1965 => (6_378_160.0, 297.0),
+            Self::Grs80 => (6_378_137.0, 298.257_222_101),
+            Self::Wgs84 => (6_378_137.0, 298.257_223_563),
+            Self::Airy1830 => (6_377_563.396, 299.324_964_6),
+            _ => {
+                let Ellipsoid { a, b } = self.ellipsoid();
+                match a == b {
+                    true => (a, 0.0),
+                    false => (a, a / (a - b)),
+                }
+            }
+        }
+    }
+
+    /// Name of the ellipsoid, as used in WKT
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sphere { .. } => "Sphere",
+            Self::Iau1965 => "IAU 1965",
+            Self::Grs80 => "GRS 1980",
+            Self::Wgs84 => "WGS 84",
+            Self::Airy1830 => "Airy 1830",
+            Self::Ellipsoid { .. } => "unknown",
+        }
+    }
+
+    /// PROJ parameters describing the ellipsoid
+    pub fn proj_params(&self) -> String {
+        match *self {
+            Self::Sphere { radius } => format!("+R={}", radius),
+            Self::Grs80 => "+ellps=GRS80".to_string(),
+            Self::Wgs84 => "+ellps=WGS84".to_string(),
+            Self::Airy1830 => "+ellps=airy".to_string(),
+            _ => {
+                let Ellipsoid { a, b } = self.ellipsoid();
+                format!("+a={} +b={}", a, b)
+            }
+        }
+    }
+}
+
+/// Reference ellipsoid (or sphere) used by the projection math
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ellipsoid {
+    /// Semi-major axis in metres
+    pub a: f64,
+    /// Semi-minor axis in metres
+    pub b: f64,
+}
+
+impl Ellipsoid {
+    pub fn sphere(radius: f64) -> Self {
+        Self {
+            a: radius,
+            b: radius,
+        }
+    }
+
+    pub fn from_flattening(a: f64, inverse_flattening: f64) -> Self {
+        Self {
+            a,
+            b: a * (1.0 - 1.0 / inverse_flattening),
+        }
+    }
+
+    /// Interprets the shape of the earth fields shared by the grid definition templates
+    /// (Code Table 3.2).
+    ///
+    /// Returns None when the code is unknown or the values it refers to are missing.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_shape_of_earth(
+        shape_of_earth: u8,
+        scale_factor_of_radius: u8,
+        scale_value_of_radius: u32,
+        scale_factor_of_major_axis: u8,
+        scale_value_of_major_axis: u32,
+        scale_factor_of_minor_axis: u8,
+        scale_value_of_minor_axis: u32,
+    ) -> Option<Self> {
+        EarthShape::from_shape_of_earth(
+            shape_of_earth,
+            scale_factor_of_radius,
+            scale_value_of_radius,
+            scale_factor_of_major_axis,
+            scale_value_of_major_axis,
+            scale_factor_of_minor_axis,
+            scale_value_of_minor_axis,
+        )
+        .map(|shape| shape.ellipsoid())
+    }
+
+    pub fn is_sphere(&self) -> bool {
+        self.a == self.b
+    }
+
+    /// Mean radius (2a + b) / 3 in metres, used for distances on the sphere
+    pub fn mean_radius(&self) -> f64 {
+        (2.0 * self.a + self.b) / 3.0
+    }
+
+    /// First eccentricity
+    pub fn e(&self) -> f64 {
+        (1.0 - (self.b * self.b) / (self.a * self.a)).sqrt()
+    }
+
+    /// Snyder's m: cos(phi) / sqrt(1 - e^2 sin^2(phi))
+    pub(crate) fn msfn(&self, phi: f64) -> f64 {
+        let e = self.e();
+        let (sin, cos) = phi.sin_cos();
+        cos / (1.0 - e * e * sin * sin).sqrt()
+    }
+
+    /// Snyder's t: tan(pi/4 - phi/2) / ((1 - e sin(phi)) / (1 + e sin(phi)))^(e/2)
+    pub(crate) fn tsfn(&self, phi: f64) -> f64 {
+        let e = self.e();
+        let es = e * phi.sin();
+        (FRAC_PI_2 / 2.0 - phi / 2.0).tan() / ((1.0 - es) / (1.0 + es)).powf(e / 2.0)
+    }
+
+    /// Inverse of [`Ellipsoid::tsfn`]
+    pub(crate) fn phi_from_ts(&self, ts: f64) -> f64 {
+        let e = self.e();
+        let mut phi = FRAC_PI_2 - 2.0 * ts.atan();
+        for _ in 0..15 {
+            let es = e * phi.sin();
+            let next = FRAC_PI_2 - 2.0 * (ts * ((1.0 - es) / (1.0 + es)).powf(e / 2.0)).atan();
+            if (next - phi).abs() < 1e-12 {
+                return next;
+            }
+            phi = next;
+        }
+        phi
+    }
+
+    /// Snyder's q: (1 - e^2) (sin(phi) / (1 - e^2 sin^2(phi)) - ln((1 - e sin(phi)) / (1 + e sin(phi))) / 2e)
+    pub(crate) fn qsfn(&self, phi: f64) -> f64 {
+        let e = self.e();
+        let sin = phi.sin();
+        if e < 1e-10 {
+            return 2.0 * sin;
+        }
+        let es = e * sin;
+        (1.0 - e * e) * (sin / (1.0 - es * es) - ((1.0 - es) / (1.0 + es)).ln() / (2.0 * e))
+    }
+
+    /// Inverse of [`Ellipsoid::qsfn`]
+    pub(crate) fn phi_from_q(&self, q: f64) -> f64 {
+        let e = self.e();
+        let mut phi = (q / 2.0).clamp(-1.0, 1.0).asin();
+        if e < 1e-10 {
+            return phi;
+        }
+        if (q.abs() - self.qsfn(FRAC_PI_2)).abs() < 1e-12 {
+            return FRAC_PI_2.copysign(q);
+        }
+        for _ in 0..15 {
+            let (sin, cos) = phi.sin_cos();
+            let es = e * sin;
+            let one_es2 = 1.0 - es * es;
+            let delta = one_es2 * one_es2 / (2.0 * cos)
+                * (q / (1.0 - e * e) - sin / one_es2 + ((1.0 - es) / (1.0 + es)).ln() / (2.0 * e));
+            phi += delta;
+            if delta.abs() < 1e-12 {
+                break;
+            }
+        }
+        phi
+    }
+}
+
+/// Great-circle distance in metres between two points given in degrees, on a sphere of
+/// `radius` metres
+pub fn great_circle_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64, radius: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let dphi = phi2 - phi1;
+    let dlambda = (lon2 - lon1).to_radians();
+    let h = (dphi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (dlambda / 2.0).sin().powi(2);
+    2.0 * radius * h.sqrt().min(1.0).asin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interprets_shapes_of_the_earth() {
+        let shape = |code, radius: (u8, u32), major: (u8, u32), minor: (u8, u32)| {
+            EarthShape::from_shape_of_earth(
+                code, radius.0, radius.1, major.0, major.1, minor.0, minor.1,
+            )
+        };
+        let none = (0, 0);
+        assert_eq!(
+            shape(0, none, none, none),
+            Some(EarthShape::Sphere {
+                radius: 6_367_470.0
+            })
+        );
+        assert_eq!(
+            shape(1, (1, 63_712_290), none, none),
+            Some(EarthShape::Sphere {
+                radius: 6_371_229.0
+            })
+        );
+        assert_eq!(shape(1, (u8::MAX, u32::MAX), none, none), None);
+        assert_eq!(
+            shape(3, none, (1, 63_781), (1, 63_568)),
+            Some(EarthShape::Ellipsoid {
+                major_axis: 6_378_100.0,
+                minor_axis: 6_356_800.0,
+            })
+        );
+        assert_eq!(
+            shape(7, none, (0, 6_378_137), (0, 6_356_752)),
+            Some(EarthShape::Ellipsoid {
+                major_axis: 6_378_137.0,
+                minor_axis: 6_356_752.0,
+            })
+        );
+        assert_eq!(shape(5, none, none, none), Some(EarthShape::Wgs84));
+        assert_eq!(shape(10, none, none, none), Some(EarthShape::Wgs84));
+        assert_eq!(shape(9, none, none, none), Some(EarthShape::Airy1830));
+        assert_eq!(shape(11, none, none, none), None);
+    }
+
+    #[test]
+    fn ellipsoid_parameters() {
+        let wgs84 = EarthShape::Wgs84.ellipsoid();
+        assert!((wgs84.b - 6_356_752.314_245).abs() < 1e-6);
+        assert!((wgs84.e() - 0.081_819_190_842_622).abs() < 1e-12);
+        assert_eq!(
+            EarthShape::Wgs84.axis_and_inverse_flattening(),
+            (6_378_137.0, 298.257_223_563)
+        );
+        let sphere = EarthShape::Sphere {
+            radius: 6_371_229.0,
+        };
+        assert!(sphere.ellipsoid().is_sphere());
+        assert_eq!(sphere.axis_and_inverse_flattening(), (6_371_229.0, 0.0));
+        assert_eq!(sphere.proj_params(), "+R=6371229");
+        assert_eq!(EarthShape::Wgs84.proj_params(), "+ellps=WGS84");
+        // The inverse flattening of an ellipsoid given by its axes is derived from them
+        let (_, rf) = EarthShape::Ellipsoid {
+            major_axis: 6_378_137.0,
+            minor_axis: wgs84.b,
+        }
+        .axis_and_inverse_flattening();
+        assert!((rf - 298.257_223_563).abs() < 1e-6);
+    }
+
+    #[test]
+    fn latitude_functions_round_trip() {
+        for ellipsoid in [
+            EarthShape::Wgs84.ellipsoid(),
+            Ellipsoid::sphere(6_371_229.0),
+        ] {
+            for degrees in [-89.5, -45.0, 0.0, 12.19, 60.0, 89.5] {
+                let phi = f64::to_radians(degrees);
+                let from_ts = ellipsoid.phi_from_ts(ellipsoid.tsfn(phi));
+                let from_q = ellipsoid.phi_from_q(ellipsoid.qsfn(phi));
+                assert!((from_ts - phi).abs() < 1e-10, "{}", degrees);
+                assert!((from_q - phi).abs() < 1e-10, "{}", degrees);
+            }
+        }
+    }
+
+    #[test]
+    fn great_circle_distances() {
+        let radius = 6_371_229.0;
+        let quarter = radius * FRAC_PI_2;
+        assert!((great_circle_distance(0.0, 0.0, 0.0, 90.0, radius) - quarter).abs() < 1e-6);
+        assert!((great_circle_distance(0.0, 10.0, 90.0, 0.0, radius) - quarter).abs() < 1e-6);
+        assert!((great_circle_distance(0.0, 0.0, 0.0, 180.0, radius) - 2.0 * quarter).abs() < 1e-6);
+        assert_eq!(great_circle_distance(45.0, 7.0, 45.0, 7.0, radius), 0.0);
+    }
+}
@@ -0,0 +1,326 @@
+use std::sync::{Arc, Mutex};
+
+use super::{QuasiRegularGrid, ScanningMode, StructuredGrid, angle_unit, longitude_increment};
+use crate::templates::{GridDefinitionTemplate3_40, MAX_GAUSSIAN_N};
+use crate::{Error, Result};
+
+/// Latitudes in degrees of the `2 * n` rows of a Gaussian grid, from north to south
+///
+/// They are the roots of the Legendre polynomial of degree `2 * n`, found by Newton iteration.
+/// Empty when `n` is 0 or above [`MAX_GAUSSIAN_N`]. The latitudes of the last few values of `n`
+/// are cached, as the same grids are used over and over.
+pub fn gaussian_latitudes(n: usize) -> Arc<[f64]> {
+    /// Number of values of `n` whose latitudes are kept
+    const CACHE_SIZE: usize = 8;
+    static CACHE: Mutex<Vec<(usize, Arc<[f64]>)>> = Mutex::new(Vec::new());
+
+    if n > MAX_GAUSSIAN_N as usize {
+        return Arc::new([]);
+    }
+    {
+        // Most recently used last
+        let mut cache = CACHE.lock().unwrap();
+        if let Some(k) = cache.iter().position(|&(m, _)| m == n) {
+            let entry = cache.remove(k);
+            let latitudes = entry.1.clone();
+            cache.push(entry);
+            return latitudes;
+        }
+    }
+    let latitudes: Arc<[f64]> = compute_gaussian_latitudes(n).into();
+    let mut cache = CACHE.lock().unwrap();
+    if !cache.iter().any(|&(m, _)| m == n) {
+        if cache.len() == CACHE_SIZE {
+            cache.remove(0);
+        }
+        cache.push((n, latitudes.clone()));
+    }
+    latitudes
+}
+
+fn compute_gaussian_latitudes(n: usize) -> Vec<f64> {
+    let degree = 2 * n;
+    let mut latitudes = vec![0.0; degree];
+    for k in 0..n {
+        // Initial guess by Tricomi's approximation
+        let mut x = (std::f64::consts::PI * (k as f64 + 0.75) / (degree as f64 + 0.5)).cos();
+        for _ in 0..100 {
+            let (p, p_prev) = legendre(degree, x);
+            let dp = degree as f64 * (x * p - p_prev) / (x * x - 1.0);
+            let dx = p / dp;
+            x -= dx;
+            if dx.abs() < 1e-15 {
+                break;
+            }
+        }
+        let lat = x.asin().to_degrees();
+        latitudes[k] = lat;
+        latitudes[degree - 1 - k] = -lat;
+    }
+    latitudes
+}
+
+/// Values of the Legendre polynomials of degree `degree` and `degree - 1` at `x`
+fn legendre(degree: usize, x: f64) -> (f64, f64) {
+    let (mut p_prev, mut p) = (1.0, x);
+    for l in 2..=degree {
+        let next = ((2 * l - 1) as f64 * x * p - (l - 1) as f64 * p_prev) / l as f64;
+        p_prev = p;
+        p = next;
+    }
+    (p, p_prev)
+}
+
+/// Fractional row (0 at the northernmost latitude) of a latitude in a north-to-south list of
+/// at least 2 latitudes
+fn row_of_latitude(latitudes: &[f64], lat: f64) -> f64 {
+    let k = latitudes
+        .partition_point(|&l| l > lat)
+        .clamp(1, latitudes.len() - 1);
+    let (l0, l1) = (latitudes[k - 1], latitudes[k]);
+    (k - 1) as f64 + (l0 - lat) / (l0 - l1)
+}
+
+/// Latitude of a fractional row, extrapolating linearly beyond the first and last rows
+fn latitude_of_row(latitudes: &[f64], row: f64) -> f64 {
+    let k = (row.floor().max(0.0) as usize).min(latitudes.len() - 2);
+    let (l0, l1) = (latitudes[k], latitudes[k + 1]);
+    (l0 + (row - k as f64) * (l1 - l0)).clamp(-90.0, 90.0)
+}
+
+impl GridDefinitionTemplate3_40 {
+    fn unit(&self) -> f64 {
+        angle_unit(self.basic_angle, self.subdivisions_of_basic_angle)
+    }
+
+    /// Gaussian latitudes of the whole globe for this grid, from north to south
+    ///
+    /// See [`gaussian_latitudes`]; empty when `n` is 0 or too large.
+    pub fn latitudes(&self) -> Arc<[f64]> {
+        gaussian_latitudes(self.n as usize)
+    }
+
+    /// Increment in degrees between points along a row
+    ///
+    /// Falls back to the span from `lo1` to `lo2` when the increment is not given (Flag Table
+    /// 3.3, bit 3), as for latitude/longitude grids.
+    pub fn i_increment(&self) -> f64 {
+        if self.d_i != u32::MAX && self.resolution_and_component_flags & 0x20 != 0 {
+            return self.d_i as f64 * self.unit();
+        }
+        longitude_increment(
+            self.lo1 as f64 * self.unit(),
+            self.lo2 as f64 * self.unit(),
+            self.n_i,
+            ScanningMode(self.scanning_mode).i_negative(),
+        )
+    }
+
+    /// Index in [`GridDefinitionTemplate3_40::latitudes`] of the first row of the grid
+    fn first_row(&self, latitudes: &[f64]) -> usize {
+        row_of_latitude(latitudes, self.la1 as f64 * self.unit()).round() as usize
+    }
+
+    /// Geometry of a reduced Gaussian grid, given the number of points of every row.
+    ///
+    /// `full_circle` tells whether the numbers of points are for whole parallels rather than
+    /// for the span from `lo1` to `lo2` (Code Table 3.11, values 1 and 2).
+    pub fn reduced_grid(
+        &self,
+        points_per_row: &[u32],
+        full_circle: bool,
+    ) -> Result<QuasiRegularGrid> {
+        let latitudes = self.latitudes();
+        if latitudes.is_empty() {
+            return Err(Error::InvalidData(
+                "number of parallels between a pole and the equator must not be 0".to_string(),
+            ));
+        }
+        if points_per_row.len() != self.n_j as usize {
+            return Err(Error::InvalidData(format!(
+                "the list of numbers of points has {} entries, but the grid has {} rows",
+                points_per_row.len(),
+                self.n_j
+            )));
+        }
+        let first_row = self.first_row(&latitudes);
+        let scan = ScanningMode(self.scanning_mode);
+        let row_latitudes = (0..points_per_row.len())
+            .map(|j| {
+                let row = match scan.j_positive() {
+                    true => first_row.checked_sub(j),
+                    false => Some(first_row + j),
+                };
+                row.and_then(|row| latitudes.get(row).copied())
+                    .ok_or_else(|| {
+                        Error::InvalidData(format!(
+                            "{} rows don't fit in a Gaussian grid with N = {}",
+                            points_per_row.len(),
+                            self.n
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let unit = self.unit();
+        QuasiRegularGrid::new(
+            row_latitudes,
+            points_per_row.to_vec(),
+            self.lo1 as f64 * unit,
+            self.lo2 as f64 * unit,
+            full_circle,
+            scan.i_negative(),
+            self.resolution_and_component_flags,
+        )
+    }
+}
+
+/// Regular Gaussian grid (`n_i` not missing)
+impl StructuredGrid for GridDefinitionTemplate3_40 {
+    fn dimensions(&self) -> (usize, usize) {
+        (self.n_i as usize, self.n_j as usize)
+    }
+
+    fn scanning_mode(&self) -> ScanningMode {
+        ScanningMode(self.scanning_mode)
+    }
+
+    fn resolution_and_component_flags(&self) -> u8 {
+        self.resolution_and_component_flags
+    }
+
+    fn xy_to_latlon(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let latitudes = self.latitudes();
+        if latitudes.is_empty() {
+            return None;
+        }
+        let row = self.first_row(&latitudes) as f64 - y;
+        let lon = (self.lo1 as f64 * self.unit() + x * self.i_increment()).rem_euclid(360.0);
+        Some((latitude_of_row(&latitudes, row), lon))
+    }
+
+    fn latlon_to_xy(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let latitudes = self.latitudes();
+        if latitudes.is_empty() {
+            return None;
+        }
+        let y = self.first_row(&latitudes) as f64 - row_of_latitude(&latitudes, lat);
+        let di = self.i_increment();
+        let mut x = (lon - self.lo1 as f64 * self.unit()).rem_euclid(360.0) / di;
+        // Grids scanning westward extend to negative x
+        if self.scanning_mode().i_negative() && x > 0.0 {
+            x -= 360.0 / di;
+        }
+        Some((x, y))
+    }
+
+    fn rotation_at(&self, _x: f64, _y: f64) -> Option<f64> {
+        Some(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Global regular Gaussian grid with N = 2 (8 x 4 points) in microdegrees
+    fn gaussian_grid() -> GridDefinitionTemplate3_40 {
+        GridDefinitionTemplate3_40 {
+            shape_of_earth: 6,
+            scale_factor_of_radius: 0,
+            scale_value_of_radius: 0,
+            scale_factor_of_major_axis: 0,
+            scale_value_of_major_axis: 0,
+            scale_factor_of_minor_axis: 0,
+            scale_value_of_minor_axis: 0,
+            n_i: 8,
+            n_j: 4,
+            basic_angle: 0,
+            subdivisions_of_basic_angle: u32::MAX,
+            la1: 59_444_489,
+            lo1: 0,
+            resolution_and_component_flags: 0x20,
+            la2: -59_444_489,
+            lo2: 315_000_000,
+            d_i: 45_000_000,
+            n: 2,
+            scanning_mode: 0,
+        }
+    }
+
+    #[test]
+    fn latitudes_are_the_legendre_roots() {
+        // P2 has its roots at x = ±1/sqrt(3)
+        let latitudes = gaussian_latitudes(1);
+        let expected = (1.0 / 3f64.sqrt()).asin().to_degrees();
+        assert_eq!(latitudes.len(), 2);
+        assert!((latitudes[0] - expected).abs() < 1e-12 && latitudes[1] == -latitudes[0]);
+        // P4 has its roots at x = ±sqrt((3 ± 2 sqrt(6/5)) / 7)
+        let latitudes = gaussian_latitudes(2);
+        let x = |sign: f64| ((3.0 + sign * 2.0 * 1.2f64.sqrt()) / 7.0).sqrt();
+        let expected = [x(1.0), x(-1.0), -x(-1.0), -x(1.0)].map(|x| x.asin().to_degrees());
+        for (lat, expected) in latitudes.iter().zip(expected) {
+            assert!((lat - expected).abs() < 1e-12);
+        }
+        // Rows are strictly decreasing and symmetric about the equator
+        let latitudes = gaussian_latitudes(320);
+        assert!(latitudes.windows(2).all(|w| w[0] > w[1]));
+        assert!((latitudes[0] + latitudes[639]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn grid_points_follow_the_rows() {
+        let grid = gaussian_grid();
+        assert!(Arc::ptr_eq(&grid.latitudes(), &grid.latitudes()));
+        let (lat, lon) = grid.xy_to_latlon(3.0, -2.0).unwrap();
+        assert!((lat - grid.latitudes()[2]).abs() < 1e-12);
+        assert_eq!(lon, 135.0);
+        let (x, y) = grid.latlon_to_xy(lat, lon).unwrap();
+        assert!((x - 3.0).abs() < 1e-12 && (y + 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn longitudes_are_normalised() {
+        let grid = GridDefinitionTemplate3_40 {
+            lo1: -90_000_000,
+            ..gaussian_grid()
+        };
+        assert_eq!(grid.xy_to_latlon(0.0, 0.0).unwrap().1, 270.0);
+        assert_eq!(grid.xy_to_latlon(3.0, 0.0).unwrap().1, 45.0);
+    }
+
+    #[test]
+    fn missing_increment_falls_back_to_the_span() {
+        let grid = GridDefinitionTemplate3_40 {
+            d_i: u32::MAX,
+            resolution_and_component_flags: 0,
+            ..gaussian_grid()
+        };
+        assert_eq!(grid.i_increment(), 45.0);
+        assert_eq!(grid.xy_to_latlon(7.0, 0.0).unwrap().1, 315.0);
+    }
+
+    #[test]
+    fn rejects_zero_parallels() {
+        let grid = GridDefinitionTemplate3_40 {
+            n: 0,
+            ..gaussian_grid()
+        };
+        assert_eq!(grid.xy_to_latlon(0.0, 0.0), None);
+        assert!(grid.reduced_grid(&[4, 8, 8, 4], true).is_err());
+
+        // Too many parallels to find their latitudes, as in templates built by hand
+        let grid = GridDefinitionTemplate3_40 {
+            n: u32::MAX,
+            ..gaussian_grid()
+        };
+        assert!(grid.latitudes().is_empty());
+        assert_eq!(grid.xy_to_latlon(0.0, 0.0), None);
+
+        // All 58 octets of the template zero, N included
+        let octets = [0u8; 58];
+        assert!(matches!(
+            GridDefinitionTemplate3_40::read(&mut &octets[..]),
+            Err(Error::InvalidData(_))
+        ));
+    }
+}
@@ -0,0 +1,198 @@
+use super::{Ellipsoid, MICRO, ScanningMode, StructuredGrid, normalize_longitude};
+use crate::templates::GridDefinitionTemplate3_30;
+
+/// Constants of a Lambert conformal conic projection with the pole at the origin
+struct Cone {
+    ellipsoid: Ellipsoid,
+    /// Cone constant
+    n: f64,
+    /// a * F in Snyder's notation
+    af: f64,
+    lov: f64,
+}
+
+impl Cone {
+    fn forward(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let phi = lat.to_radians();
+        // The pole opposite to the apex of the cone is at infinity
+        if (phi + std::f64::consts::FRAC_PI_2.copysign(self.n)).abs() < 1e-10 {
+            return None;
+        }
+        let rho = self.af * self.ellipsoid.tsfn(phi).powf(self.n);
+        let theta = self.n * normalize_longitude(lon - self.lov).to_radians();
+        Some((rho * theta.sin(), -rho * theta.cos()))
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let sign = self.n.signum();
+        let rho = sign * x.hypot(y);
+        let theta = (sign * x).atan2(-sign * y);
+        let lat = if rho == 0.0 {
+            90f64.copysign(self.n)
+        } else {
+            let ts = (rho / self.af).powf(1.0 / self.n);
+            self.ellipsoid.phi_from_ts(ts).to_degrees()
+        };
+        let lon = self.lov + (theta / self.n).to_degrees();
+        (lat, normalize_longitude(lon))
+    }
+}
+
+impl GridDefinitionTemplate3_30 {
+    /// Whether the cone touches the earth along a single standard parallel
+    pub fn is_tangent(&self) -> bool {
+        self.latin1 == self.latin2
+    }
+
+    fn cone(&self) -> Option<Cone> {
+        let ellipsoid = self.ellipsoid()?;
+        let phi1 = (self.latin1 as f64 * MICRO).to_radians();
+        let phi2 = (self.latin2 as f64 * MICRO).to_radians();
+        let (m1, t1) = (ellipsoid.msfn(phi1), ellipsoid.tsfn(phi1));
+        let n = if self.is_tangent() {
+            phi1.sin()
+        } else {
+            let (m2, t2) = (ellipsoid.msfn(phi2), ellipsoid.tsfn(phi2));
+            (m1.ln() - m2.ln()) / (t1.ln() - t2.ln())
+        };
+        if n == 0.0 || !n.is_finite() {
+            return None;
+        }
+        Some(Cone {
+            ellipsoid,
+            n,
+            af: ellipsoid.a * m1 / (n * t1.powf(n)),
+            lov: self.lov as f64 * MICRO,
+        })
+    }
+
+    /// Position in metres of the first grid point on the plane of `+proj=lcc`, whose origin is
+    /// at `lad` on `lov` rather than at the pole
+    pub(super) fn projected_first_point(&self) -> Option<(f64, f64)> {
+        let cone = self.cone()?;
+        let (x, y) = cone.forward(self.la1 as f64 * MICRO, self.lo1 as f64 * MICRO)?;
+        let rho0 = cone.af
+            * cone
+                .ellipsoid
+                .tsfn((self.lad as f64 * MICRO).to_radians())
+                .powf(cone.n);
+        Some((x, y + rho0))
+    }
+}
+
+impl StructuredGrid for GridDefinitionTemplate3_30 {
+    fn dimensions(&self) -> (usize, usize) {
+        (self.n_x as usize, self.n_y as usize)
+    }
+
+    fn scanning_mode(&self) -> ScanningMode {
+        ScanningMode(self.scanning_mode)
+    }
+
+    fn resolution_and_component_flags(&self) -> u8 {
+        self.resolution_and_component_flags
+    }
+
+    fn xy_to_latlon(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let cone = self.cone()?;
+        let (x1, y1) = cone.forward(self.la1 as f64 * MICRO, self.lo1 as f64 * MICRO)?;
+        Some(cone.inverse(
+            x1 + x * self.d_x as f64 * 1e-3,
+            y1 + y * self.d_y as f64 * 1e-3,
+        ))
+    }
+
+    fn latlon_to_xy(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let cone = self.cone()?;
+        let (x1, y1) = cone.forward(self.la1 as f64 * MICRO, self.lo1 as f64 * MICRO)?;
+        let (x, y) = cone.forward(lat, lon)?;
+        Some((
+            (x - x1) / (self.d_x as f64 * 1e-3),
+            (y - y1) / (self.d_y as f64 * 1e-3),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Secant grid of 20 km from 35N 75W on the Clarke 1866 ellipsoid, with standard parallels
+    /// 33N and 45N, origin at 23N 96W
+    fn secant_grid() -> GridDefinitionTemplate3_30 {
+        GridDefinitionTemplate3_30 {
+            shape_of_earth: 7,
+            scale_factor_of_radius: 0,
+            scale_value_of_radius: 0,
+            scale_factor_of_major_axis: 1,
+            scale_value_of_major_axis: 63_782_064,
+            scale_factor_of_minor_axis: 1,
+            scale_value_of_minor_axis: 63_565_838,
+            n_x: 100,
+            n_y: 100,
+            la1: 35_000_000,
+            lo1: -75_000_000,
+            resolution_and_component_flags: 0x08,
+            lad: 23_000_000,
+            lov: -96_000_000,
+            d_x: 20_000_000,
+            d_y: 20_000_000,
+            projection_centre_flag: 0,
+            scanning_mode: 0x40,
+            latin1: 33_000_000,
+            latin2: 45_000_000,
+            latitude_of_southern_pole: -90_000_000,
+            longitude_of_southern_pole: 0,
+        }
+    }
+
+    #[test]
+    fn matches_snyder_example() {
+        // Snyder, Map Projections: A Working Manual, appendix A
+        let (x, y) = secant_grid().projected_first_point().unwrap();
+        assert!((x - 1_894_410.9).abs() < 0.1, "{}", x);
+        assert!((y - 1_564_649.5).abs() < 0.1, "{}", y);
+    }
+
+    #[test]
+    fn tangent_cone_on_a_sphere() {
+        // Tangent at 45N: n = sin 45, rho = R cot 45 (tan(45 - lat / 2) / tan(22.5))^n
+        let grid = GridDefinitionTemplate3_30 {
+            shape_of_earth: 6,
+            scale_value_of_major_axis: 0,
+            scale_value_of_minor_axis: 0,
+            la1: 30_000_000,
+            lo1: -80_000_000,
+            lad: 45_000_000,
+            latin1: 45_000_000,
+            latin2: 45_000_000,
+            ..secant_grid()
+        };
+        let n = 45f64.to_radians().sin();
+        let rho = |lat: f64| {
+            6_371_229.0
+                * ((45.0 - lat / 2.0).to_radians().tan() / 22.5f64.to_radians().tan()).powf(n)
+        };
+        let theta = n * 16f64.to_radians();
+        let (x, y) = grid.projected_first_point().unwrap();
+        assert!((x - rho(30.0) * theta.sin()).abs() < 1e-6);
+        assert!((y - (rho(45.0) - rho(30.0) * theta.cos())).abs() < 1e-6);
+    }
+
+    #[test]
+    fn grid_points_round_trip() {
+        let grid = secant_grid();
+        let (lat, lon) = grid.xy_to_latlon(0.0, 0.0).unwrap();
+        assert!((lat - 35.0).abs() < 1e-9 && (lon + 75.0).abs() < 1e-9);
+        for (x, y) in [(1.0, 0.0), (0.0, 1.0), (99.0, 99.0), (-40.0, 20.5)] {
+            let (lat, lon) = grid.xy_to_latlon(x, y).unwrap();
+            let (x1, y1) = grid.latlon_to_xy(lat, lon).unwrap();
+            assert!(
+                (x1 - x).abs() < 1e-6 && (y1 - y).abs() < 1e-6,
+                "({}, {})",
+                x,
+                y
+            );
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use std::f64::consts::FRAC_PI_2;
+
+use super::{Ellipsoid, MICRO, ScanningMode, StructuredGrid, normalize_longitude};
+use crate::templates::GridDefinitionTemplate3_140;
+
+/// Constants of a Lambert azimuthal equal-area projection (Snyder, oblique and polar aspects)
+struct Azimuthal {
+    ellipsoid: Ellipsoid,
+    phi1: f64,
+    lon0: f64,
+    qp: f64,
+    /// Authalic latitude of the centre
+    beta1: f64,
+    rq: f64,
+    d: f64,
+}
+
+impl Azimuthal {
+    fn new(ellipsoid: Ellipsoid, lat0: f64, lon0: f64) -> Self {
+        let phi1 = lat0.to_radians();
+        let qp = ellipsoid.qsfn(FRAC_PI_2);
+        let beta1 = (ellipsoid.qsfn(phi1) / qp).clamp(-1.0, 1.0).asin();
+        let rq = ellipsoid.a * (qp / 2.0).sqrt();
+        let d = match beta1.cos() {
+            cos if cos < 1e-10 => 1.0,
+            cos => ellipsoid.a * ellipsoid.msfn(phi1) / (rq * cos),
+        };
+        Self {
+            ellipsoid,
+            phi1,
+            lon0,
+            qp,
+            beta1,
+            rq,
+            d,
+        }
+    }
+
+    /// Centre at one of the poles: 1 for north, -1 for south, 0 otherwise
+    fn polar(&self) -> f64 {
+        if (self.phi1.abs() - FRAC_PI_2).abs() < 1e-10 {
+            self.phi1.signum()
+        } else {
+            0.0
+        }
+    }
+
+    fn forward(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let q = self.ellipsoid.qsfn(lat.to_radians());
+        let (sin_dlon, cos_dlon) = normalize_longitude(lon - self.lon0).to_radians().sin_cos();
+        let pole = self.polar();
+        if pole != 0.0 {
+            let rho = self.ellipsoid.a * (self.qp - pole * q).max(0.0).sqrt();
+            return Some((rho * sin_dlon, -pole * rho * cos_dlon));
+        }
+        let beta = (q / self.qp).clamp(-1.0, 1.0).asin();
+        let (sin_b, cos_b) = beta.sin_cos();
+        let (sin_b1, cos_b1) = self.beta1.sin_cos();
+        let denominator = 1.0 + sin_b1 * sin_b + cos_b1 * cos_b * cos_dlon;
+        // The antipode of the centre can't be projected
+        if denominator < 1e-12 {
+            return None;
+        }
+        let b = self.rq * (2.0 / denominator).sqrt();
+        Some((
+            b * self.d * cos_b * sin_dlon,
+            (b / self.d) * (cos_b1 * sin_b - sin_b1 * cos_b * cos_dlon),
+        ))
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let pole = self.polar();
+        let a = self.ellipsoid.a;
+        let (q, dlon) = if pole != 0.0 {
+            let rho = x.hypot(y);
+            (pole * (self.qp - (rho / a).powi(2)), x.atan2(-pole * y))
+        } else {
+            let rho = (x / self.d).hypot(self.d * y);
+            if rho < 1e-9 {
+                return Some((self.phi1.to_degrees(), self.lon0));
+            }
+            let ratio = rho / (2.0 * self.rq);
+            if ratio > 1.0 {
+                return None;
+            }
+            let ce = 2.0 * ratio.asin();
+            let (sin_ce, cos_ce) = ce.sin_cos();
+            let (sin_b1, cos_b1) = self.beta1.sin_cos();
+            let q = self.qp * (cos_ce * sin_b1 + self.d * y * sin_ce * cos_b1 / rho);
+            let dlon = (x * sin_ce)
+                .atan2(self.d * rho * cos_b1 * cos_ce - self.d * self.d * y * sin_b1 * sin_ce);
+            (q, dlon)
+        };
+        if q.abs() > self.qp + 1e-12 {
+            return None;
+        }
+        let lat = self.ellipsoid.phi_from_q(q).to_degrees();
+        Some((lat, normalize_longitude(self.lon0 + dlon.to_degrees())))
+    }
+}
+
+impl GridDefinitionTemplate3_140 {
+    fn azimuthal(&self) -> Option<Azimuthal> {
+        Some(Azimuthal::new(
+            self.ellipsoid()?,
+            self.standard_parallel as f64 * MICRO,
+            self.central_longitude as f64 * MICRO,
+        ))
+    }
+
+    /// Position in metres of the first grid point on the plane of `+proj=laea`
+    pub(super) fn projected_first_point(&self) -> Option<(f64, f64)> {
+        self.azimuthal()?
+            .forward(self.la1 as f64 * MICRO, self.lo1 as f64 * MICRO)
+    }
+}
+
+impl StructuredGrid for GridDefinitionTemplate3_140 {
+    fn dimensions(&self) -> (usize, usize) {
+        (self.n_x as usize, self.n_y as usize)
+    }
+
+    fn scanning_mode(&self) -> ScanningMode {
+        ScanningMode(self.scanning_mode)
+    }
+
+    fn resolution_and_component_flags(&self) -> u8 {
+        self.resolution_and_component_flags
+    }
+
+    fn xy_to_latlon(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let azimuthal = self.azimuthal()?;
+        let (x1, y1) = azimuthal.forward(self.la1 as f64 * MICRO, self.lo1 as f64 * MICRO)?;
+        azimuthal.inverse(
+            x1 + x * self.d_x as f64 * 1e-3,
+            y1 + y * self.d_y as f64 * 1e-3,
+        )
+    }
+
+    fn latlon_to_xy(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let azimuthal = self.azimuthal()?;
+        let (x1, y1) = azimuthal.forward(self.la1 as f64 * MICRO, self.lo1 as f64 * MICRO)?;
+        let (x, y) = azimuthal.forward(lat, lon)?;
+        Some((
+            (x - x1) / (self.d_x as f64 * 1e-3),
+            (y - y1) / (self.d_y as f64 * 1e-3),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Grid of 10 km from 30N 110W on the Clarke 1866 ellipsoid, centred on 40N 100W
+    fn oblique_grid() -> GridDefinitionTemplate3_140 {
+        GridDefinitionTemplate3_140 {
+            shape_of_earth: 7,
+            scale_factor_of_radius: 0,
+            scale_value_of_radius: 0,
+            scale_factor_of_major_axis: 1,
+            scale_value_of_major_axis: 63_782_064,
+            scale_factor_of_minor_axis: 1,
+            scale_value_of_minor_axis: 63_565_838,
+            n_x: 200,
+            n_y: 200,
+            la1: 30_000_000,
+            lo1: -110_000_000,
+            standard_parallel: 40_000_000,
+            central_longitude: -100_000_000,
+            resolution_and_component_flags: 0x08,
+            d_x: 10_000_000,
+            d_y: 10_000_000,
+            scanning_mode: 0x40,
+        }
+    }
+
+    #[test]
+    fn matches_snyder_example() {
+        // Snyder, Map Projections: A Working Manual, appendix A
+        let (x, y) = oblique_grid().projected_first_point().unwrap();
+        assert!((x + 965_932.1).abs() < 0.1, "{}", x);
+        assert!((y + 1_056_814.9).abs() < 0.1, "{}", y);
+    }
+
+    #[test]
+    fn polar_aspect_on_a_sphere() {
+        // North polar: rho = 2 R sin(45 - lat / 2)
+        let grid = GridDefinitionTemplate3_140 {
+            shape_of_earth: 6,
+            scale_value_of_major_axis: 0,
+            scale_value_of_minor_axis: 0,
+            la1: 60_000_000,
+            lo1: 10_000_000,
+            standard_parallel: 90_000_000,
+            central_longitude: 0,
+            ..oblique_grid()
+        };
+        let rho = 2.0 * 6_371_229.0 * 15f64.to_radians().sin();
+        let (x, y) = grid.projected_first_point().unwrap();
+        assert!((x - rho * 10f64.to_radians().sin()).abs() < 1e-6);
+        assert!((y + rho * 10f64.to_radians().cos()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn grid_points_round_trip() {
+        let grid = oblique_grid();
+        let (lat, lon) = grid.xy_to_latlon(0.0, 0.0).unwrap();
+        assert!((lat - 30.0).abs() < 1e-9 && (lon + 110.0).abs() < 1e-9);
+        for (x, y) in [(1.0, 0.0), (0.0, 1.0), (199.0, 199.0), (96.5, 105.5)] {
+            let (lat, lon) = grid.xy_to_latlon(x, y).unwrap();
+            let (x1, y1) = grid.latlon_to_xy(lat, lon).unwrap();
+            assert!(
+                (x1 - x).abs() < 1e-6 && (y1 - y).abs() < 1e-6,
+                "({}, {})",
+                x,
+                y
+            );
+        }
+    }
+}
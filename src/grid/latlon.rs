@@ -0,0 +1,295 @@
+use super::{QuasiRegularGrid, ScanningMode, StructuredGrid, angle_unit, longitude_increment};
+use crate::templates::GridDefinitionTemplate3_0;
+use crate::{Error, Result};
+
+impl GridDefinitionTemplate3_0 {
+    /// Whether rows have different numbers of points (quasi-regular grid), in which case `n_i`
+    /// is missing and the numbers of points per row follow the template.
+    pub fn is_quasi_regular(&self) -> bool {
+        self.n_i == u32::MAX
+    }
+
+    /// Size in degrees of one unit of the angles of the template
+    fn unit(&self) -> f64 {
+        angle_unit(self.basic_angle, self.subdivisions_of_basic_angle)
+    }
+
+    /// Increment in degrees between points along a row
+    ///
+    /// Falls back to the span from `lo1` to `lo2` in the scanning direction when the increment is
+    /// not given (Flag Table 3.3, bit 3). A grid whose first and last longitudes coincide is
+    /// taken to go once around the globe.
+    pub fn i_increment(&self) -> f64 {
+        if self.d_i != u32::MAX && self.resolution_and_component_flags & 0x20 != 0 {
+            return self.d_i as f64 * self.unit();
+        }
+        longitude_increment(
+            self.lo1 as f64 * self.unit(),
+            self.lo2 as f64 * self.unit(),
+            self.n_i,
+            ScanningMode(self.scanning_mode).i_negative(),
+        )
+    }
+
+    /// Increment in degrees between rows, given the number of rows
+    ///
+    /// Falls back to the span from `la1` to `la2` when the increment is not given (Flag Table
+    /// 3.3, bit 4).
+    pub(super) fn j_increment(&self, rows: usize) -> f64 {
+        if self.d_j != u32::MAX && self.resolution_and_component_flags & 0x10 != 0 {
+            return self.d_j as f64 * self.unit();
+        }
+        match rows {
+            0 | 1 => 0.0,
+            rows => (self.la2 as f64 - self.la1 as f64).abs() * self.unit() / (rows - 1) as f64,
+        }
+    }
+
+    /// Latitude in degrees of every row, in scanning order (`j` = 0 first)
+    pub fn latitudes(&self) -> Vec<f64> {
+        (0..self.n_j as usize)
+            .map(|j| {
+                let (_, y) = self.scanning_mode().xy(0.0, j as f64);
+                self.latitude_at(y)
+            })
+            .collect()
+    }
+
+    /// Longitude in degrees of every column, in scanning order (`i` = 0 first), within
+    /// [0, 360). Empty for quasi-regular grids.
+    pub fn longitudes(&self) -> Vec<f64> {
+        if self.is_quasi_regular() {
+            return vec![];
+        }
+        (0..self.n_i as usize)
+            .map(|i| {
+                let (x, _) = self.scanning_mode().xy(i as f64, 0.0);
+                self.longitude_at(x)
+            })
+            .collect()
+    }
+
+    /// Grid position `(i, j)`, latitude and longitude in degrees of every point, in data order
+    ///
+    /// Empty for quasi-regular grids; use [`QuasiRegularGrid`] for those.
+    pub fn points(&self) -> impl Iterator<Item = (usize, usize, f64, f64)> + '_ {
+        let (ni, nj) = match self.is_quasi_regular() {
+            true => (0, 0),
+            false => self.dimensions(),
+        };
+        let (latitudes, longitudes) = (self.latitudes(), self.longitudes());
+        let scan = self.scanning_mode();
+        (0..ni * nj).map(move |index| {
+            let (i, j) = scan.ij(index, ni, nj);
+            (i, j, latitudes[j], longitudes[i])
+        })
+    }
+
+    /// Latitude and longitude in degrees of every point, in data order
+    pub fn latlon_arrays(&self) -> (Vec<f64>, Vec<f64>) {
+        self.points().map(|(_, _, lat, lon)| (lat, lon)).unzip()
+    }
+
+    /// Longitude and latitude in degrees of the first grid point
+    pub(super) fn first_point(&self) -> (f64, f64) {
+        (self.lo1 as f64 * self.unit(), self.la1 as f64 * self.unit())
+    }
+
+    fn latitude_at(&self, y: f64) -> f64 {
+        self.la1 as f64 * self.unit() + y * self.j_increment(self.n_j as usize)
+    }
+
+    fn longitude_at(&self, x: f64) -> f64 {
+        (self.lo1 as f64 * self.unit() + x * self.i_increment()).rem_euclid(360.0)
+    }
+
+    /// Geometry of a quasi-regular grid, given the number of points of every row.
+    ///
+    /// `full_circle` tells whether the numbers of points are for whole parallels rather than
+    /// for the span from `lo1` to `lo2` (Code Table 3.11, values 1 and 2).
+    pub fn quasi_regular_grid(
+        &self,
+        points_per_row: &[u32],
+        full_circle: bool,
+    ) -> Result<QuasiRegularGrid> {
+        let scan = ScanningMode(self.scanning_mode);
+        if scan.j_consecutive() {
+            return Err(Error::UnsupportedData(
+                "quasi-regular grids with varying column lengths are not supported".to_string(),
+            ));
+        }
+        if points_per_row.len() != self.n_j as usize {
+            return Err(Error::InvalidData(format!(
+                "the list of numbers of points has {} entries, but the grid has {} rows",
+                points_per_row.len(),
+                self.n_j
+            )));
+        }
+        let la1 = self.la1 as f64 * self.unit();
+        let dj = self.j_increment(points_per_row.len());
+        let dj = if scan.j_positive() { dj } else { -dj };
+        let rows = points_per_row.len();
+        QuasiRegularGrid::new(
+            (0..rows).map(|j| la1 + j as f64 * dj).collect(),
+            points_per_row.to_vec(),
+            self.lo1 as f64 * self.unit(),
+            self.lo2 as f64 * self.unit(),
+            full_circle,
+            scan.i_negative(),
+            self.resolution_and_component_flags,
+        )
+    }
+
+    /// Geometry of a grid of `n_i` x `n_j` points whose rows are at the given latitudes, in
+    /// units of the template (Code Table 3.11, value 3)
+    pub fn grid_at_latitudes(&self, row_latitudes: &[i32]) -> Result<QuasiRegularGrid> {
+        let scan = ScanningMode(self.scanning_mode);
+        if scan.j_consecutive() || self.is_quasi_regular() {
+            return Err(Error::UnsupportedData(
+                "row latitudes are only supported for rows of equal length".to_string(),
+            ));
+        }
+        if row_latitudes.len() != self.n_j as usize {
+            return Err(Error::InvalidData(format!(
+                "the list of row latitudes has {} entries, but the grid has {} rows",
+                row_latitudes.len(),
+                self.n_j
+            )));
+        }
+        let full_circle = (self.n_i as f64 * self.i_increment() - 360.0).abs() < 1e-6;
+        QuasiRegularGrid::new(
+            row_latitudes
+                .iter()
+                .map(|&lat| lat as f64 * self.unit())
+                .collect(),
+            vec![self.n_i; row_latitudes.len()],
+            self.lo1 as f64 * self.unit(),
+            self.lo2 as f64 * self.unit(),
+            full_circle,
+            scan.i_negative(),
+            self.resolution_and_component_flags,
+        )
+    }
+}
+
+impl StructuredGrid for GridDefinitionTemplate3_0 {
+    fn dimensions(&self) -> (usize, usize) {
+        (self.n_i as usize, self.n_j as usize)
+    }
+
+    fn scanning_mode(&self) -> ScanningMode {
+        ScanningMode(self.scanning_mode)
+    }
+
+    fn resolution_and_component_flags(&self) -> u8 {
+        self.resolution_and_component_flags
+    }
+
+    fn xy_to_latlon(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        Some((self.latitude_at(y), self.longitude_at(x)))
+    }
+
+    fn latlon_to_xy(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let di = self.i_increment();
+        let dj = self.j_increment(self.n_j as usize);
+        let mut x = (lon - self.lo1 as f64 * self.unit()).rem_euclid(360.0) / di;
+        // Grids scanning westward extend to negative x
+        if self.scanning_mode().i_negative() && x > 0.0 {
+            x -= 360.0 / di;
+        }
+        Some((x, (lat - self.la1 as f64 * self.unit()) / dj))
+    }
+
+    fn rotation_at(&self, _x: f64, _y: f64) -> Option<f64> {
+        Some(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::{GridCatalog, GridGeometry};
+    use crate::templates::GridDefinitionTemplate;
+
+    /// 4 x 3 grid of 10° from 20N 0E to 0N 30E with the given scanning mode, whose first point
+    /// is the corner the scanning starts from
+    fn small_grid(scanning_mode: u8) -> GridDefinitionTemplate3_0 {
+        let Some(GridDefinitionTemplate::Template3_0(tmpl)) = GridCatalog::builtin().get(3) else {
+            unreachable!()
+        };
+        let scan = ScanningMode(scanning_mode);
+        let (lo1, lo2) = match scan.i_negative() {
+            true => (30_000_000, 0),
+            false => (0, 30_000_000),
+        };
+        let (la1, la2) = match scan.j_positive() {
+            true => (0, 20_000_000),
+            false => (20_000_000, 0),
+        };
+        GridDefinitionTemplate3_0 {
+            n_i: 4,
+            n_j: 3,
+            la1,
+            lo1,
+            la2,
+            lo2,
+            d_i: 10_000_000,
+            d_j: 10_000_000,
+            scanning_mode,
+            ..tmpl.clone()
+        }
+    }
+
+    #[test]
+    fn global_grid_coordinates() {
+        let Some(GridDefinitionTemplate::Template3_0(tmpl)) = GridCatalog::builtin().get(3) else {
+            unreachable!()
+        };
+        let (latitudes, longitudes) = tmpl.latlon_arrays();
+        assert_eq!(latitudes.len(), 360 * 181);
+        assert_eq!((latitudes[0], longitudes[0]), (90.0, 0.0));
+        assert_eq!((latitudes[361], longitudes[361]), (89.0, 1.0));
+        assert_eq!((latitudes[65159], longitudes[65159]), (-90.0, 359.0));
+        assert!(tmpl.is_periodic());
+    }
+
+    #[test]
+    fn every_scanning_mode_covers_the_same_points() {
+        let expected: Vec<(i64, i64)> = (0..3)
+            .rev()
+            .flat_map(|j| (0..4).map(move |i| (j * 10, i * 10)))
+            .collect();
+        for scanning_mode in [0x00, 0x40, 0x80, 0xc0, 0x20, 0x60, 0x10, 0x50, 0x30] {
+            let grid = small_grid(scanning_mode);
+            let scan = ScanningMode(scanning_mode);
+            let mut points: Vec<(usize, (i64, i64))> = grid
+                .points()
+                .enumerate()
+                .map(|(index, (i, j, lat, lon))| {
+                    assert_eq!(scan.ij(index, 4, 3), (i, j));
+                    assert_eq!(grid.latlon(index), Some((lat, lon)));
+                    let (x, y) = grid.xy_of_index(index);
+                    assert_eq!(grid.xy_to_latlon(x, y), Some((lat, lon)));
+                    // Index in the canonical north-west first, row-major layout
+                    let canonical = scan.canonical_index(index, 4, 3);
+                    (canonical, (lat.round() as i64, lon.round() as i64))
+                })
+                .collect();
+            points.sort();
+            let points: Vec<_> = points.into_iter().map(|(_, p)| p).collect();
+            assert_eq!(points, expected, "mode {:#x}", scanning_mode);
+        }
+    }
+
+    #[test]
+    fn missing_increments_come_from_the_corners() {
+        let grid = GridDefinitionTemplate3_0 {
+            d_i: u32::MAX,
+            d_j: u32::MAX,
+            resolution_and_component_flags: 0,
+            ..small_grid(0)
+        };
+        assert_eq!(grid.latitudes(), [20.0, 10.0, 0.0]);
+        assert_eq!(grid.longitudes(), [0.0, 10.0, 20.0, 30.0]);
+    }
+}
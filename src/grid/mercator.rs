@@ -0,0 +1,137 @@
+use super::{Ellipsoid, MICRO, ScanningMode, StructuredGrid, normalize_longitude};
+use crate::templates::GridDefinitionTemplate3_10;
+
+impl GridDefinitionTemplate3_10 {
+    /// Length in metres on the projection plane of one radian of longitude
+    fn radian_length(&self, ellipsoid: &Ellipsoid) -> f64 {
+        ellipsoid.a * ellipsoid.msfn((self.lad as f64 * MICRO).to_radians())
+    }
+
+    /// Northing in metres of a latitude in degrees, from the equator
+    fn northing(&self, ellipsoid: &Ellipsoid, lat: f64) -> Option<f64> {
+        if lat.abs() >= 90.0 {
+            return None;
+        }
+        Some(-self.radian_length(ellipsoid) * ellipsoid.tsfn(lat.to_radians()).ln())
+    }
+
+    /// Position in metres of the first grid point on the plane of `+proj=merc +lon_0=0`
+    pub(super) fn projected_first_point(&self) -> Option<(f64, f64)> {
+        let ellipsoid = self.ellipsoid()?;
+        let lon = normalize_longitude(self.lo1 as f64 * MICRO);
+        Some((
+            self.radian_length(&ellipsoid) * lon.to_radians(),
+            self.northing(&ellipsoid, self.la1 as f64 * MICRO)?,
+        ))
+    }
+
+    /// Rotates offsets in metres from grid axes to east/north axes by the grid orientation.
+    fn orient(&self, x: f64, y: f64, sign: f64) -> (f64, f64) {
+        if self.orientation_of_the_grid == 0 {
+            return (x, y);
+        }
+        let (sin, cos) = (sign * self.orientation_of_the_grid as f64 * MICRO)
+            .to_radians()
+            .sin_cos();
+        (x * cos - y * sin, x * sin + y * cos)
+    }
+}
+
+impl StructuredGrid for GridDefinitionTemplate3_10 {
+    fn dimensions(&self) -> (usize, usize) {
+        (self.n_i as usize, self.n_j as usize)
+    }
+
+    fn scanning_mode(&self) -> ScanningMode {
+        ScanningMode(self.scanning_mode)
+    }
+
+    fn resolution_and_component_flags(&self) -> u8 {
+        self.resolution_and_component_flags
+    }
+
+    fn xy_to_latlon(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let ellipsoid = self.ellipsoid()?;
+        let k = self.radian_length(&ellipsoid);
+        let (dx, dy) = self.orient(x * self.d_i as f64 * 1e-3, y * self.d_j as f64 * 1e-3, 1.0);
+        let northing = self.northing(&ellipsoid, self.la1 as f64 * MICRO)? + dy;
+        let lat = ellipsoid.phi_from_ts((-northing / k).exp()).to_degrees();
+        let lon = self.lo1 as f64 * MICRO + (dx / k).to_degrees();
+        Some((lat, normalize_longitude(lon)))
+    }
+
+    fn latlon_to_xy(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let ellipsoid = self.ellipsoid()?;
+        let k = self.radian_length(&ellipsoid);
+        let mut dlon = (lon - self.lo1 as f64 * MICRO).rem_euclid(360.0);
+        // Grids scanning westward extend to negative x
+        if self.scanning_mode().i_negative() && dlon > 0.0 {
+            dlon -= 360.0;
+        }
+        let dx = k * dlon.to_radians();
+        let dy =
+            self.northing(&ellipsoid, lat)? - self.northing(&ellipsoid, self.la1 as f64 * MICRO)?;
+        let (dx, dy) = self.orient(dx, dy, -1.0);
+        Some((dx / (self.d_i as f64 * 1e-3), dy / (self.d_j as f64 * 1e-3)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 100 x 80 grid of 20 km from 35N 105E on the Clarke 1866 ellipsoid
+    fn mercator_grid() -> GridDefinitionTemplate3_10 {
+        GridDefinitionTemplate3_10 {
+            shape_of_earth: 7,
+            scale_factor_of_radius: 0,
+            scale_value_of_radius: 0,
+            scale_factor_of_major_axis: 1,
+            scale_value_of_major_axis: 63_782_064,
+            scale_factor_of_minor_axis: 1,
+            scale_value_of_minor_axis: 63_565_838,
+            n_i: 100,
+            n_j: 80,
+            la1: 35_000_000,
+            lo1: 105_000_000,
+            resolution_and_component_flags: 0x30,
+            lad: 0,
+            la2: 0,
+            lo2: 0,
+            scanning_mode: 0x40,
+            orientation_of_the_grid: 0,
+            d_i: 20_000_000,
+            d_j: 20_000_000,
+        }
+    }
+
+    #[test]
+    fn matches_snyder_example() {
+        // Snyder, Map Projections: A Working Manual, appendix A: 35N 75W with lon_0 = 180W
+        let (x, y) = mercator_grid().projected_first_point().unwrap();
+        assert!((x - 11_688_673.7).abs() < 0.1, "{}", x);
+        assert!((y - 4_139_145.6).abs() < 0.1, "{}", y);
+    }
+
+    #[test]
+    fn grid_points_round_trip() {
+        let grid = mercator_grid();
+        let (lat, lon) = grid.xy_to_latlon(0.0, 0.0).unwrap();
+        assert!((lat - 35.0).abs() < 1e-9 && (lon - 105.0).abs() < 1e-9);
+        for (x, y) in [(1.0, 0.0), (0.0, 1.0), (99.0, 79.0), (3.5, -12.25)] {
+            let (lat, lon) = grid.xy_to_latlon(x, y).unwrap();
+            let (x1, y1) = grid.latlon_to_xy(lat, lon).unwrap();
+            assert!(
+                (x1 - x).abs() < 1e-9 && (y1 - y).abs() < 1e-9,
+                "({}, {})",
+                x,
+                y
+            );
+        }
+        // Rows are parallels and columns meridians
+        let (lat, lon) = grid.xy_to_latlon(0.0, 10.0).unwrap();
+        assert!(lat > 35.0 && (lon - 105.0).abs() < 1e-12);
+        let (lat, _) = grid.xy_to_latlon(10.0, 0.0).unwrap();
+        assert!((lat - 35.0).abs() < 1e-12);
+    }
+}
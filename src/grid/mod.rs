@@ -0,0 +1,684 @@
+//! Geometry of the grids described in section 3
+//!
+//! [`GridGeometry`] gives the position on the earth of every data point. Grids made of `ni` x
+//! `nj` points on a projection plane implement [`StructuredGrid`] instead and get
+//! [`GridGeometry`] for free.
+
+mod catalog;
+pub mod crs;
+mod curvilinear;
+pub mod earth;
+mod gaussian;
+mod lambert;
+mod lambert_azimuthal;
+mod latlon;
+mod mercator;
+mod neighbours;
+mod polar_stereographic;
+mod polygon;
+mod quasi_regular;
+mod rotated;
+pub mod scanning;
+mod space_view;
+mod subset;
+mod unstructured;
+pub mod wind;
+
+pub use catalog::*;
+pub use curvilinear::*;
+pub use earth::*;
+pub use gaussian::*;
+pub use polygon::*;
+pub use quasi_regular::*;
+pub use scanning::*;
+pub use subset::*;
+pub use unstructured::*;
+pub use wind::*;
+
+use std::io::Read;
+use std::sync::Arc;
+
+use crate::message::GridDefinitionSectionHeader;
+use crate::templates::{GribRead, GridDefinitionTemplate};
+use crate::{Error, Result};
+
+/// Position of the data points of a grid on the earth
+pub trait GridGeometry {
+    /// Number of data points
+    fn number_of_points(&self) -> usize;
+
+    /// Latitude and longitude in degrees of the data point at `index` (in data order).
+    ///
+    /// Returns None for points that don't lie on the earth.
+    fn latlon(&self, index: usize) -> Option<(f64, f64)>;
+
+    /// Counter-clockwise angle in radians from local east to the +x axis of the grid at the data
+    /// point at `index`.
+    ///
+    /// This is the angle needed to turn grid-relative vector components into earth-relative
+    /// ones. Returns None where the angle is undefined.
+    fn grid_rotation(&self, index: usize) -> Option<f64>;
+
+    /// Whether vector components (u, v) are resolved relative to the grid axes rather than to
+    /// easterly and northerly directions (Flag Table 3.3, bit 5).
+    fn uv_relative_to_grid(&self) -> bool;
+}
+
+/// Grid of `ni` x `nj` points laid out at regular intervals on a projection plane
+///
+/// Positions on the plane are expressed as `(x, y)` offsets from the first grid point, in grid
+/// units along the +x (east) and +y (north) axes. Which data value sits at which offset is
+/// defined by the scanning mode.
+pub trait StructuredGrid {
+    /// Number of points along the x axis (`ni`) and the y axis (`nj`)
+    fn dimensions(&self) -> (usize, usize);
+
+    fn scanning_mode(&self) -> ScanningMode;
+
+    /// Resolution and component flags (Flag Table 3.3)
+    fn resolution_and_component_flags(&self) -> u8;
+
+    /// Latitude and longitude in degrees of the position `(x, y)` on the plane
+    fn xy_to_latlon(&self, x: f64, y: f64) -> Option<(f64, f64)>;
+
+    /// Position on the plane of the latitude and longitude in degrees
+    fn latlon_to_xy(&self, lat: f64, lon: f64) -> Option<(f64, f64)>;
+
+    /// Counter-clockwise angle in radians from local east to the +x axis at `(x, y)`.
+    ///
+    /// The default implementation differentiates [`StructuredGrid::xy_to_latlon`] numerically.
+    fn rotation_at(&self, x: f64, y: f64) -> Option<f64> {
+        const H: f64 = 1e-3;
+        let (lat0, lon0) = self.xy_to_latlon(x - H, y)?;
+        let (lat1, lon1) = self.xy_to_latlon(x + H, y)?;
+        let dlon = (lon1 - lon0 + 540.0).rem_euclid(360.0) - 180.0;
+        let east = dlon * ((lat0 + lat1) / 2.0).to_radians().cos();
+        let north = lat1 - lat0;
+        if east == 0.0 && north == 0.0 {
+            return None;
+        }
+        Some(north.atan2(east))
+    }
+
+    /// Whether the rows go around the globe, so that the point after the last one of a row is
+    /// the first one
+    fn is_periodic(&self) -> bool {
+        let (ni, _) = self.dimensions();
+        let (x, _) = self.scanning_mode().xy(ni as f64, 0.0);
+        match (self.xy_to_latlon(0.0, 0.0), self.xy_to_latlon(x, 0.0)) {
+            (Some((lat0, lon0)), Some((lat1, lon1))) => {
+                let dlon = (lon1 - lon0 + 180.0).rem_euclid(360.0) - 180.0;
+                (lat1 - lat0).abs() < 1e-6 && dlon.abs() < 1e-6
+            }
+            _ => false,
+        }
+    }
+
+    /// Offsets `(x, y)` of the data point at `index`
+    fn xy_of_index(&self, index: usize) -> (f64, f64) {
+        let (ni, nj) = self.dimensions();
+        let scan = self.scanning_mode();
+        let (i, j) = scan.ij(index, ni, nj);
+        scan.xy(i as f64, j as f64)
+    }
+}
+
+impl<T: StructuredGrid> GridGeometry for T {
+    fn number_of_points(&self) -> usize {
+        let (ni, nj) = self.dimensions();
+        ni * nj
+    }
+
+    fn latlon(&self, index: usize) -> Option<(f64, f64)> {
+        let (x, y) = self.xy_of_index(index);
+        self.xy_to_latlon(x, y)
+    }
+
+    fn grid_rotation(&self, index: usize) -> Option<f64> {
+        let (x, y) = self.xy_of_index(index);
+        self.rotation_at(x, y)
+    }
+
+    fn uv_relative_to_grid(&self) -> bool {
+        self.resolution_and_component_flags() & 0x08 != 0
+    }
+}
+
+/// The templates that are structured grids on their own, as the trait object the caller expects
+macro_rules! structured_template {
+    ($template:expr) => {
+        Some(match $template {
+            GridDefinitionTemplate::Template3_0(tmpl) if !tmpl.is_quasi_regular() => tmpl,
+            GridDefinitionTemplate::Template3_1(tmpl) => tmpl,
+            GridDefinitionTemplate::Template3_10(tmpl) => tmpl,
+            GridDefinitionTemplate::Template3_20(tmpl) => tmpl,
+            GridDefinitionTemplate::Template3_30(tmpl) => tmpl,
+            GridDefinitionTemplate::Template3_40(tmpl) if !tmpl.is_reduced() => tmpl,
+            GridDefinitionTemplate::Template3_90(tmpl) => tmpl,
+            GridDefinitionTemplate::Template3_140(tmpl) => tmpl,
+            _ => return None,
+        })
+    };
+}
+
+impl GridDefinitionTemplate {
+    /// The template as a [`StructuredGrid`], if it is one
+    pub fn as_structured(&self) -> Option<&dyn StructuredGrid> {
+        structured_template!(self)
+    }
+
+    /// The template as a [`GridGeometry`], if the template alone is enough to locate the points
+    ///
+    /// Quasi-regular and reduced grids also need the numbers of points per row that follow the
+    /// template, and unstructured and curvilinear grids need external coordinates; see
+    /// [`GridDefinition`].
+    pub fn as_geometry(&self) -> Option<&dyn GridGeometry> {
+        structured_template!(self)
+    }
+}
+
+/// Content of section 3: the template and the optional list of numbers of points
+#[derive(Debug)]
+pub struct GridDefinition {
+    pub header: GridDefinitionSectionHeader,
+    pub template: GridDefinitionTemplate,
+    /// Number of points along every row (or column), for grids whose rows have different
+    /// lengths. Empty when the section has no such list, or when the list holds the latitudes
+    /// of the rows (see [`GridDefinition::quasi_regular`]).
+    pub number_of_points: Vec<u32>,
+    quasi_regular: Option<QuasiRegularGrid>,
+    unstructured: Option<Arc<UnstructuredGrid>>,
+    curvilinear: Option<Arc<CurvilinearGrid>>,
+}
+
+impl GridDefinition {
+    /// Reads the body of section 3 following its header.
+    ///
+    /// Predefined grids are looked up in [`GridCatalog::builtin`].
+    pub fn read<R: Read>(header: GridDefinitionSectionHeader, reader: &mut R) -> Result<Self> {
+        Self::read_with_catalog(header, reader, GridCatalog::builtin())
+    }
+
+    /// Reads the body of section 3 following its header, looking up predefined grids in
+    /// `catalog`.
+    ///
+    /// Sections of a predefined grid (source of grid definition 1) are looked up by the number
+    /// the key of the catalog finds in the header; see [`GridCatalog`]. Sections whose grid
+    /// definition does not apply (source 255) have no grid and give
+    /// [`Error::UnsupportedData`].
+    pub fn read_with_catalog<R: Read>(
+        header: GridDefinitionSectionHeader,
+        reader: &mut R,
+        catalog: &GridCatalog,
+    ) -> Result<Self> {
+        let mut reader = reader.take(header.body_len() as u64);
+        let template = match header.source_of_grid_definition {
+            0 => GridDefinitionTemplate::read(header.template_number, &mut reader)?,
+            1 => {
+                let number = catalog.grid_number(&header).ok_or_else(|| {
+                    Error::UnsupportedData(
+                        "number of the predefined grid is not given in section 3".to_string(),
+                    )
+                })?;
+                let template = catalog.get(number).ok_or_else(|| {
+                    Error::UnsupportedData(format!(
+                        "predefined grid {} is not in the catalog",
+                        number
+                    ))
+                })?;
+                let points = template.as_geometry().map(|grid| grid.number_of_points());
+                if points != Some(header.number_of_data_points as usize) {
+                    return Err(Error::InvalidData(format!(
+                        "predefined grid {} doesn't have {} data points",
+                        number, header.number_of_data_points
+                    )));
+                }
+                std::io::copy(&mut reader, &mut std::io::sink())?;
+                template.clone()
+            }
+            255 => {
+                return Err(Error::UnsupportedData(
+                    "a grid definition does not apply to this product".to_string(),
+                ));
+            }
+            source => {
+                return Err(Error::UnsupportedData(format!(
+                    "source of grid definition {} is not supported",
+                    source
+                )));
+            }
+        };
+
+        // List at the end of the section, interpreted after Code Table 3.11
+        let octets = header.number_of_octects_for_number_of_points as u64;
+        let interpretation = header.interpretation_of_number_of_points;
+        let list = match (octets, interpretation) {
+            (0, _) | (_, 0) => vec![],
+            (1 | 2 | 4, 1..=3) => (0..reader.limit() / octets)
+                .map(|_| {
+                    Ok(match octets {
+                        1 => reader.read_grib_value::<u8>()? as u32,
+                        2 => reader.read_grib_value::<u16>()? as u32,
+                        _ => reader.read_grib_value::<u32>()?,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?,
+            (1 | 2 | 4, interpretation) => {
+                return Err(Error::UnsupportedData(format!(
+                    "interpretation {} of the list of numbers of points is not supported",
+                    interpretation
+                )));
+            }
+            (octets, _) => {
+                return Err(Error::InvalidData(format!(
+                    "number of octets for the list of numbers of points must be 1, 2 or 4, but got {}",
+                    octets
+                )));
+            }
+        };
+
+        let (quasi_regular, number_of_points) = match (&template, interpretation) {
+            // The list holds the latitude of every row, as signed values of the octet width
+            (GridDefinitionTemplate::Template3_0(tmpl), 3) => {
+                let sign = 1u32 << (octets * 8 - 1);
+                let latitudes: Vec<i32> = list
+                    .iter()
+                    .map(|&v| match v & sign {
+                        0 => v as i32,
+                        _ => -((v & !sign) as i32),
+                    })
+                    .collect();
+                (Some(tmpl.grid_at_latitudes(&latitudes)?), vec![])
+            }
+            (_, 3) => {
+                return Err(Error::UnsupportedData(format!(
+                    "row latitudes are not supported for template 3.{}",
+                    header.template_number
+                )));
+            }
+            (GridDefinitionTemplate::Template3_0(tmpl), _) if tmpl.is_quasi_regular() => {
+                let grid = tmpl.quasi_regular_grid(&list, interpretation == 1)?;
+                (Some(grid), list)
+            }
+            (GridDefinitionTemplate::Template3_40(tmpl), _) if tmpl.is_reduced() => {
+                let grid = tmpl.reduced_grid(&list, interpretation == 1)?;
+                (Some(grid), list)
+            }
+            _ => (None, list),
+        };
+        if let Some(grid) = &quasi_regular
+            && grid.number_of_points() != header.number_of_data_points as usize
+        {
+            return Err(Error::InvalidData(format!(
+                "rows have {} points in total, but the grid has {} data points",
+                grid.number_of_points(),
+                header.number_of_data_points
+            )));
+        }
+
+        Ok(Self {
+            header,
+            template,
+            number_of_points,
+            quasi_regular,
+            unstructured: None,
+            curvilinear: None,
+        })
+    }
+
+    /// Attaches the coordinates of an unstructured grid (template 3.101) from `provider`.
+    ///
+    /// Does nothing for other templates. Fails if the provider doesn't know the grid or if its
+    /// number of points doesn't match the section.
+    pub fn resolve_coordinates(&mut self, provider: &dyn CoordinateProvider) -> Result<()> {
+        let GridDefinitionTemplate::Template3_101(tmpl) = &self.template else {
+            return Ok(());
+        };
+        let grid = provider.coordinates(&tmpl.uuid_of_h_grid).ok_or_else(|| {
+            Error::InvalidData(format!(
+                "no coordinates are registered for the grid {}",
+                tmpl.uuid_string()
+            ))
+        })?;
+        if grid.number_of_points() != self.header.number_of_data_points as usize {
+            return Err(Error::InvalidData(format!(
+                "the grid {} has {} points, but the section has {} data points",
+                tmpl.uuid_string(),
+                grid.number_of_points(),
+                self.header.number_of_data_points
+            )));
+        }
+        self.unstructured = Some(grid);
+        Ok(())
+    }
+
+    /// Coordinates of an unstructured grid, once resolved
+    pub fn unstructured(&self) -> Option<&UnstructuredGrid> {
+        self.unstructured.as_deref()
+    }
+
+    /// Attaches the coordinates of a curvilinear grid (template 3.204), built from the companion
+    /// latitude and longitude fields with
+    /// [`GridDefinitionTemplate3_204::curvilinear_grid`](crate::templates::GridDefinitionTemplate3_204::curvilinear_grid).
+    ///
+    /// The same grid can be shared by all the fields on it.
+    pub fn attach_curvilinear(&mut self, grid: Arc<CurvilinearGrid>) -> Result<()> {
+        let GridDefinitionTemplate::Template3_204(tmpl) = &self.template else {
+            return Err(Error::InvalidData(format!(
+                "coordinates can only be attached to template 3.204, but the grid uses 3.{}",
+                self.header.template_number
+            )));
+        };
+        let dimensions = (tmpl.n_i as usize, tmpl.n_j as usize);
+        if grid.dimensions() != dimensions || grid.scanning_mode().0 != tmpl.scanning_mode {
+            return Err(Error::InvalidData(format!(
+                "coordinates of a {} x {} grid don't fit a {} x {} grid",
+                grid.dimensions().0,
+                grid.dimensions().1,
+                dimensions.0,
+                dimensions.1
+            )));
+        }
+        self.curvilinear = Some(grid);
+        Ok(())
+    }
+
+    /// Coordinates of a curvilinear grid, once attached
+    pub fn curvilinear(&self) -> Option<&CurvilinearGrid> {
+        self.curvilinear.as_deref()
+    }
+
+    /// Geometry of a grid whose rows have different numbers of points, or whose rows are at
+    /// listed latitudes
+    pub fn quasi_regular(&self) -> Option<&QuasiRegularGrid> {
+        self.quasi_regular.as_ref()
+    }
+
+    /// The grid as a [`StructuredGrid`], if it is one
+    ///
+    /// Grids whose rows are at listed latitudes are not, even with rows of equal length.
+    pub fn as_structured(&self) -> Option<&dyn StructuredGrid> {
+        match self.quasi_regular {
+            Some(_) => None,
+            None => self.template.as_structured(),
+        }
+    }
+
+    /// Reorders decoded values into the canonical layout (north-up, west to east, row-major);
+    /// see [`ScanningMode::to_canonical`].
+    ///
+    /// Only grids with rows of equal length can be reordered.
+    pub fn to_canonical<T: Copy>(&self, values: &[T]) -> Result<(Vec<T>, CanonicalTransform)> {
+        let (scan, (ni, nj)) = match (&self.template, self.as_structured()) {
+            (_, Some(grid)) => (grid.scanning_mode(), grid.dimensions()),
+            (GridDefinitionTemplate::Template3_204(tmpl), None) => (
+                ScanningMode(tmpl.scanning_mode),
+                (tmpl.n_i as usize, tmpl.n_j as usize),
+            ),
+            _ => {
+                return Err(Error::UnsupportedData(format!(
+                    "values on grids of template 3.{} can't be reordered",
+                    self.header.template_number
+                )));
+            }
+        };
+        scan.to_canonical(values, ni, nj)
+    }
+
+    /// The grid as a [`GridGeometry`], if its points can be located
+    pub fn as_geometry(&self) -> Option<&dyn GridGeometry> {
+        if let Some(grid) = &self.quasi_regular {
+            return Some(grid);
+        }
+        if let Some(grid) = &self.unstructured {
+            return Some(grid.as_ref());
+        }
+        if let Some(grid) = &self.curvilinear {
+            return Some(grid.as_ref());
+        }
+        self.template.as_geometry()
+    }
+}
+
+/// Size in degrees of one unit of the angles in the projected grid templates
+pub(crate) const MICRO: f64 = 1e-6;
+
+/// Adds `ellipsoid()` to templates carrying the shape of the earth fields.
+macro_rules! ellipsoid {
+    ($($template:ident),+) => {
+        $(
+            impl crate::templates::$template {
+                pub fn ellipsoid(&self) -> Option<Ellipsoid> {
+                    Ellipsoid::from_shape_of_earth(
+                        self.shape_of_earth,
+                        self.scale_factor_of_radius,
+                        self.scale_value_of_radius,
+                        self.scale_factor_of_major_axis,
+                        self.scale_value_of_major_axis,
+                        self.scale_factor_of_minor_axis,
+                        self.scale_value_of_minor_axis,
+                    )
+                }
+            }
+        )+
+    };
+}
+
+ellipsoid!(
+    GridDefinitionTemplate3_10,
+    GridDefinitionTemplate3_20,
+    GridDefinitionTemplate3_30,
+    GridDefinitionTemplate3_90,
+    GridDefinitionTemplate3_140
+);
+
+/// Size in degrees of one unit of the angles in the latitude/longitude based templates
+///
+/// Angles are in units of `basic_angle / subdivisions_of_basic_angle` degrees, or in
+/// microdegrees when the basic angle is 0 or missing.
+pub(crate) fn angle_unit(basic_angle: u32, subdivisions_of_basic_angle: u32) -> f64 {
+    match (basic_angle, subdivisions_of_basic_angle) {
+        (0 | u32::MAX, _) | (_, 0 | u32::MAX) => MICRO,
+        (basic, subdivisions) => basic as f64 / subdivisions as f64,
+    }
+}
+
+/// Increment in degrees between the `n_i` points of a row going from `lo1` to `lo2` in degrees,
+/// eastward or westward
+///
+/// A row whose first and last longitudes coincide is taken to go once around the globe.
+pub(crate) fn longitude_increment(lo1: f64, lo2: f64, n_i: u32, i_negative: bool) -> f64 {
+    let span = match i_negative {
+        true => (lo1 - lo2).rem_euclid(360.0),
+        false => (lo2 - lo1).rem_euclid(360.0),
+    };
+    match n_i {
+        0 | 1 | u32::MAX => 0.0,
+        n_i if span == 0.0 => 360.0 / n_i as f64,
+        n_i => span / (n_i - 1) as f64,
+    }
+}
+
+/// Brings a longitude in degrees into [-180, 180).
+pub(crate) fn normalize_longitude(lon: f64) -> f64 {
+    (lon + 180.0).rem_euclid(360.0) - 180.0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use super::*;
+
+    /// Octets of a GRIB signed integer in sign-and-magnitude form
+    fn signed(value: i32) -> [u8; 4] {
+        match value {
+            0.. => (value as u32).to_be_bytes(),
+            _ => (value.unsigned_abs() | 0x8000_0000).to_be_bytes(),
+        }
+    }
+
+    /// Body of template 3.0 on the sphere of shape 6, angles in microdegrees
+    fn template_3_0(n_i: u32, n_j: u32, (la1, lo1): (i32, i32), (la2, lo2): (i32, i32)) -> Vec<u8> {
+        let mut body = vec![6, 0];
+        body.extend([0; 4]);
+        body.extend([0; 5]);
+        body.extend([0; 5]);
+        body.extend(n_i.to_be_bytes());
+        body.extend(n_j.to_be_bytes());
+        body.extend(0u32.to_be_bytes());
+        body.extend(u32::MAX.to_be_bytes());
+        body.extend(signed(la1));
+        body.extend(signed(lo1));
+        body.push(0);
+        body.extend(signed(la2));
+        body.extend(signed(lo2));
+        body.extend(u32::MAX.to_be_bytes());
+        body.extend(u32::MAX.to_be_bytes());
+        body.push(0);
+        body
+    }
+
+    /// Reads section 3 made of `body` followed by `list`, written with `octets` octets per entry
+    fn read_section(
+        template_number: u16,
+        number_of_data_points: u32,
+        mut body: Vec<u8>,
+        (octets, interpretation): (u8, u8),
+        list: &[u32],
+    ) -> Result<GridDefinition> {
+        for &value in list {
+            body.extend(&value.to_be_bytes()[4 - octets as usize..]);
+        }
+        let header = GridDefinitionSectionHeader {
+            section_length: 14 + body.len() as u32,
+            source_of_grid_definition: 0,
+            number_of_data_points,
+            number_of_octects_for_number_of_points: octets,
+            interpretation_of_number_of_points: interpretation,
+            template_number,
+        };
+        GridDefinition::read(header, &mut &body[..])
+    }
+
+    fn longitudes(grid: &dyn GridGeometry, range: Range<usize>) -> Vec<f64> {
+        range.map(|index| grid.latlon(index).unwrap().1).collect()
+    }
+
+    #[test]
+    fn rows_of_full_circles() {
+        let body = template_3_0(u32::MAX, 3, (60_000_000, 0), (-60_000_000, 90_000_000));
+        let grid = read_section(0, 16, body, (2, 1), &[4, 8, 4]).unwrap();
+        assert_eq!(grid.number_of_points, [4, 8, 4]);
+        let geometry = grid.as_geometry().unwrap();
+        assert!(grid.quasi_regular().unwrap().is_global());
+        assert_eq!(longitudes(geometry, 0..4), [0.0, 90.0, -180.0, -90.0]);
+        assert_eq!(geometry.latlon(4).unwrap(), (0.0, 0.0));
+        assert_eq!(geometry.latlon(5).unwrap(), (0.0, 45.0));
+    }
+
+    #[test]
+    fn rows_spanning_the_coverage() {
+        let body = template_3_0(u32::MAX, 3, (60_000_000, 0), (-60_000_000, 90_000_000));
+        let grid = read_section(0, 16, body, (1, 2), &[4, 8, 4]).unwrap();
+        let geometry = grid.as_geometry().unwrap();
+        assert!(!grid.quasi_regular().unwrap().is_global());
+        assert_eq!(longitudes(geometry, 0..4), [0.0, 30.0, 60.0, 90.0]);
+        let step = 90.0 / 7.0;
+        assert!((geometry.latlon(5).unwrap().1 - step).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rows_at_listed_latitudes() {
+        // Latitudes written in 4 octets, sign-and-magnitude
+        let body = template_3_0(4, 3, (60_000_000, 0), (-50_000_000, 270_000_000));
+        let list = [60_000_000, 10_000_000, 0x8000_0000 | 50_000_000];
+        let grid = read_section(0, 12, body, (4, 3), &list).unwrap();
+        assert!(grid.number_of_points.is_empty());
+        assert!(grid.as_structured().is_none());
+        let geometry = grid.as_geometry().unwrap();
+        assert!(grid.quasi_regular().unwrap().is_global());
+        assert_eq!(geometry.latlon(5).unwrap(), (10.0, 90.0));
+        assert_eq!(geometry.latlon(11).unwrap(), (-50.0, -90.0));
+    }
+
+    #[test]
+    fn rejects_invalid_lists() {
+        let body = || template_3_0(u32::MAX, 3, (60_000_000, 0), (-60_000_000, 90_000_000));
+        assert!(matches!(
+            read_section(0, 12, body(), (2, 1), &[4, 8]),
+            Err(Error::InvalidData(_))
+        ));
+        assert!(matches!(
+            read_section(0, 16, body(), (2, 4), &[4, 8, 4]),
+            Err(Error::UnsupportedData(_))
+        ));
+        assert!(matches!(
+            read_section(0, 16, body(), (3, 1), &[4, 8, 4]),
+            Err(Error::InvalidData(_))
+        ));
+        assert!(matches!(
+            read_section(0, 15, body(), (2, 1), &[4, 8, 4]),
+            Err(Error::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn reorders_values_to_the_canonical_layout() {
+        // 3 x 2 grid scanning south to north, then a list of rows of different lengths
+        let mut body = template_3_0(3, 2, (0, 0), (10_000_000, 20_000_000));
+        *body.last_mut().unwrap() = 0x40;
+        let grid = read_section(0, 6, body, (0, 0), &[]).unwrap();
+        let (canonical, transform) = grid.to_canonical(&[3, 4, 5, 0, 1, 2]).unwrap();
+        assert_eq!(canonical, [0, 1, 2, 3, 4, 5]);
+        assert!(transform.flip_y);
+
+        let body = template_3_0(u32::MAX, 3, (60_000_000, 0), (-60_000_000, 90_000_000));
+        let grid = read_section(0, 16, body, (2, 1), &[4, 8, 4]).unwrap();
+        assert!(matches!(
+            grid.to_canonical(&[0; 16]),
+            Err(Error::UnsupportedData(_))
+        ));
+    }
+
+    #[test]
+    fn predefined_grids() {
+        let header = |source_of_grid_definition, number_of_data_points, template_number| {
+            GridDefinitionSectionHeader {
+                section_length: 14,
+                source_of_grid_definition,
+                number_of_data_points,
+                number_of_octects_for_number_of_points: 0,
+                interpretation_of_number_of_points: 0,
+                template_number,
+            }
+        };
+        let grid = GridDefinition::read(header(1, 93 * 65, 211), &mut &[][..]).unwrap();
+        assert_eq!(grid.as_structured().unwrap().dimensions(), (93, 65));
+        assert!(matches!(
+            GridDefinition::read(header(1, 100, 211), &mut &[][..]),
+            Err(Error::InvalidData(_))
+        ));
+        assert!(matches!(
+            GridDefinition::read(header(1, 100, 999), &mut &[][..]),
+            Err(Error::UnsupportedData(_))
+        ));
+        // WMO sets the template number to 65535, leaving the grid number to the centre
+        assert!(matches!(
+            GridDefinition::read(header(1, 93 * 65, u16::MAX), &mut &[][..]),
+            Err(Error::UnsupportedData(_))
+        ));
+        let catalog = GridCatalog::ncep().with_key(|header| match header.number_of_data_points {
+            6045 => Some(211),
+            _ => None,
+        });
+        let grid =
+            GridDefinition::read_with_catalog(header(1, 93 * 65, u16::MAX), &mut &[][..], &catalog)
+                .unwrap();
+        assert_eq!(grid.as_structured().unwrap().dimensions(), (93, 65));
+        assert!(matches!(
+            GridDefinition::read(header(255, 0, u16::MAX), &mut &[][..]),
+            Err(Error::UnsupportedData(_))
+        ));
+    }
+}
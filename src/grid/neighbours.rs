@@ -0,0 +1,305 @@
+use super::{GridDefinition, great_circle_distance};
+
+/// Radius in metres of the sphere used when the shape of the earth is unknown
+const DEFAULT_RADIUS: f64 = 6_371_229.0;
+
+impl GridDefinition {
+    /// Radius in metres of the sphere used for distances on this grid
+    pub fn earth_radius(&self) -> f64 {
+        self.template
+            .earth_shape()
+            .map(|shape| shape.ellipsoid().mean_radius())
+            .unwrap_or(DEFAULT_RADIUS)
+    }
+
+    /// Grid points around the latitude and longitude in degrees, with their weights for
+    /// bilinear interpolation
+    ///
+    /// Structured grids give the four corners of the enclosing cell on the projection plane;
+    /// quasi-regular grids give two points on each of the rows on either side. None outside the
+    /// grid, or for grids without such a layout (unstructured and curvilinear grids).
+    pub fn surrounding_points(&self, lat: f64, lon: f64) -> Option<Vec<(usize, f64)>> {
+        const EPSILON: f64 = 1e-9;
+        if let Some(grid) = self.quasi_regular() {
+            let mut points = Vec::with_capacity(4);
+            for (row, row_weight) in grid.bracketing_rows(lat)? {
+                if row_weight == 0.0 {
+                    continue;
+                }
+                for (index, weight) in grid.bracketing_points(row, lon)? {
+                    points.push((index, row_weight * weight));
+                }
+            }
+            return Some(points);
+        }
+
+        let grid = self.as_structured()?;
+        let (ni, nj) = grid.dimensions();
+        let scan = grid.scanning_mode();
+        let (x, y) = grid.latlon_to_xy(lat, lon)?;
+        let (i, j) = scan.ij_from_xy(x, y);
+        let periodic = grid.is_periodic();
+        let i = if periodic { i.rem_euclid(ni as f64) } else { i };
+        let in_range = |p: f64, n: usize| p > -EPSILON && p < (n - 1) as f64 + EPSILON;
+        let inside = i.is_finite() && j.is_finite() && in_range(j, nj);
+        if !inside || !(periodic || in_range(i, ni)) {
+            return None;
+        }
+        // Lower corner of the cell and the fractional position inside it, along one axis
+        let corner = |p: f64, n: usize, wrap: bool| -> (usize, usize, f64) {
+            let last = if wrap { n - 1 } else { n.saturating_sub(2) };
+            let p0 = (p.max(0.0).floor() as usize).min(last);
+            let p1 = if wrap {
+                (p0 + 1) % n
+            } else {
+                (p0 + 1).min(n - 1)
+            };
+            (p0, p1, (p - p0 as f64).clamp(0.0, 1.0))
+        };
+        let (i0, i1, wi) = corner(i, ni, periodic);
+        let (j0, j1, wj) = corner(j, nj, false);
+        Some(vec![
+            (scan.index(i0, j0, ni, nj), (1.0 - wi) * (1.0 - wj)),
+            (scan.index(i1, j0, ni, nj), wi * (1.0 - wj)),
+            (scan.index(i0, j1, ni, nj), (1.0 - wi) * wj),
+            (scan.index(i1, j1, ni, nj), wi * wj),
+        ])
+    }
+
+    /// The grid point whose cell contains the latitude and longitude in degrees
+    ///
+    /// Cells of structured grids extend half a grid length on each side of their point, so this
+    /// also covers the border half-cells that [`Self::surrounding_points`] leaves out. Grids
+    /// without a regular layout fall back to [`Self::nearest_points`].
+    pub fn nearest_point(&self, lat: f64, lon: f64) -> Option<usize> {
+        if self.quasi_regular().is_some() {
+            let points = self.surrounding_points(lat, lon)?;
+            return points
+                .into_iter()
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(index, _)| index);
+        }
+        let Some(grid) = self.as_structured() else {
+            return self
+                .nearest_points(lat, lon, 1)
+                .first()
+                .map(|&(index, _)| index);
+        };
+        let (ni, nj) = grid.dimensions();
+        let scan = grid.scanning_mode();
+        let (x, y) = grid.latlon_to_xy(lat, lon)?;
+        let (i, j) = scan.ij_from_xy(x, y);
+        let (i, j) = (i.round(), j.round());
+        let i = if grid.is_periodic() {
+            i.rem_euclid(ni as f64)
+        } else {
+            i
+        };
+        let in_range = |p: f64, n: usize| p >= 0.0 && p < n as f64;
+        if !(in_range(i, ni) && in_range(j, nj)) {
+            return None;
+        }
+        Some(scan.index(i as usize, j as usize, ni, nj))
+    }
+
+    /// The `k` grid points closest to the latitude and longitude in degrees, with their
+    /// distances in metres, closest first
+    ///
+    /// Unstructured grids are searched with a tree of their points, built on the first search.
+    /// On other grids this checks every point, so it is meant for locations outside the cells
+    /// that [`Self::surrounding_points`] finds.
+    pub fn nearest_points(&self, lat: f64, lon: f64, k: usize) -> Vec<(usize, f64)> {
+        let radius = self.earth_radius();
+        if let Some(grid) = self.unstructured() {
+            return grid
+                .tree()
+                .nearest(lat, lon, k)
+                .into_iter()
+                .map(|(index, chord)| (index, 2.0 * radius * (chord / 2.0).min(1.0).asin()))
+                .collect();
+        }
+        let Some(geometry) = self.as_geometry() else {
+            return vec![];
+        };
+        let mut nearest: Vec<(usize, f64)> = Vec::with_capacity(k + 1);
+        for index in 0..geometry.number_of_points() {
+            let Some((plat, plon)) = geometry.latlon(index) else {
+                continue;
+            };
+            let distance = great_circle_distance(lat, lon, plat, plon, radius);
+            if nearest.len() == k && nearest.last().is_none_or(|&(_, d)| distance >= d) {
+                continue;
+            }
+            let at = nearest.partition_point(|&(_, d)| d <= distance);
+            nearest.insert(at, (index, distance));
+            nearest.truncate(k);
+        }
+        nearest
+    }
+}
+
+/// k-d tree of points on the unit sphere, for nearest-neighbour searches on grids without a
+/// regular layout
+///
+/// The tree is stored implicitly: the median point of every slice splits it along one of the
+/// axes, cycling through x, y and z.
+#[derive(Debug, Clone)]
+pub(crate) struct PointTree {
+    /// Position of every point on the unit sphere, with its index in data order
+    nodes: Vec<([f64; 3], usize)>,
+}
+
+impl PointTree {
+    /// Builds the tree of points given by their latitude and longitude in degrees, in data
+    /// order. Points with missing coordinates are left out.
+    pub(crate) fn new(points: impl Iterator<Item = (f64, f64)>) -> Self {
+        let mut nodes: Vec<([f64; 3], usize)> = points
+            .enumerate()
+            .filter(|(_, (lat, lon))| lat.is_finite() && lon.is_finite())
+            .map(|(index, (lat, lon))| (unit_vector(lat, lon), index))
+            .collect();
+        split(&mut nodes, 0);
+        Self { nodes }
+    }
+
+    /// The `k` points closest to the latitude and longitude in degrees, with their chord
+    /// distances on the unit sphere, closest first
+    pub(crate) fn nearest(&self, lat: f64, lon: f64, k: usize) -> Vec<(usize, f64)> {
+        let mut nearest = Vec::with_capacity(k + 1);
+        if k > 0 && lat.is_finite() && lon.is_finite() {
+            search(&self.nodes, 0, &unit_vector(lat, lon), k, &mut nearest);
+        }
+        nearest
+            .into_iter()
+            .map(|(index, squared)| (index, f64::sqrt(squared)))
+            .collect()
+    }
+}
+
+fn unit_vector(lat: f64, lon: f64) -> [f64; 3] {
+    let (sin_lat, cos_lat) = lat.to_radians().sin_cos();
+    let (sin_lon, cos_lon) = lon.to_radians().sin_cos();
+    [cos_lat * cos_lon, cos_lat * sin_lon, sin_lat]
+}
+
+/// Orders `nodes` into a tree whose root splits them along `axis`
+fn split(nodes: &mut [([f64; 3], usize)], axis: usize) {
+    if nodes.len() <= 1 {
+        return;
+    }
+    let mid = nodes.len() / 2;
+    nodes.select_nth_unstable_by(mid, |a, b| a.0[axis].total_cmp(&b.0[axis]));
+    let (below, above) = nodes.split_at_mut(mid);
+    split(below, (axis + 1) % 3);
+    split(&mut above[1..], (axis + 1) % 3);
+}
+
+/// Adds the points of the tree `nodes` closer to `target` than the `k` in `nearest`, kept as
+/// indices and squared distances, closest first
+fn search(
+    nodes: &[([f64; 3], usize)],
+    axis: usize,
+    target: &[f64; 3],
+    k: usize,
+    nearest: &mut Vec<(usize, f64)>,
+) {
+    if nodes.is_empty() {
+        return;
+    }
+    let mid = nodes.len() / 2;
+    let (point, index) = nodes[mid];
+    let squared: f64 = (0..3).map(|a| (point[a] - target[a]).powi(2)).sum();
+    // Ties go to the point first in data order
+    let closer = |&(i, d): &(usize, f64)| d < squared || (d == squared && i < index);
+    if nearest.len() < k || nearest.last().is_some_and(|last| !closer(last)) {
+        let at = nearest.partition_point(closer);
+        nearest.insert(at, (index, squared));
+        nearest.truncate(k);
+    }
+    let delta = target[axis] - point[axis];
+    let (near, far) = match delta < 0.0 {
+        true => (&nodes[..mid], &nodes[mid + 1..]),
+        false => (&nodes[mid + 1..], &nodes[..mid]),
+    };
+    search(near, (axis + 1) % 3, target, k, nearest);
+    if nearest.len() < k || nearest.last().is_some_and(|&(_, d)| delta * delta <= d) {
+        search(far, (axis + 1) % 3, target, k, nearest);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::{predefined_grid, unstructured_grid};
+    use crate::xorshift;
+
+    /// Pseudo-random points spread over the globe
+    fn scattered_points(n: usize) -> (Vec<f64>, Vec<f64>) {
+        let mut random =
+            xorshift(0x2545_f491_4f6c_dd1d).map(|state| (state >> 11) as f64 / (1u64 << 53) as f64);
+        let mut next = move || random.next().unwrap();
+        (0..n)
+            .map(|_| ((2.0 * next() - 1.0).asin().to_degrees(), 360.0 * next()))
+            .unzip()
+    }
+
+    #[test]
+    fn tree_finds_the_nearest_points() {
+        let (latitudes, longitudes) = scattered_points(2000);
+        let grid = unstructured_grid(latitudes.clone(), longitudes.clone());
+        let radius = grid.earth_radius();
+        for (lat, lon) in [(0.0, 0.0), (89.9, 10.0), (-45.0, 179.9), (12.3, -170.0)] {
+            let mut expected: Vec<(usize, f64)> = latitudes
+                .iter()
+                .zip(&longitudes)
+                .map(|(&plat, &plon)| great_circle_distance(lat, lon, plat, plon, radius))
+                .enumerate()
+                .collect();
+            expected.sort_by(|a, b| a.1.total_cmp(&b.1));
+            let nearest = grid.nearest_points(lat, lon, 5);
+            assert_eq!(nearest.len(), 5);
+            for (found, expected) in nearest.iter().zip(&expected) {
+                assert_eq!(found.0, expected.0);
+                assert!(
+                    (found.1 - expected.1).abs() < 1e-3,
+                    "{:?}",
+                    (found, expected)
+                );
+            }
+            assert_eq!(grid.nearest_point(lat, lon), Some(expected[0].0));
+        }
+        assert!(grid.nearest_points(0.0, 0.0, 0).is_empty());
+        assert_eq!(grid.nearest_points(0.0, 0.0, 5000).len(), 2000);
+    }
+
+    #[test]
+    fn surrounding_points_weigh_the_cell_corners() {
+        let grid = predefined_grid(3);
+        // Row 44 is at 46N and row 45 at 45N
+        let points = grid.surrounding_points(45.25, 10.75).unwrap();
+        assert_eq!(
+            points,
+            [
+                (44 * 360 + 10, 0.25 * 0.25),
+                (44 * 360 + 11, 0.75 * 0.25),
+                (45 * 360 + 10, 0.25 * 0.75),
+                (45 * 360 + 11, 0.75 * 0.75),
+            ]
+        );
+        // Cells across the last column wrap around
+        let points = grid.surrounding_points(0.0, 359.5).unwrap();
+        assert_eq!(points[0], (90 * 360 + 359, 0.5));
+        assert_eq!(points[1], (90 * 360, 0.5));
+    }
+
+    #[test]
+    fn nearest_points_of_regional_grids_cover_border_half_cells() {
+        let grid = predefined_grid(211);
+        let structured = grid.as_structured().unwrap();
+        let (lat, lon) = structured.xy_to_latlon(-0.3, 10.0).unwrap();
+        assert_eq!(grid.surrounding_points(lat, lon), None);
+        assert_eq!(grid.nearest_point(lat, lon), Some(10 * 93));
+        let (lat, lon) = structured.xy_to_latlon(-0.7, 10.0).unwrap();
+        assert_eq!(grid.nearest_point(lat, lon), None);
+    }
+}
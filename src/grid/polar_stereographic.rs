@@ -0,0 +1,164 @@
+use super::{Ellipsoid, MICRO, ScanningMode, StructuredGrid, normalize_longitude};
+use crate::templates::GridDefinitionTemplate3_20;
+
+impl GridDefinitionTemplate3_20 {
+    /// Whether the south pole, rather than the north pole, is on the projection plane
+    pub fn is_south_polar(&self) -> bool {
+        self.projection_centre_flag & 0x80 != 0
+    }
+
+    /// Ratio of the distance from the pole on the plane to Snyder's t
+    fn rho_per_ts(&self, ellipsoid: &Ellipsoid) -> f64 {
+        let lad = (self.lad as f64 * MICRO).abs();
+        if (lad - 90.0).abs() < 1e-9 {
+            let e = ellipsoid.e();
+            2.0 * ellipsoid.a / ((1.0 + e).powf(1.0 + e) * (1.0 - e).powf(1.0 - e)).sqrt()
+        } else {
+            let phi_c = lad.to_radians();
+            ellipsoid.a * ellipsoid.msfn(phi_c) / ellipsoid.tsfn(phi_c)
+        }
+    }
+
+    /// Projects latitude and longitude in degrees to metres on the plane, with the pole at the
+    /// origin.
+    fn forward(&self, ellipsoid: &Ellipsoid, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        // The south polar aspect is the north polar one with latitudes and longitudes negated
+        let sign = if self.is_south_polar() { -1.0 } else { 1.0 };
+        let phi = (sign * lat).to_radians();
+        if phi <= -std::f64::consts::FRAC_PI_2 {
+            return None;
+        }
+        let rho = self.rho_per_ts(ellipsoid) * ellipsoid.tsfn(phi);
+        let (sin, cos) = (sign * (lon - self.lov as f64 * MICRO))
+            .to_radians()
+            .sin_cos();
+        Some((sign * rho * sin, -sign * rho * cos))
+    }
+
+    /// Position in metres of the first grid point on the plane of `+proj=stere`
+    pub(super) fn projected_first_point(&self) -> Option<(f64, f64)> {
+        let ellipsoid = self.ellipsoid()?;
+        self.forward(&ellipsoid, self.la1 as f64 * MICRO, self.lo1 as f64 * MICRO)
+    }
+
+    /// Inverse of `forward`
+    fn inverse(&self, ellipsoid: &Ellipsoid, x: f64, y: f64) -> (f64, f64) {
+        let sign = if self.is_south_polar() { -1.0 } else { 1.0 };
+        let (x, y) = (sign * x, sign * y);
+        let ts = x.hypot(y) / self.rho_per_ts(ellipsoid);
+        let lat = sign * ellipsoid.phi_from_ts(ts).to_degrees();
+        let lon = self.lov as f64 * MICRO + sign * x.atan2(-y).to_degrees();
+        (lat, normalize_longitude(lon))
+    }
+}
+
+impl StructuredGrid for GridDefinitionTemplate3_20 {
+    fn dimensions(&self) -> (usize, usize) {
+        (self.n_x as usize, self.n_y as usize)
+    }
+
+    fn scanning_mode(&self) -> ScanningMode {
+        ScanningMode(self.scanning_mode)
+    }
+
+    fn resolution_and_component_flags(&self) -> u8 {
+        self.resolution_and_component_flags
+    }
+
+    fn xy_to_latlon(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let ellipsoid = self.ellipsoid()?;
+        let (x1, y1) =
+            self.forward(&ellipsoid, self.la1 as f64 * MICRO, self.lo1 as f64 * MICRO)?;
+        Some(self.inverse(
+            &ellipsoid,
+            x1 + x * self.d_x as f64 * 1e-3,
+            y1 + y * self.d_y as f64 * 1e-3,
+        ))
+    }
+
+    fn latlon_to_xy(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let ellipsoid = self.ellipsoid()?;
+        let (x1, y1) =
+            self.forward(&ellipsoid, self.la1 as f64 * MICRO, self.lo1 as f64 * MICRO)?;
+        let (x, y) = self.forward(&ellipsoid, lat, lon)?;
+        Some((
+            (x - x1) / (self.d_x as f64 * 1e-3),
+            (y - y1) / (self.d_y as f64 * 1e-3),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// South polar grid of 50 km from 75S 150E on the International 1924 ellipsoid, true at 71S
+    /// with lov = 100W
+    fn south_polar_grid() -> GridDefinitionTemplate3_20 {
+        GridDefinitionTemplate3_20 {
+            shape_of_earth: 7,
+            scale_factor_of_radius: 0,
+            scale_value_of_radius: 0,
+            scale_factor_of_major_axis: 0,
+            scale_value_of_major_axis: 6_378_388,
+            scale_factor_of_minor_axis: 2,
+            scale_value_of_minor_axis: 635_691_195,
+            n_x: 60,
+            n_y: 60,
+            la1: -75_000_000,
+            lo1: 150_000_000,
+            resolution_and_component_flags: 0x08,
+            lad: -71_000_000,
+            lov: -100_000_000,
+            d_x: 50_000_000,
+            d_y: 50_000_000,
+            projection_centre_flag: 0x80,
+            scanning_mode: 0x40,
+        }
+    }
+
+    #[test]
+    fn matches_snyder_example() {
+        // Snyder, Map Projections: A Working Manual, appendix A
+        let (x, y) = south_polar_grid().projected_first_point().unwrap();
+        assert!((x + 1_540_033.6).abs() < 0.1, "{}", x);
+        assert!((y + 560_526.4).abs() < 0.1, "{}", y);
+    }
+
+    #[test]
+    fn matches_spherical_formula() {
+        // North polar, true at 60N on a sphere: rho = R (1 + sin 60) tan(45 - lat / 2)
+        let grid = GridDefinitionTemplate3_20 {
+            shape_of_earth: 6,
+            scale_value_of_major_axis: 0,
+            scale_value_of_minor_axis: 0,
+            la1: 50_000_000,
+            lo1: 10_000_000,
+            lad: 60_000_000,
+            lov: -20_000_000,
+            projection_centre_flag: 0,
+            ..south_polar_grid()
+        };
+        let rho = 6_371_229.0 * (1.0 + 60f64.to_radians().sin()) * 20f64.to_radians().tan();
+        let (x, y) = grid.projected_first_point().unwrap();
+        assert!((x - rho * 30f64.to_radians().sin()).abs() < 1e-6);
+        assert!((y + rho * 30f64.to_radians().cos()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn grid_points_round_trip() {
+        let grid = south_polar_grid();
+        let (lat, lon) = grid.xy_to_latlon(0.0, 0.0).unwrap();
+        assert!((lat + 75.0).abs() < 1e-9 && (lon - 150.0).abs() < 1e-9);
+        for (x, y) in [(1.0, 0.0), (0.0, 1.0), (59.0, 59.0), (30.5, -2.0)] {
+            let (lat, lon) = grid.xy_to_latlon(x, y).unwrap();
+            let (x1, y1) = grid.latlon_to_xy(lat, lon).unwrap();
+            assert!(
+                (x1 - x).abs() < 1e-6 && (y1 - y).abs() < 1e-6,
+                "({}, {})",
+                x,
+                y
+            );
+        }
+    }
+}
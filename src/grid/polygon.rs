@@ -0,0 +1,298 @@
+use super::{BoundingBox, GridDefinition, StructuredGrid};
+
+/// Polygon of latitudes and longitudes in degrees, with optional holes
+///
+/// Rings are lists of `(lat, lon)` vertices, closed or not. Longitudes are unwrapped when the
+/// polygon is built, so a polygon may cross the antimeridian as long as it spans less than 360
+/// degrees of longitude.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    pub exterior: Vec<(f64, f64)>,
+    pub holes: Vec<Vec<(f64, f64)>>,
+}
+
+impl Polygon {
+    pub fn new(exterior: Vec<(f64, f64)>, holes: Vec<Vec<(f64, f64)>>) -> Self {
+        let exterior = unwrap_ring(exterior, None);
+        let reference = exterior.first().map(|&(_, lon)| lon);
+        let holes = holes
+            .into_iter()
+            .map(|hole| unwrap_ring(hole, reference))
+            .collect();
+        Self { exterior, holes }
+    }
+
+    /// Smallest latitude/longitude box containing the polygon
+    pub fn bounding_box(&self) -> BoundingBox {
+        let (mut south, mut north) = (f64::INFINITY, f64::NEG_INFINITY);
+        let (mut west, mut east) = (f64::INFINITY, f64::NEG_INFINITY);
+        for &(lat, lon) in &self.exterior {
+            (south, north) = (south.min(lat), north.max(lat));
+            (west, east) = (west.min(lon), east.max(lon));
+        }
+        BoundingBox::new(south, north, west, east)
+    }
+
+    /// Whether the point is inside the polygon and outside its holes
+    pub fn contains(&self, lat: f64, lon: f64) -> bool {
+        let Some(&(_, west)) = self.exterior.iter().min_by(|a, b| a.1.total_cmp(&b.1)) else {
+            return false;
+        };
+        let lon = west + (lon - west).rem_euclid(360.0);
+        ring_contains(&self.exterior, lat, lon)
+            && !self.holes.iter().any(|hole| ring_contains(hole, lat, lon))
+    }
+
+    /// Area in square metres on a sphere of `radius` metres
+    pub fn area(&self, radius: f64) -> f64 {
+        let holes: f64 = self.holes.iter().map(|hole| ring_area(hole, radius)).sum();
+        (ring_area(&self.exterior, radius) - holes).max(0.0)
+    }
+
+    /// Fraction of the area of a convex ring, such as a grid cell, inside the polygon and
+    /// outside its holes
+    ///
+    /// Edges are straight lines on the equal-area cylindrical projection, as in
+    /// [`Polygon::area`]; see [`intersection_area`].
+    pub(crate) fn fraction_of(&self, cell: &[(f64, f64)]) -> f64 {
+        let bbox = self.bounding_box();
+        let cell = unwrap_ring(cell.to_vec(), Some((bbox.west + bbox.east) / 2.0));
+        let area = ring_area(&cell, 1.0);
+        if area <= 0.0 {
+            return 0.0;
+        }
+        let holes: f64 = self
+            .holes
+            .iter()
+            .map(|hole| intersection_area(hole, &cell, 1.0))
+            .sum();
+        ((intersection_area(&self.exterior, &cell, 1.0) - holes) / area).clamp(0.0, 1.0)
+    }
+
+    /// Rings as GeoJSON coordinates: closed rings of `[lon, lat]`, the exterior counterclockwise
+    /// and the holes clockwise
+    pub fn geojson_coordinates(&self) -> Vec<Vec<[f64; 2]>> {
+        let ring = |ring: &[(f64, f64)], counterclockwise: bool| {
+            let mut coordinates: Vec<[f64; 2]> =
+                ring.iter().map(|&(lat, lon)| [lon, lat]).collect();
+            let area: f64 = (0..coordinates.len())
+                .map(|k| {
+                    let ([x1, y1], [x2, y2]) =
+                        (coordinates[k], coordinates[(k + 1) % coordinates.len()]);
+                    x1 * y2 - x2 * y1
+                })
+                .sum();
+            if (area > 0.0) != counterclockwise {
+                coordinates.reverse();
+            }
+            if let Some(&first) = coordinates.first()
+                && coordinates.last() != Some(&first)
+            {
+                coordinates.push(first);
+            }
+            coordinates
+        };
+        std::iter::once(ring(&self.exterior, true))
+            .chain(self.holes.iter().map(|hole| ring(hole, false)))
+            .collect()
+    }
+}
+
+/// Shifts the longitudes of a ring so that consecutive vertices are less than 180 degrees
+/// apart, starting within 180 degrees of `reference`
+pub(crate) fn unwrap_ring(ring: Vec<(f64, f64)>, reference: Option<f64>) -> Vec<(f64, f64)> {
+    let mut previous = reference;
+    ring.into_iter()
+        .map(|(lat, lon)| {
+            let lon = match previous {
+                Some(p) => p + (lon - p + 180.0).rem_euclid(360.0) - 180.0,
+                None => lon,
+            };
+            previous = Some(lon);
+            (lat, lon)
+        })
+        .collect()
+}
+
+/// Even-odd test of a point against a ring
+pub(crate) fn ring_contains(ring: &[(f64, f64)], lat: f64, lon: f64) -> bool {
+    let mut inside = false;
+    for (k, &(lat1, lon1)) in ring.iter().enumerate() {
+        let (lat2, lon2) = ring[(k + 1) % ring.len()];
+        if (lat1 > lat) != (lat2 > lat) && lon < lon1 + (lat - lat1) / (lat2 - lat1) * (lon2 - lon1)
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Area in square metres enclosed by a ring on a sphere of `radius` metres
+///
+/// Edges are taken as straight lines on an equal-area cylindrical projection, so areas of cells
+/// bounded by meridians and parallels are exact.
+pub(crate) fn ring_area(ring: &[(f64, f64)], radius: f64) -> f64 {
+    let n = ring.len();
+    if n < 3 {
+        return 0.0;
+    }
+    let sum: f64 = (0..n)
+        .map(|k| {
+            let (lat, _) = ring[k];
+            let (_, next) = ring[(k + 1) % n];
+            let (_, previous) = ring[(k + n - 1) % n];
+            // Longitudes relative to the vertex, so that rings crossing the antimeridian work
+            let (_, lon) = ring[k];
+            let dlon = |other: f64| (other - lon + 180.0).rem_euclid(360.0) - 180.0;
+            (dlon(next) - dlon(previous)).to_radians() * lat.to_radians().sin()
+        })
+        .sum();
+    (sum * radius * radius / 2.0).abs()
+}
+
+/// Area in square metres of the part of `subject` inside the convex ring `clip`, on a sphere of
+/// `radius` metres
+///
+/// Edges are straight lines on the equal-area cylindrical projection, as in [`ring_area`], so
+/// the intersections of cells bounded by meridians and parallels are exact. `subject` may be
+/// concave. The longitudes of both rings must be unwrapped to the same range. Slivers left by
+/// rounding where the rings only share an edge count as no overlap.
+pub(crate) fn intersection_area(subject: &[(f64, f64)], clip: &[(f64, f64)], radius: f64) -> f64 {
+    let plane = |ring: &[(f64, f64)]| -> Vec<(f64, f64)> {
+        ring.iter()
+            .map(|&(lat, lon)| (lon.to_radians(), lat.to_radians().sin()))
+            .collect()
+    };
+    /// Part of the area of the clip ring below which the intersection is a rounding sliver
+    const SLIVER: f64 = 1e-10;
+    let clip = plane(clip);
+    let clip_area = signed_area(&clip);
+    let orientation = clip_area.signum();
+    if orientation == 0.0 {
+        return 0.0;
+    }
+    // Sutherland-Hodgman: keep the part of the subject on the inner side of every clip edge
+    let cross = |o: (f64, f64), a: (f64, f64), b: (f64, f64)| {
+        (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+    };
+    let mut polygon = plane(subject);
+    for (k, &a) in clip.iter().enumerate() {
+        let b = clip[(k + 1) % clip.len()];
+        let inside = |p: (f64, f64)| cross(a, b, p) * orientation >= 0.0;
+        let crossing = |s: (f64, f64), e: (f64, f64)| {
+            let t = cross(a, b, s) / (cross(a, b, s) - cross(a, b, e));
+            (s.0 + t * (e.0 - s.0), s.1 + t * (e.1 - s.1))
+        };
+        let Some(&last) = polygon.last() else {
+            return 0.0;
+        };
+        let mut clipped = Vec::with_capacity(polygon.len() + 2);
+        let mut previous = last;
+        for &point in &polygon {
+            match (inside(previous), inside(point)) {
+                (true, true) => clipped.push(point),
+                (true, false) => clipped.push(crossing(previous, point)),
+                (false, true) => clipped.extend([crossing(previous, point), point]),
+                (false, false) => {}
+            }
+            previous = point;
+        }
+        polygon = clipped;
+    }
+    match signed_area(&polygon).abs() {
+        area if area <= SLIVER * clip_area.abs() => 0.0,
+        area => area * radius * radius,
+    }
+}
+
+/// Signed area of a ring of plane points, positive counterclockwise
+fn signed_area(ring: &[(f64, f64)]) -> f64 {
+    (0..ring.len())
+        .map(|k| {
+            let ((x1, y1), (x2, y2)) = (ring[k], ring[(k + 1) % ring.len()]);
+            x1 * y2 - x2 * y1
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Corners of the cell of the point at `(x, y)` of a structured grid, bounded halfway to the
+/// neighbouring points, as a ring of latitudes and longitudes in degrees
+///
+/// Corners beyond a pole are clamped to it. None if a corner is off the earth.
+pub(crate) fn cell_ring<G: StructuredGrid + ?Sized>(
+    grid: &G,
+    x: f64,
+    y: f64,
+) -> Option<Vec<(f64, f64)>> {
+    [(-0.5, -0.5), (0.5, -0.5), (0.5, 0.5), (-0.5, 0.5)]
+        .into_iter()
+        .map(|(dx, dy)| {
+            grid.xy_to_latlon(x + dx, y + dy)
+                .map(|(lat, lon)| (lat.clamp(-90.0, 90.0), lon))
+        })
+        .collect()
+}
+
+impl GridDefinition {
+    /// Area in square metres of the cell of the point at `index`, bounded halfway to the
+    /// neighbouring points
+    ///
+    /// None for grids whose points aren't laid out on a plane.
+    pub fn cell_area(&self, index: usize) -> Option<f64> {
+        let grid = self.as_structured()?;
+        let (x, y) = grid.xy_of_index(index);
+        let corners = cell_ring(grid, x, y)?;
+        Some(ring_area(&corners, self.earth_radius()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intersection_areas() {
+        let square = |lat: f64, lon: f64, size: f64| {
+            vec![
+                (lat, lon),
+                (lat, lon + size),
+                (lat + size, lon + size),
+                (lat + size, lon),
+            ]
+        };
+        let cell = square(0.0, 0.0, 2.0);
+        let area = ring_area(&cell, 1.0);
+        assert!((intersection_area(&cell, &cell, 1.0) - area).abs() < 1e-15);
+        // Half the cell in longitude, clockwise clip ring
+        let mut clip = square(-1.0, 1.0, 4.0);
+        clip.reverse();
+        let half = intersection_area(&cell, &clip, 1.0);
+        assert!((half - area / 2.0).abs() < 1e-15);
+        assert_eq!(intersection_area(&square(10.0, 10.0, 1.0), &cell, 1.0), 0.0);
+
+        // Concave subject: an L covering three quarters of the cell
+        let l_shape = [
+            (0.0, 0.0),
+            (0.0, 2.0),
+            (1.0, 2.0),
+            (1.0, 1.0),
+            (2.0, 1.0),
+            (2.0, 0.0),
+        ];
+        let expected = ring_area(&l_shape, 6_371_229.0);
+        let found = intersection_area(&l_shape, &square(-1.0, -1.0, 4.0), 6_371_229.0);
+        assert!((found - expected).abs() < 1e-6 * expected);
+        let quarter = intersection_area(&l_shape, &square(1.0, 0.0, 1.0), 1.0);
+        assert!((quarter - ring_area(&square(1.0, 0.0, 1.0), 1.0)).abs() < 1e-15);
+    }
+
+    #[test]
+    fn cell_areas_cover_the_sphere() {
+        let grid = super::super::predefined_grid(3);
+        let radius = grid.earth_radius();
+        let total: f64 = (0..360 * 181).map(|i| grid.cell_area(i).unwrap()).sum();
+        let sphere = 4.0 * std::f64::consts::PI * radius * radius;
+        assert!(((total - sphere) / sphere).abs() < 1e-12);
+    }
+}
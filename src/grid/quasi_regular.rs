@@ -0,0 +1,211 @@
+use super::{GridGeometry, normalize_longitude};
+use crate::{Error, Result};
+
+/// Grid whose rows have different numbers of equally spaced points
+///
+/// This covers quasi-regular latitude/longitude grids and reduced Gaussian grids. The numbers
+/// of points per row come from the optional list at the end of section 3.
+#[derive(Debug, Clone)]
+pub struct QuasiRegularGrid {
+    /// Latitude of every row, in data order
+    latitudes: Vec<f64>,
+    points_per_row: Vec<u32>,
+    /// Index of the first value of every row, plus the total number of values
+    row_offsets: Vec<usize>,
+    lon_first: f64,
+    lon_span: f64,
+    global: bool,
+    i_negative: bool,
+    resolution_and_component_flags: u8,
+}
+
+impl QuasiRegularGrid {
+    /// Builds the geometry from the latitude and number of points of every row (in data order)
+    /// and the longitudes of the first and last points of the rows.
+    ///
+    /// With `full_circle` (Code Table 3.11, value 1), the points of every row are spread evenly
+    /// around the whole parallel from `lon_first`, and `lon_last` is ignored. Otherwise (value 2)
+    /// they go from `lon_first` to `lon_last`.
+    pub fn new(
+        row_latitudes: Vec<f64>,
+        points_per_row: Vec<u32>,
+        lon_first: f64,
+        lon_last: f64,
+        full_circle: bool,
+        i_negative: bool,
+        resolution_and_component_flags: u8,
+    ) -> Result<Self> {
+        if row_latitudes.len() != points_per_row.len() {
+            return Err(Error::InvalidData(format!(
+                "{} rows have a latitude, but {} have a number of points",
+                row_latitudes.len(),
+                points_per_row.len()
+            )));
+        }
+        let mut row_offsets = Vec::with_capacity(points_per_row.len() + 1);
+        let mut offset = 0;
+        for &count in &points_per_row {
+            row_offsets.push(offset);
+            offset += count as usize;
+        }
+        row_offsets.push(offset);
+
+        let span = match i_negative {
+            true => (lon_first - lon_last).rem_euclid(360.0),
+            false => (lon_last - lon_first).rem_euclid(360.0),
+        };
+        Ok(Self {
+            latitudes: row_latitudes,
+            points_per_row,
+            row_offsets,
+            lon_first,
+            lon_span: if full_circle { 360.0 } else { span },
+            global: full_circle,
+            i_negative,
+            resolution_and_component_flags,
+        })
+    }
+
+    /// Latitude in degrees of every row, in data order
+    pub fn row_latitudes(&self) -> &[f64] {
+        &self.latitudes
+    }
+
+    pub fn points_per_row(&self) -> &[u32] {
+        &self.points_per_row
+    }
+
+    /// Whether the rows go all around the globe
+    pub fn is_global(&self) -> bool {
+        self.global
+    }
+
+    /// Longitude step in degrees between the points of a row with `count` points
+    fn step(&self, count: u32) -> f64 {
+        let step = match (self.global, count) {
+            (true, count) => self.lon_span / count as f64,
+            (false, 0 | 1) => 0.0,
+            (false, count) => self.lon_span / (count - 1) as f64,
+        };
+        if self.i_negative { -step } else { step }
+    }
+
+    /// Row and position within the row of the value at `index`
+    pub fn row_and_column(&self, index: usize) -> Option<(usize, usize)> {
+        if index >= self.number_of_points() {
+            return None;
+        }
+        let row = self.row_offsets.partition_point(|&offset| offset <= index) - 1;
+        Some((row, index - self.row_offsets[row]))
+    }
+
+    /// Rows on either side of the latitude in degrees, with the weight of each for linear
+    /// interpolation
+    pub(crate) fn bracketing_rows(&self, lat: f64) -> Option<[(usize, f64); 2]> {
+        if let [only] = self.latitudes[..] {
+            return ((only - lat).abs() < 1e-9).then_some([(0, 1.0), (0, 0.0)]);
+        }
+        self.latitudes
+            .windows(2)
+            .enumerate()
+            .find_map(|(row, pair)| {
+                let (l0, l1) = (pair[0], pair[1]);
+                let (lo, hi) = (l0.min(l1), l0.max(l1));
+                if !(lo..=hi).contains(&lat) || l0 == l1 {
+                    return None;
+                }
+                let w = (lat - l0) / (l1 - l0);
+                Some([(row, 1.0 - w), (row + 1, w)])
+            })
+    }
+
+    /// Indices of the points of `row` on either side of the longitude in degrees, with the
+    /// weight of each for linear interpolation
+    pub(crate) fn bracketing_points(&self, row: usize, lon: f64) -> Option<[(usize, f64); 2]> {
+        let count = *self.points_per_row.get(row)? as usize;
+        if count == 0 {
+            return None;
+        }
+        let offset = self.row_offsets[row];
+        let step = self.step(count as u32);
+        if step == 0.0 {
+            return Some([(offset, 1.0), (offset, 0.0)]);
+        }
+        // Position along the row in units of the spacing, going the way the row scans
+        let pos = ((lon - self.lon_first) * step.signum()).rem_euclid(360.0) / step.abs();
+        let k = pos.floor() as usize;
+        let w = pos - k as f64;
+        match self.global {
+            true => Some([(offset + k % count, 1.0 - w), (offset + (k + 1) % count, w)]),
+            false if k + 1 < count => Some([(offset + k, 1.0 - w), (offset + k + 1, w)]),
+            false if k + 1 == count && w < 1e-9 => Some([(offset + k, 1.0), (offset + k, 0.0)]),
+            false => None,
+        }
+    }
+
+    /// Linearly interpolates every row to `n_i` points, producing the values of the regular grid
+    /// with the same rows (e.g. the regular Gaussian grid for a reduced Gaussian grid).
+    ///
+    /// Output points of a row are placed like those of a row of `n_i` points of this grid.
+    /// Points next to a missing value (NAN) are missing as well.
+    pub fn interpolate_to_regular(&self, values: &[f32], n_i: usize) -> Result<Vec<f32>> {
+        if values.len() != self.number_of_points() {
+            return Err(Error::InvalidData(format!(
+                "expected {} values, but got {}",
+                self.number_of_points(),
+                values.len()
+            )));
+        }
+        let mut regular = Vec::with_capacity(n_i * self.points_per_row.len());
+        for (row, &count) in self.points_per_row.iter().enumerate() {
+            let row_values = &values[self.row_offsets[row]..self.row_offsets[row + 1]];
+            if count == 0 {
+                regular.extend(std::iter::repeat_n(f32::NAN, n_i));
+                continue;
+            }
+            let out_step = match (self.global, n_i) {
+                (true, n) => 1.0 / n as f64,
+                (false, 0 | 1) => 0.0,
+                (false, n) => 1.0 / (n - 1) as f64,
+            };
+            let in_points = if self.global { count } else { count - 1 } as f64;
+            for i in 0..n_i {
+                // Position along the row in units of the input spacing
+                let pos = i as f64 * out_step * in_points;
+                let i0 = pos.floor() as usize;
+                let w = pos - i0 as f64;
+                let v0 = row_values[i0.min(count as usize - 1)];
+                let v1 = match self.global {
+                    true => row_values[(i0 + 1) % count as usize],
+                    false => row_values[(i0 + 1).min(count as usize - 1)],
+                };
+                regular.push(match w {
+                    w if w < 1e-9 => v0,
+                    w if w > 1.0 - 1e-9 => v1,
+                    w => (v0 as f64 * (1.0 - w) + v1 as f64 * w) as f32,
+                });
+            }
+        }
+        Ok(regular)
+    }
+}
+
+impl GridGeometry for QuasiRegularGrid {
+    fn number_of_points(&self) -> usize {
+        *self.row_offsets.last().unwrap_or(&0)
+    }
+
+    fn latlon(&self, index: usize) -> Option<(f64, f64)> {
+        let (row, column) = self.row_and_column(index)?;
+        let lon = self.lon_first + column as f64 * self.step(self.points_per_row[row]);
+        Some((self.latitudes[row], normalize_longitude(lon)))
+    }
+
+    fn grid_rotation(&self, _index: usize) -> Option<f64> {
+        Some(0.0)
+    }
+
+    fn uv_relative_to_grid(&self) -> bool {
+        self.resolution_and_component_flags & 0x08 != 0
+    }
+}
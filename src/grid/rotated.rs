@@ -0,0 +1,165 @@
+use super::{ScanningMode, StructuredGrid, angle_unit, normalize_longitude};
+use crate::templates::GridDefinitionTemplate3_1;
+
+impl GridDefinitionTemplate3_1 {
+    /// Geographic latitude and longitude in degrees of the southern pole of the rotated system
+    pub fn southern_pole(&self) -> (f64, f64) {
+        let tmpl = &self.template_0;
+        let unit = angle_unit(tmpl.basic_angle, tmpl.subdivisions_of_basic_angle);
+        (
+            self.latitude_of_southern_pole as f64 * unit,
+            self.longitude_of_southern_pole as f64 * unit,
+        )
+    }
+
+    /// Converts latitude and longitude in the rotated system into geographic ones.
+    pub fn unrotate(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let (pole_lat, pole_lon) = self.southern_pole();
+        let (sin_c, cos_c) = (pole_lat + 90.0).to_radians().sin_cos();
+        let (sin_x, cos_x) = (lon + self.angle_of_rotation as f64).to_radians().sin_cos();
+        let (sin_y, cos_y) = lat.to_radians().sin_cos();
+
+        let sin_lat = (cos_c * sin_y + sin_c * cos_y * cos_x).clamp(-1.0, 1.0);
+        let cos_lat = sin_lat.asin().cos();
+        if cos_lat == 0.0 {
+            return (sin_lat.asin().to_degrees(), 0.0);
+        }
+        let cos_dlon = ((cos_c * cos_y * cos_x - sin_c * sin_y) / cos_lat).clamp(-1.0, 1.0);
+        let sin_dlon = cos_y * sin_x / cos_lat;
+        let dlon = cos_dlon.acos().copysign(sin_dlon);
+        (
+            sin_lat.asin().to_degrees(),
+            normalize_longitude(dlon.to_degrees() + pole_lon),
+        )
+    }
+
+    /// Converts geographic latitude and longitude into ones in the rotated system.
+    pub fn rotate(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let (pole_lat, pole_lon) = self.southern_pole();
+        let (sin_c, cos_c) = (pole_lat + 90.0).to_radians().sin_cos();
+        let (sin_dlon, cos_dlon) = (lon - pole_lon).to_radians().sin_cos();
+        let (sin_lat, cos_lat) = lat.to_radians().sin_cos();
+
+        let sin_y = (cos_c * sin_lat - sin_c * cos_lat * cos_dlon).clamp(-1.0, 1.0);
+        let cos_y = sin_y.asin().cos();
+        if cos_y == 0.0 {
+            return (sin_y.asin().to_degrees(), 0.0);
+        }
+        let cos_x = ((cos_c * cos_lat * cos_dlon + sin_c * sin_lat) / cos_y).clamp(-1.0, 1.0);
+        let sin_x = cos_lat * sin_dlon / cos_y;
+        let x = cos_x.acos().copysign(sin_x);
+        (
+            sin_y.asin().to_degrees(),
+            normalize_longitude(x.to_degrees() - self.angle_of_rotation as f64),
+        )
+    }
+}
+
+impl StructuredGrid for GridDefinitionTemplate3_1 {
+    fn dimensions(&self) -> (usize, usize) {
+        self.template_0.dimensions()
+    }
+
+    fn scanning_mode(&self) -> ScanningMode {
+        self.template_0.scanning_mode()
+    }
+
+    fn resolution_and_component_flags(&self) -> u8 {
+        self.template_0.resolution_and_component_flags()
+    }
+
+    fn xy_to_latlon(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let (lat, lon) = self.template_0.xy_to_latlon(x, y)?;
+        Some(self.unrotate(lat, lon))
+    }
+
+    fn latlon_to_xy(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let (lat, lon) = self.rotate(lat, lon);
+        self.template_0.latlon_to_xy(lat, lon)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::GridCatalog;
+    use crate::templates::{GridDefinitionTemplate, GridDefinitionTemplate3_0};
+
+    /// COSMO-like 0.5° grid of 40 x 30 points around the rotated origin, with the southern pole
+    /// at 40S 10E (the northern pole of the rotated system at 40N 170W)
+    fn rotated_grid() -> GridDefinitionTemplate3_1 {
+        let Some(GridDefinitionTemplate::Template3_0(template_0)) = GridCatalog::builtin().get(3)
+        else {
+            unreachable!()
+        };
+        GridDefinitionTemplate3_1 {
+            template_0: GridDefinitionTemplate3_0 {
+                n_i: 40,
+                n_j: 30,
+                la1: -7_000_000,
+                lo1: 350_000_000,
+                la2: 7_500_000,
+                lo2: 9_500_000,
+                d_i: 500_000,
+                d_j: 500_000,
+                scanning_mode: 0x40,
+                ..template_0.clone()
+            },
+            latitude_of_southern_pole: -40_000_000,
+            longitude_of_southern_pole: 10_000_000,
+            angle_of_rotation: 0.0,
+        }
+    }
+
+    fn unit_vector(lat: f64, lon: f64) -> [f64; 3] {
+        let (sin_lat, cos_lat) = lat.to_radians().sin_cos();
+        let (sin_lon, cos_lon) = lon.to_radians().sin_cos();
+        [cos_lat * cos_lon, cos_lat * sin_lon, sin_lat]
+    }
+
+    #[test]
+    fn unrotates_onto_the_rotated_axes() {
+        let grid = rotated_grid();
+        assert_eq!(grid.unrotate(0.0, 0.0), (50.0, 10.0));
+        let (lat, lon) = grid.unrotate(90.0, 0.0);
+        assert!((lat - 40.0).abs() < 1e-9 && (lon + 170.0).abs() < 1e-9);
+
+        // Rotated coordinates are the spherical coordinates in the frame whose x axis points to
+        // 50N 10E and whose z axis points to 40N 170W
+        let ex = unit_vector(50.0, 10.0);
+        let ez = unit_vector(40.0, -170.0);
+        let ey = [
+            ez[1] * ex[2] - ez[2] * ex[1],
+            ez[2] * ex[0] - ez[0] * ex[2],
+            ez[0] * ex[1] - ez[1] * ex[0],
+        ];
+        for (lat, lon) in [(10.0, 20.0), (-35.0, -120.0), (60.0, 179.0), (-5.0, 3.5)] {
+            let [x, y, z] = unit_vector(lat, lon);
+            let expected: Vec<f64> = (0..3).map(|k| x * ex[k] + y * ey[k] + z * ez[k]).collect();
+            let (glat, glon) = grid.unrotate(lat, lon);
+            let actual = unit_vector(glat, glon);
+            for k in 0..3 {
+                assert!(
+                    (actual[k] - expected[k]).abs() < 1e-12,
+                    "({}, {})",
+                    lat,
+                    lon
+                );
+            }
+            let (rlat, rlon) = grid.rotate(glat, glon);
+            assert!((rlat - lat).abs() < 1e-9 && (rlon - lon).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn grid_points_round_trip() {
+        let grid = rotated_grid();
+        let (x, y) = grid.xy_of_index(12 * 40 + 20);
+        assert_eq!((x, y), (20.0, 12.0));
+        let (lat, lon) = grid.xy_to_latlon(x, y).unwrap();
+        // Rotated point (-1°, 0°)
+        assert_eq!((lat, lon), grid.unrotate(-1.0, 0.0));
+        let (x1, y1) = grid.latlon_to_xy(lat, lon).unwrap();
+        assert!((x1 - x).abs() < 1e-9 && (y1 - y).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,199 @@
+use crate::{Error, Result};
+
+/// Scanning mode (Flag Table 3.4)
+///
+/// Describes how the data values of an `ni` x `nj` grid are ordered. `i` counts points along a
+/// row (the x direction) and `j` counts rows (the y direction), both starting at the first grid
+/// point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanningMode(pub u8);
+
+impl ScanningMode {
+    /// Bit 1: points of a row scan in the -i (westward) direction
+    pub fn i_negative(&self) -> bool {
+        self.0 & 0x80 != 0
+    }
+
+    /// Bit 2: rows scan in the +j (northward) direction
+    pub fn j_positive(&self) -> bool {
+        self.0 & 0x40 != 0
+    }
+
+    /// Bit 3: adjacent points in the j direction are consecutive
+    pub fn j_consecutive(&self) -> bool {
+        self.0 & 0x20 != 0
+    }
+
+    /// Bit 4: every other row scans in the opposite direction (boustrophedon)
+    pub fn boustrophedon(&self) -> bool {
+        self.0 & 0x10 != 0
+    }
+
+    /// Grid position `(i, j)` of the value at `index` in the data
+    pub fn ij(&self, index: usize, ni: usize, nj: usize) -> (usize, usize) {
+        if self.j_consecutive() {
+            let (i, j) = (index / nj, index % nj);
+            match self.boustrophedon() && i % 2 == 1 {
+                true => (i, nj - 1 - j),
+                false => (i, j),
+            }
+        } else {
+            let (i, j) = (index % ni, index / ni);
+            match self.boustrophedon() && j % 2 == 1 {
+                true => (ni - 1 - i, j),
+                false => (i, j),
+            }
+        }
+    }
+
+    /// Index in the data of the value at grid position `(i, j)`
+    pub fn index(&self, i: usize, j: usize, ni: usize, nj: usize) -> usize {
+        if self.j_consecutive() {
+            let j = match self.boustrophedon() && i % 2 == 1 {
+                true => nj - 1 - j,
+                false => j,
+            };
+            i * nj + j
+        } else {
+            let i = match self.boustrophedon() && j % 2 == 1 {
+                true => ni - 1 - i,
+                false => i,
+            };
+            j * ni + i
+        }
+    }
+
+    /// Offsets of grid position `(i, j)` from the first grid point, in grid units along the +x
+    /// (east) and +y (north) axes.
+    pub fn xy(&self, i: f64, j: f64) -> (f64, f64) {
+        (
+            if self.i_negative() { -i } else { i },
+            if self.j_positive() { j } else { -j },
+        )
+    }
+
+    /// Inverse of [`ScanningMode::xy`]
+    pub fn ij_from_xy(&self, x: f64, y: f64) -> (f64, f64) {
+        self.xy(x, y)
+    }
+
+    /// Changes this scanning mode makes to the canonical layout
+    ///
+    /// The canonical layout starts at the north-west corner, goes west to east along the rows
+    /// and north to south from row to row, i.e. scanning mode 0 without boustrophedon.
+    pub fn canonical_transform(&self) -> CanonicalTransform {
+        CanonicalTransform {
+            flip_x: self.i_negative(),
+            flip_y: self.j_positive(),
+            transpose: self.j_consecutive(),
+            unzigzag: self.boustrophedon(),
+        }
+    }
+
+    /// Index in the canonical layout of the value at `index` in the data
+    pub fn canonical_index(&self, index: usize, ni: usize, nj: usize) -> usize {
+        let (i, j) = self.ij(index, ni, nj);
+        let column = if self.i_negative() { ni - 1 - i } else { i };
+        let row = if self.j_positive() { nj - 1 - j } else { j };
+        row * ni + column
+    }
+
+    /// Reorders the values of an `ni` x `nj` grid into the canonical layout (north-up, west to
+    /// east, row-major).
+    ///
+    /// Returns the reordered values and the transform that was applied.
+    pub fn to_canonical<T: Copy>(
+        &self,
+        values: &[T],
+        ni: usize,
+        nj: usize,
+    ) -> Result<(Vec<T>, CanonicalTransform)> {
+        if values.len() != ni * nj {
+            return Err(Error::InvalidData(format!(
+                "{} values don't fill a {} x {} grid",
+                values.len(),
+                ni,
+                nj
+            )));
+        }
+        let transform = self.canonical_transform();
+        if transform.is_identity() {
+            return Ok((values.to_vec(), transform));
+        }
+        let mut out = values.to_vec();
+        for (index, &value) in values.iter().enumerate() {
+            out[self.canonical_index(index, ni, nj)] = value;
+        }
+        Ok((out, transform))
+    }
+}
+
+/// Transform from a scanning mode to the canonical layout, as applied by
+/// [`ScanningMode::to_canonical`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CanonicalTransform {
+    /// Rows were reversed west to east
+    pub flip_x: bool,
+    /// Row order was reversed to go north to south
+    pub flip_y: bool,
+    /// Columns were turned into rows (adjacent points in j were consecutive)
+    pub transpose: bool,
+    /// Every other row (or column) was reversed to undo boustrophedon scanning
+    pub unzigzag: bool,
+}
+
+impl CanonicalTransform {
+    /// Whether the values were already in the canonical layout
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_round_trips() {
+        for mode in [0x00, 0x10, 0x20, 0x30, 0x40, 0x50, 0x80, 0xf0] {
+            let scan = ScanningMode(mode);
+            for index in 0..12 {
+                let (i, j) = scan.ij(index, 4, 3);
+                assert!(i < 4 && j < 3);
+                assert_eq!(scan.index(i, j, 4, 3), index, "mode {:#x}", mode);
+            }
+        }
+    }
+
+    #[test]
+    fn reorders_known_layouts() {
+        // 3 x 2 grid whose canonical layout holds 0..6
+        let layouts: [(u8, [u8; 6]); 7] = [
+            (0x00, [0, 1, 2, 3, 4, 5]),
+            (0x40, [3, 4, 5, 0, 1, 2]),
+            (0x80, [2, 1, 0, 5, 4, 3]),
+            (0xc0, [5, 4, 3, 2, 1, 0]),
+            (0x20, [0, 3, 1, 4, 2, 5]),
+            (0x10, [0, 1, 2, 5, 4, 3]),
+            (0x50, [3, 4, 5, 2, 1, 0]),
+        ];
+        for (mode, values) in layouts {
+            let scan = ScanningMode(mode);
+            let (canonical, transform) = scan.to_canonical(&values, 3, 2).unwrap();
+            assert_eq!(canonical, [0, 1, 2, 3, 4, 5], "mode {:#x}", mode);
+            assert_eq!(transform, scan.canonical_transform());
+            assert_eq!(transform.is_identity(), mode == 0);
+            for (index, &value) in values.iter().enumerate() {
+                assert_eq!(scan.canonical_index(index, 3, 2), value as usize);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_values_not_filling_the_grid() {
+        assert!(matches!(
+            ScanningMode(0).to_canonical(&[0; 5], 3, 2),
+            Err(Error::InvalidData(_))
+        ));
+    }
+}
@@ -0,0 +1,250 @@
+use super::{Ellipsoid, MICRO, ScanningMode, StructuredGrid, normalize_longitude};
+use crate::templates::GridDefinitionTemplate3_90;
+
+/// Geostationary view of the earth from a satellite above the equator
+///
+/// Image coordinates are in grid lengths, increasing eastward (x) and northward (y), as seen
+/// from the satellite. Scan angles are the angles of the line of sight from the sub-satellite
+/// direction.
+struct Geostationary {
+    ellipsoid: Ellipsoid,
+    /// Distance of the satellite from the centre of the earth in metres
+    h: f64,
+    lop: f64,
+    /// Scan angle per grid length in radians
+    rx: f64,
+    ry: f64,
+    /// Image coordinates of the sub-satellite point
+    xp: f64,
+    yp: f64,
+    /// Rotation of the image, as (sin, cos)
+    orientation: (f64, f64),
+}
+
+impl Geostationary {
+    /// Latitude and longitude seen at the image coordinates, or None off the earth's disk
+    fn image_to_latlon(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let (sin_o, cos_o) = self.orientation;
+        let (dx, dy) = (x - self.xp, y - self.yp);
+        let (dx, dy) = (dx * cos_o - dy * sin_o, dx * sin_o + dy * cos_o);
+        let (sin_x, cos_x) = (dx * self.rx).sin_cos();
+        let (sin_y, cos_y) = (dy * self.ry).sin_cos();
+
+        let (a, b) = (self.ellipsoid.a, self.ellipsoid.b);
+        let f2 = (a * a) / (b * b);
+        let q = cos_y * cos_y + f2 * sin_y * sin_y;
+        let hc = self.h * cos_x * cos_y;
+        let discriminant = hc * hc - q * (self.h * self.h - a * a);
+        if discriminant <= 0.0 {
+            return None;
+        }
+        let sn = (hc - discriminant.sqrt()) / q;
+        let s1 = self.h - sn * cos_x * cos_y;
+        let s2 = sn * sin_x * cos_y;
+        let s3 = sn * sin_y;
+        let lat = (f2 * s3 / s1.hypot(s2)).atan().to_degrees();
+        let lon = self.lop + s2.atan2(s1).to_degrees();
+        Some((lat, normalize_longitude(lon)))
+    }
+
+    /// Image coordinates of the latitude and longitude, or None if not visible
+    fn latlon_to_image(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let (a, b) = (self.ellipsoid.a, self.ellipsoid.b);
+        let e2 = 1.0 - (b * b) / (a * a);
+        // Geocentric latitude and distance from the centre of the earth
+        let c = ((b * b) / (a * a) * lat.to_radians().tan()).atan();
+        let (sin_c, cos_c) = c.sin_cos();
+        let r = b / (1.0 - e2 * cos_c * cos_c).sqrt();
+        let (sin_l, cos_l) = (lon - self.lop).to_radians().sin_cos();
+        let (p1, p2, p3) = (r * cos_c * cos_l, r * cos_c * sin_l, r * sin_c);
+        // Hidden behind the limb
+        if self.h * p1 - (p1 * p1 + p2 * p2 + p3 * p3) <= 0.0 {
+            return None;
+        }
+        let (v1, v2, v3) = (self.h - p1, p2, p3);
+        let x = v2.atan2(v1) / self.rx;
+        let y = (v3 / (v1 * v1 + v2 * v2 + v3 * v3).sqrt()).asin() / self.ry;
+        let (sin_o, cos_o) = self.orientation;
+        Some((
+            self.xp + x * cos_o + y * sin_o,
+            self.yp - x * sin_o + y * cos_o,
+        ))
+    }
+}
+
+impl GridDefinitionTemplate3_90 {
+    /// Whether this is an orthographic view (camera at infinity), which is not supported
+    pub fn is_orthographic(&self) -> bool {
+        self.n_r == u32::MAX
+    }
+
+    fn geostationary(&self) -> Option<Geostationary> {
+        if self.is_orthographic() || self.n_r <= 1_000_000 {
+            return None;
+        }
+        let ellipsoid = self.ellipsoid()?;
+        let nr = self.n_r as f64 * MICRO;
+        let angular_size = 2.0 * (1.0 / nr).asin();
+        Some(Geostationary {
+            ellipsoid,
+            h: nr * ellipsoid.a,
+            lop: self.lop as f64 * MICRO,
+            rx: angular_size / self.d_x as f64,
+            ry: (ellipsoid.b / ellipsoid.a) * angular_size / self.d_y as f64,
+            xp: self.x_p as f64 * 1e-3,
+            yp: self.y_p as f64 * 1e-3,
+            orientation: (self.orientation_of_the_grid as f64 * MICRO)
+                .to_radians()
+                .sin_cos(),
+        })
+    }
+
+    /// Image coordinates of the first grid point
+    ///
+    /// `(x_o, y_o)` is taken as the south-west corner of the sector in image coordinates, so
+    /// the first grid point is at the corner the scanning mode starts from.
+    fn first_point(&self) -> (f64, f64) {
+        let scan = ScanningMode(self.scanning_mode);
+        let x = match scan.i_negative() {
+            true => self.x_o as f64 + self.n_x as f64 - 1.0,
+            false => self.x_o as f64,
+        };
+        let y = match scan.j_positive() {
+            true => self.y_o as f64,
+            false => self.y_o as f64 + self.n_y as f64 - 1.0,
+        };
+        (x, y)
+    }
+
+    /// Whether the position `(x, y)` on the grid sees the earth
+    pub fn is_on_disk(&self, x: f64, y: f64) -> bool {
+        self.xy_to_latlon(x, y).is_some()
+    }
+}
+
+impl StructuredGrid for GridDefinitionTemplate3_90 {
+    fn dimensions(&self) -> (usize, usize) {
+        (self.n_x as usize, self.n_y as usize)
+    }
+
+    fn scanning_mode(&self) -> ScanningMode {
+        ScanningMode(self.scanning_mode)
+    }
+
+    fn resolution_and_component_flags(&self) -> u8 {
+        self.resolution_and_component_flags
+    }
+
+    fn xy_to_latlon(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let (x0, y0) = self.first_point();
+        self.geostationary()?.image_to_latlon(x0 + x, y0 + y)
+    }
+
+    fn latlon_to_xy(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let (x0, y0) = self.first_point();
+        let (x, y) = self.geostationary()?.latlon_to_image(lat, lon)?;
+        Some((x - x0, y - y0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Full disk of 3712 x 3712 points seen from 9.5E, on the ellipsoid used for Meteosat
+    fn full_disk() -> GridDefinitionTemplate3_90 {
+        GridDefinitionTemplate3_90 {
+            shape_of_earth: 7,
+            scale_factor_of_radius: 0,
+            scale_value_of_radius: 0,
+            scale_factor_of_major_axis: 0,
+            scale_value_of_major_axis: 6_378_169,
+            scale_factor_of_minor_axis: 1,
+            scale_value_of_minor_axis: 63_565_838,
+            n_x: 3712,
+            n_y: 3712,
+            lap: 0,
+            lop: 9_500_000,
+            resolution_and_component_flags: 0,
+            d_x: 3622,
+            d_y: 3610,
+            x_p: 1_856_000,
+            y_p: 1_856_000,
+            scanning_mode: 0x40,
+            orientation_of_the_grid: 0,
+            n_r: 6_610_689,
+            x_o: 0,
+            y_o: 0,
+        }
+    }
+
+    #[test]
+    fn sub_satellite_point() {
+        let (lat, lon) = full_disk().xy_to_latlon(1856.0, 1856.0).unwrap();
+        assert!(lat.abs() < 1e-9 && (lon - 9.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn scan_angles_along_the_equator() {
+        // A point on the equator 40° east of the satellite is seen at an angle of
+        // atan(a sin 40 / (h - a cos 40)) from the sub-satellite direction
+        let grid = full_disk();
+        let (a, nr): (f64, f64) = (6_378_169.0, 6.610689);
+        let angle = (a * 40f64.to_radians().sin() / (nr * a - a * 40f64.to_radians().cos())).atan();
+        let per_grid_length = 2.0 * (1.0 / nr).asin() / 3622.0;
+        let (x, y) = grid.latlon_to_xy(0.0, 49.5).unwrap();
+        assert!(
+            (x - (1856.0 + angle / per_grid_length)).abs() < 1e-6,
+            "{}",
+            x
+        );
+        assert!((y - 1856.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn scan_angles_along_the_meridian_of_a_sphere() {
+        let grid = GridDefinitionTemplate3_90 {
+            shape_of_earth: 6,
+            scale_value_of_major_axis: 0,
+            scale_value_of_minor_axis: 0,
+            d_y: 3622,
+            ..full_disk()
+        };
+        let (r, nr): (f64, f64) = (6_371_229.0, 6.610689);
+        let angle = (r * 30f64.to_radians().sin() / (nr * r - r * 30f64.to_radians().cos())).atan();
+        let per_grid_length = 2.0 * (1.0 / nr).asin() / 3622.0;
+        let (lat, lon) = grid
+            .xy_to_latlon(1856.0, 1856.0 - angle / per_grid_length)
+            .unwrap();
+        assert!((lat + 30.0).abs() < 1e-9 && (lon - 9.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn points_off_the_disk() {
+        let grid = full_disk();
+        assert!(grid.is_on_disk(1856.0 + 1810.0, 1856.0));
+        assert!(!grid.is_on_disk(1856.0 + 1812.0, 1856.0));
+        assert!(!grid.is_on_disk(0.0, 0.0));
+        assert_eq!(grid.latlon_to_xy(0.0, 9.5 + 100.0), None);
+    }
+
+    #[test]
+    fn grid_points_round_trip() {
+        let grid = full_disk();
+        for (x, y) in [
+            (100.0, 1856.0),
+            (1856.0, 3600.0),
+            (2500.0, 700.0),
+            (1000.5, 2900.25),
+        ] {
+            let (lat, lon) = grid.xy_to_latlon(x, y).unwrap();
+            let (x1, y1) = grid.latlon_to_xy(lat, lon).unwrap();
+            assert!(
+                (x1 - x).abs() < 1e-6 && (y1 - y).abs() < 1e-6,
+                "({}, {})",
+                x,
+                y
+            );
+        }
+    }
+}
@@ -0,0 +1,600 @@
+use std::ops::Range;
+
+use super::{GridDefinition, ScanningMode, StructuredGrid};
+use crate::templates::GridDefinitionTemplate;
+use crate::{Error, Result};
+
+/// Number of samples along every edge of a bounding box when looking for its window
+const EDGE_SAMPLES: usize = 256;
+
+/// Latitude/longitude bounding box in degrees
+///
+/// A box whose western edge is east of its eastern edge crosses the antimeridian.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub south: f64,
+    pub north: f64,
+    pub west: f64,
+    pub east: f64,
+}
+
+impl BoundingBox {
+    pub fn new(south: f64, north: f64, west: f64, east: f64) -> Self {
+        Self {
+            south,
+            north,
+            west,
+            east,
+        }
+    }
+
+    /// Width in degrees of longitude, going east from the western edge
+    pub fn width(&self) -> f64 {
+        let width = (self.east - self.west).rem_euclid(360.0);
+        if width == 0.0 && self.east != self.west {
+            360.0
+        } else {
+            width
+        }
+    }
+
+    pub fn crosses_antimeridian(&self) -> bool {
+        let west = (self.west + 180.0).rem_euclid(360.0);
+        west + self.width() > 360.0
+    }
+
+    /// Whether the latitude and longitude in degrees are inside the box
+    pub fn contains(&self, lat: f64, lon: f64) -> bool {
+        (self.south..=self.north).contains(&lat)
+            && (lon - self.west).rem_euclid(360.0) <= self.width()
+    }
+
+    /// Part of the box inside `other`, None if they don't overlap
+    ///
+    /// A box overlapping both the western and the eastern end of `other` keeps all the
+    /// longitudes of `other`.
+    pub fn intersection(&self, other: &BoundingBox) -> Option<BoundingBox> {
+        let (south, north) = (self.south.max(other.south), self.north.min(other.north));
+        if south > north {
+            return None;
+        }
+        // Longitudes as offsets east of the western edge of `other`
+        let other_width = other.width();
+        let start = (self.west - other.west).rem_euclid(360.0);
+        let end = start + self.width();
+        let (first, last) = match (start <= other_width, end >= 360.0) {
+            (true, true) => (0.0, other_width),
+            (true, _) => (start, end.min(other_width)),
+            (false, true) => (0.0, (end - 360.0).min(other_width)),
+            (false, false) => return None,
+        };
+        Some(BoundingBox::new(
+            south,
+            north,
+            other.west + first,
+            other.west + last,
+        ))
+    }
+
+    /// Points along the edges of the box
+    fn edge_points(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        let width = self.width();
+        (0..=EDGE_SAMPLES).flat_map(move |k| {
+            let t = k as f64 / EDGE_SAMPLES as f64;
+            let lat = self.south + t * (self.north - self.south);
+            let lon = self.west + t * width;
+            [
+                (lat, self.west),
+                (lat, self.west + width),
+                (self.south, lon),
+                (self.north, lon),
+            ]
+        })
+    }
+}
+
+/// Rectangle of grid positions `(i, j)` of an `ni` x `nj` grid
+///
+/// On grids that go around the globe, the window may run past the last column and continue
+/// from the first one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridWindow {
+    pub i0: usize,
+    pub j0: usize,
+    pub ni: usize,
+    pub nj: usize,
+    grid_ni: usize,
+    grid_nj: usize,
+    scanning_mode: ScanningMode,
+}
+
+impl GridWindow {
+    /// Window of `ni` x `nj` points starting at `(i0, j0)` of a `grid_ni` x `grid_nj` grid
+    ///
+    /// Only windows of grids going around the globe (`periodic`) may run past the last column.
+    pub fn new(
+        (i0, j0): (usize, usize),
+        (ni, nj): (usize, usize),
+        (grid_ni, grid_nj): (usize, usize),
+        scanning_mode: ScanningMode,
+        periodic: bool,
+    ) -> Result<Self> {
+        if i0 >= grid_ni || ni > grid_ni || j0 + nj > grid_nj || ni == 0 || nj == 0 {
+            return Err(Error::InvalidData(format!(
+                "a {} x {} window at ({}, {}) doesn't fit in a {} x {} grid",
+                ni, nj, i0, j0, grid_ni, grid_nj
+            )));
+        }
+        if !periodic && i0 + ni > grid_ni {
+            return Err(Error::InvalidData(format!(
+                "a {} x {} window at ({}, {}) runs past the last column of a {} x {} grid that \
+                 doesn't go around the globe",
+                ni, nj, i0, j0, grid_ni, grid_nj
+            )));
+        }
+        Ok(Self {
+            i0,
+            j0,
+            ni,
+            nj,
+            grid_ni,
+            grid_nj,
+            scanning_mode,
+        })
+    }
+
+    /// Smallest window of `grid` whose cells cover the bounding box, or None if the box misses
+    /// the grid
+    ///
+    /// On grids that don't go around the globe, the box is first clipped to the extent of the
+    /// grid, as longitudes west of a latitude/longitude grid map to the far end of its rows.
+    pub fn covering<G: StructuredGrid + ?Sized>(grid: &G, bbox: &BoundingBox) -> Option<Self> {
+        let (ni, nj) = grid.dimensions();
+        let scan = grid.scanning_mode();
+        let periodic = grid.is_periodic();
+        let bbox = match (periodic, extent(grid)) {
+            (false, Some(extent)) => bbox.intersection(&extent)?,
+            _ => *bbox,
+        };
+        let (is, js): (Vec<f64>, Vec<f64>) = bbox
+            .edge_points()
+            .filter_map(|(lat, lon)| grid.latlon_to_xy(lat, lon))
+            .map(|(x, y)| scan.ij_from_xy(x, y))
+            .filter(|(i, j)| i.is_finite() && j.is_finite())
+            .unzip();
+        let (j0, j1) = index_range(&js, nj)?;
+        let (i0, i1) = match periodic {
+            true => periodic_index_range(&is, ni)?,
+            false => index_range(&is, ni)?,
+        };
+        Self::new(
+            (i0, j0),
+            (i1 - i0 + 1, j1 - j0 + 1),
+            (ni, nj),
+            scan,
+            periodic,
+        )
+        .ok()
+    }
+
+    /// Whether the window runs past the last column of the grid
+    pub fn wraps(&self) -> bool {
+        self.i0 + self.ni > self.grid_ni
+    }
+
+    /// Index in the data of the whole grid of the value at `index` in the data of the window
+    pub fn source_index(&self, index: usize) -> usize {
+        let scan = self.scanning_mode;
+        let (i, j) = scan.ij(index, self.ni, self.nj);
+        scan.index(
+            (self.i0 + i) % self.grid_ni,
+            self.j0 + j,
+            self.grid_ni,
+            self.grid_nj,
+        )
+    }
+
+    /// Runs of consecutive indices in the data of the whole grid that make up the window, in
+    /// the data order of the window
+    pub fn source_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for index in 0..self.ni * self.nj {
+            let source = self.source_index(index);
+            match ranges.last_mut() {
+                Some(range) if range.end == source => range.end += 1,
+                _ => ranges.push(source..source + 1),
+            }
+        }
+        ranges
+    }
+
+    /// Values of the window taken from the values of the whole grid
+    pub fn extract<T: Copy>(&self, values: &[T]) -> Result<Vec<T>> {
+        if values.len() != self.grid_ni * self.grid_nj {
+            return Err(Error::InvalidData(format!(
+                "{} values don't fill a {} x {} grid",
+                values.len(),
+                self.grid_ni,
+                self.grid_nj
+            )));
+        }
+        Ok(self
+            .source_ranges()
+            .into_iter()
+            .flat_map(|range| values[range].iter().copied())
+            .collect())
+    }
+}
+
+/// Bounding box of the points of `grid`, found from points along its edges
+///
+/// The box is shrunk by a hair in longitude so that its edges map inside the grid rather than
+/// around the globe. None if some of the edges are off the earth.
+fn extent<G: StructuredGrid + ?Sized>(grid: &G) -> Option<BoundingBox> {
+    const MARGIN: f64 = 1e-7;
+    let (ni, nj) = grid.dimensions();
+    if ni == 0 || nj == 0 {
+        return None;
+    }
+    let scan = grid.scanning_mode();
+    let (last_i, last_j) = ((ni - 1) as f64, (nj - 1) as f64);
+    let points = (0..=EDGE_SAMPLES)
+        .flat_map(|k| {
+            let t = k as f64 / EDGE_SAMPLES as f64;
+            [
+                (t * last_i, 0.0),
+                (t * last_i, last_j),
+                (0.0, t * last_j),
+                (last_i, t * last_j),
+            ]
+        })
+        .map(|(i, j)| {
+            let (x, y) = scan.xy(i, j);
+            grid.xy_to_latlon(x, y)
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let south = points.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+    let north = points.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max);
+    // A pole strictly inside the grid is circled by its edges
+    let encloses = |lat: f64| {
+        grid.latlon_to_xy(lat, 0.0)
+            .map(|(x, y)| scan.ij_from_xy(x, y))
+            .is_some_and(|(i, j)| 0.0 < i && i < last_i && 0.0 < j && j < last_j)
+    };
+    let (north_pole, south_pole) = (encloses(90.0), encloses(-90.0));
+    if north_pole || south_pole {
+        return Some(BoundingBox::new(
+            if south_pole { -90.0 } else { south },
+            if north_pole { 90.0 } else { north },
+            -180.0,
+            180.0,
+        ));
+    }
+    // The longitudes not covered are the largest gap between the longitudes of the edges
+    let mut lons: Vec<f64> = points.iter().map(|p| p.1.rem_euclid(360.0)).collect();
+    lons.sort_by(f64::total_cmp);
+    let (mut gap, mut west) = (lons[0] + 360.0 - lons[lons.len() - 1], lons[0]);
+    for pair in lons.windows(2) {
+        if pair[1] - pair[0] > gap {
+            (gap, west) = (pair[1] - pair[0], pair[1]);
+        }
+    }
+    let width = (360.0 - gap - 2.0 * MARGIN).max(0.0);
+    Some(BoundingBox::new(
+        south,
+        north,
+        west + MARGIN,
+        west + MARGIN + width,
+    ))
+}
+
+/// Range of whole indices covering the fractional `positions`, clamped to `0..n`
+pub(crate) fn index_range(positions: &[f64], n: usize) -> Option<(usize, usize)> {
+    const EPSILON: f64 = 1e-9;
+    let min = positions.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = positions.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if n == 0 || max < -EPSILON || min > (n - 1) as f64 + EPSILON {
+        return None;
+    }
+    let first = (min + EPSILON).floor().max(0.0) as usize;
+    let last = ((max - EPSILON).ceil().max(0.0) as usize).min(n - 1);
+    Some((first, last.max(first)))
+}
+
+/// Like [`index_range`] on a circle of `n` columns: the returned last index may be `n` or more
+/// when the range wraps around.
+pub(crate) fn periodic_index_range(positions: &[f64], n: usize) -> Option<(usize, usize)> {
+    const EPSILON: f64 = 1e-9;
+    let period = n as f64;
+    let mut positions: Vec<f64> = positions.iter().map(|p| p.rem_euclid(period)).collect();
+    positions.sort_by(f64::total_cmp);
+    let (&first, &last) = (positions.first()?, positions.last()?);
+    // The largest gap between neighbouring positions is where the range doesn't go
+    let (mut gap, mut start, mut end) = (first + period - last, first, last);
+    for pair in positions.windows(2) {
+        if pair[1] - pair[0] > gap {
+            (gap, start, end) = (pair[1] - pair[0], pair[1], pair[0] + period);
+        }
+    }
+    let first = (start + EPSILON).floor();
+    let last = (end - EPSILON).ceil().max(first);
+    let width = ((last - first) as usize + 1).min(n);
+    let i0 = match width {
+        width if width == n => 0,
+        _ => first as usize % n,
+    };
+    Some((i0, i0 + width - 1))
+}
+
+impl GridDefinitionTemplate {
+    /// The template describing only the points in `window`
+    ///
+    /// None for templates whose points aren't laid out on an `ni` x `nj` plane.
+    pub fn subset(&self, window: &GridWindow) -> Option<Self> {
+        let (ni, nj) = (window.ni as u32, window.nj as u32);
+        let scan = window.scanning_mode;
+        let (x0, y0) = scan.xy(window.i0 as f64, window.j0 as f64);
+        let (x1, y1) = scan.xy(
+            (window.i0 + window.ni - 1) as f64,
+            (window.j0 + window.nj - 1) as f64,
+        );
+        let first = |grid: &dyn StructuredGrid| grid.xy_to_latlon(x0, y0);
+        let last = |grid: &dyn StructuredGrid| grid.xy_to_latlon(x1, y1);
+        Some(match self {
+            Self::Template3_0(tmpl) if !tmpl.is_quasi_regular() => {
+                let mut sub = tmpl.clone();
+                let unit = super::angle_unit(tmpl.basic_angle, tmpl.subdivisions_of_basic_angle);
+                let ((la1, lo1), (la2, lo2)) = (first(tmpl)?, last(tmpl)?);
+                (sub.la1, sub.lo1) = (to_units(la1, unit), to_units(lon_360(lo1), unit));
+                (sub.la2, sub.lo2) = (to_units(la2, unit), to_units(lon_360(lo2), unit));
+                (sub.n_i, sub.n_j) = (ni, nj);
+                Self::Template3_0(sub)
+            }
+            Self::Template3_1(tmpl) => {
+                let Self::Template3_0(template_0) =
+                    Self::Template3_0(tmpl.template_0.clone()).subset(window)?
+                else {
+                    unreachable!()
+                };
+                let mut sub = tmpl.clone();
+                sub.template_0 = template_0;
+                Self::Template3_1(sub)
+            }
+            Self::Template3_10(tmpl) => {
+                let mut sub = tmpl.clone();
+                let ((la1, lo1), (la2, lo2)) = (first(tmpl)?, last(tmpl)?);
+                (sub.la1, sub.lo1) = (to_units(la1, 1e-6), to_units(lon_360(lo1), 1e-6));
+                (sub.la2, sub.lo2) = (to_units(la2, 1e-6), to_units(lon_360(lo2), 1e-6));
+                (sub.n_i, sub.n_j) = (ni, nj);
+                Self::Template3_10(sub)
+            }
+            Self::Template3_20(tmpl) => {
+                let mut sub = tmpl.clone();
+                let (la1, lo1) = first(tmpl)?;
+                (sub.la1, sub.lo1) = (to_units(la1, 1e-6), to_units(lon_360(lo1), 1e-6));
+                (sub.n_x, sub.n_y) = (ni, nj);
+                Self::Template3_20(sub)
+            }
+            Self::Template3_30(tmpl) => {
+                let mut sub = tmpl.clone();
+                let (la1, lo1) = first(tmpl)?;
+                (sub.la1, sub.lo1) = (to_units(la1, 1e-6), to_units(lon_360(lo1), 1e-6));
+                (sub.n_x, sub.n_y) = (ni, nj);
+                Self::Template3_30(sub)
+            }
+            Self::Template3_40(tmpl) if !tmpl.is_reduced() => {
+                let mut sub = tmpl.clone();
+                let unit = super::angle_unit(tmpl.basic_angle, tmpl.subdivisions_of_basic_angle);
+                let ((la1, lo1), (la2, lo2)) = (first(tmpl)?, last(tmpl)?);
+                (sub.la1, sub.lo1) = (to_units(la1, unit), to_units(lon_360(lo1), unit));
+                (sub.la2, sub.lo2) = (to_units(la2, unit), to_units(lon_360(lo2), unit));
+                (sub.n_i, sub.n_j) = (ni, nj);
+                Self::Template3_40(sub)
+            }
+            Self::Template3_90(tmpl) => {
+                // The sector origin is its south-west corner in image coordinates
+                let mut sub = tmpl.clone();
+                let west = match scan.i_negative() {
+                    true => window.grid_ni - window.i0 - window.ni,
+                    false => window.i0,
+                };
+                let south = match scan.j_positive() {
+                    true => window.j0,
+                    false => window.grid_nj - window.j0 - window.nj,
+                };
+                sub.x_o += west as u32;
+                sub.y_o += south as u32;
+                (sub.n_x, sub.n_y) = (ni, nj);
+                Self::Template3_90(sub)
+            }
+            Self::Template3_140(tmpl) => {
+                let mut sub = tmpl.clone();
+                let (la1, lo1) = first(tmpl)?;
+                (sub.la1, sub.lo1) = (to_units(la1, 1e-6), to_units(lon_360(lo1), 1e-6));
+                (sub.n_x, sub.n_y) = (ni, nj);
+                Self::Template3_140(sub)
+            }
+            _ => return None,
+        })
+    }
+}
+
+impl GridDefinition {
+    /// Smallest window of the grid whose cells cover the bounding box
+    ///
+    /// None if the grid isn't laid out on an `ni` x `nj` plane or if the box misses it.
+    pub fn window(&self, bbox: &BoundingBox) -> Option<GridWindow> {
+        GridWindow::covering(self.as_structured()?, bbox)
+    }
+
+    /// Grid definition of the points in `window`
+    ///
+    /// The section 3 header is that of a section holding the template alone.
+    pub fn subset(&self, window: &GridWindow) -> Result<Self> {
+        let template = match self.quasi_regular {
+            Some(_) => None,
+            None => self.template.subset(window),
+        };
+        let template = template.ok_or_else(|| {
+            Error::UnsupportedData(format!(
+                "grids of template 3.{} can't be subset",
+                self.template.template_number()
+            ))
+        })?;
+        let mut header = self.header.clone();
+        header.section_length = 14 + template.body_len();
+        header.source_of_grid_definition = 0;
+        header.number_of_data_points = (window.ni * window.nj) as u32;
+        header.number_of_octects_for_number_of_points = 0;
+        header.interpretation_of_number_of_points = 0;
+        header.template_number = template.template_number();
+        Ok(Self {
+            header,
+            template,
+            number_of_points: vec![],
+            quasi_regular: None,
+            unstructured: None,
+            curvilinear: None,
+        })
+    }
+}
+
+/// Angle in degrees as an integer number of `unit`s
+fn to_units(degrees: f64, unit: f64) -> i32 {
+    (degrees / unit).round() as i32
+}
+
+fn lon_360(lon: f64) -> f64 {
+    lon.rem_euclid(360.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::{GridCatalog, predefined_grid as predefined};
+    use crate::templates::GridDefinitionTemplate3_0;
+
+    /// 4 x 3 grid of 10° from 20N 0E to 0N 30E
+    fn regional_grid() -> GridDefinitionTemplate3_0 {
+        let Some(GridDefinitionTemplate::Template3_0(tmpl)) = GridCatalog::builtin().get(3) else {
+            unreachable!()
+        };
+        GridDefinitionTemplate3_0 {
+            n_i: 4,
+            n_j: 3,
+            la1: 20_000_000,
+            lo1: 0,
+            la2: 0,
+            lo2: 30_000_000,
+            d_i: 10_000_000,
+            d_j: 10_000_000,
+            ..tmpl.clone()
+        }
+    }
+
+    fn columns(window: Option<GridWindow>) -> Option<(usize, usize)> {
+        window.map(|window| (window.i0, window.ni))
+    }
+
+    #[test]
+    fn intersects_boxes() {
+        let grid = BoundingBox::new(0.0, 20.0, 0.0, 30.0);
+        let clip = |west, east| BoundingBox::new(-10.0, 10.0, west, east).intersection(&grid);
+        assert_eq!(
+            clip(-10.0, 15.0),
+            Some(BoundingBox::new(0.0, 10.0, 0.0, 15.0))
+        );
+        assert_eq!(
+            clip(25.0, 40.0),
+            Some(BoundingBox::new(0.0, 10.0, 25.0, 30.0))
+        );
+        assert_eq!(clip(40.0, 50.0), None);
+        // Overlapping both ends
+        assert_eq!(
+            clip(20.0, 10.0),
+            Some(BoundingBox::new(0.0, 10.0, 0.0, 30.0))
+        );
+        assert_eq!(
+            BoundingBox::new(30.0, 40.0, 0.0, 1.0).intersection(&grid),
+            None
+        );
+    }
+
+    #[test]
+    fn windows_overlapping_the_edges_of_regional_grids() {
+        let grid = regional_grid();
+        assert!(!grid.is_periodic());
+        let covering = |west, east| {
+            columns(GridWindow::covering(
+                &grid,
+                &BoundingBox::new(5.0, 15.0, west, east),
+            ))
+        };
+        assert_eq!(covering(-10.0, 15.0), Some((0, 3)));
+        assert_eq!(covering(-10.0, 5.0), Some((0, 2)));
+        assert_eq!(covering(25.0, 40.0), Some((2, 2)));
+        assert_eq!(covering(-20.0, 50.0), Some((0, 4)));
+        assert_eq!(covering(40.0, 50.0), None);
+        assert_eq!(covering(-50.0, -10.0), None);
+    }
+
+    #[test]
+    fn windows_of_global_grids_wrap() {
+        let grid = predefined(3);
+        let window = grid
+            .window(&BoundingBox::new(-5.0, 5.0, -10.0, 10.0))
+            .unwrap();
+        assert_eq!(
+            (window.i0, window.ni, window.j0, window.nj),
+            (350, 21, 85, 11)
+        );
+        assert!(window.wraps());
+        assert_eq!(window.source_index(0), 85 * 360 + 350);
+        assert_eq!(window.source_index(10), 85 * 360);
+        assert_eq!(
+            window.source_ranges()[..2],
+            [85 * 360 + 350..86 * 360, 85 * 360..85 * 360 + 11]
+        );
+    }
+
+    #[test]
+    fn only_periodic_windows_wrap() {
+        let scan = ScanningMode(0);
+        assert!(GridWindow::new((350, 0), (20, 1), (360, 181), scan, true).is_ok());
+        assert!(matches!(
+            GridWindow::new((350, 0), (20, 1), (360, 181), scan, false),
+            Err(Error::InvalidData(_))
+        ));
+        assert!(GridWindow::new((340, 0), (20, 1), (360, 181), scan, false).is_ok());
+        assert!(GridWindow::new((0, 180), (1, 2), (360, 181), scan, true).is_err());
+    }
+
+    #[test]
+    fn subsets_grid_definitions() {
+        let grid = predefined(3);
+        let window = grid
+            .window(&BoundingBox::new(40.0, 50.0, 10.0, 20.0))
+            .unwrap();
+        let sub = grid.subset(&window).unwrap();
+        assert_eq!(sub.header.section_length, 72);
+        assert_eq!(sub.header.source_of_grid_definition, 0);
+        assert_eq!(sub.header.number_of_data_points, 11 * 11);
+        let geometry = sub.as_geometry().unwrap();
+        assert_eq!(geometry.latlon(0), Some((50.0, 10.0)));
+        assert_eq!(geometry.latlon(120), Some((40.0, 20.0)));
+
+        let values: Vec<usize> = (0..360 * 181).collect();
+        let extracted = window.extract(&values).unwrap();
+        assert_eq!(extracted[0], 40 * 360 + 10);
+        assert_eq!(extracted.len(), 121);
+
+        // Windows of projected grids never wrap
+        let grid = predefined(211);
+        let window = grid
+            .window(&BoundingBox::new(30.0, 40.0, -100.0, -90.0))
+            .unwrap();
+        assert!(!window.wraps());
+        let sub = grid.subset(&window).unwrap();
+        assert_eq!(sub.header.section_length, 81);
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::BufRead;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+#[cfg(test)]
+use super::GridDefinition;
+use super::neighbours::PointTree;
+use super::{GridGeometry, normalize_longitude};
+use crate::templates::GridDefinitionTemplate3_101;
+use crate::{Error, Result};
+
+/// Grid whose points are located by explicit coordinates, such as the triangular ICON grids
+#[derive(Debug, Clone)]
+pub struct UnstructuredGrid {
+    latitudes: Vec<f64>,
+    longitudes: Vec<f64>,
+    tree: OnceLock<PointTree>,
+}
+
+impl UnstructuredGrid {
+    /// Builds the grid from the latitude and longitude in degrees of every point, in data order.
+    pub fn new(latitudes: Vec<f64>, longitudes: Vec<f64>) -> Result<Self> {
+        if latitudes.len() != longitudes.len() {
+            return Err(Error::InvalidData(format!(
+                "{} latitudes were given for {} longitudes",
+                latitudes.len(),
+                longitudes.len()
+            )));
+        }
+        Ok(Self {
+            latitudes,
+            longitudes,
+            tree: OnceLock::new(),
+        })
+    }
+
+    /// Reads the coordinates from a text file with one point per line.
+    ///
+    /// Every line holds the latitude and longitude in degrees, separated by whitespace or a
+    /// comma. Empty lines and lines starting with `#` are skipped.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Self::from_reader(std::io::BufReader::new(file))
+    }
+
+    /// Reads the coordinates in the format of [`UnstructuredGrid::from_file`].
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self> {
+        let mut latitudes = Vec::new();
+        let mut longitudes = Vec::new();
+        for (n, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|f| !f.is_empty())
+                .map(str::parse::<f64>);
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some(Ok(lat)), Some(Ok(lon)), None) => {
+                    latitudes.push(lat);
+                    longitudes.push(lon);
+                }
+                _ => {
+                    return Err(Error::InvalidData(format!(
+                        "line {} of the coordinate file is not a latitude and a longitude",
+                        n + 1
+                    )));
+                }
+            }
+        }
+        Self::new(latitudes, longitudes)
+    }
+
+    /// Latitudes in degrees of the points, in data order
+    pub fn latitudes(&self) -> &[f64] {
+        &self.latitudes
+    }
+
+    /// Longitudes in degrees of the points, in data order
+    pub fn longitudes(&self) -> &[f64] {
+        &self.longitudes
+    }
+
+    /// Tree of the points for nearest-neighbour searches, built on first use and shared by the
+    /// fields on the grid
+    pub(crate) fn tree(&self) -> &PointTree {
+        self.tree.get_or_init(|| {
+            PointTree::new(
+                self.latitudes
+                    .iter()
+                    .zip(&self.longitudes)
+                    .map(|(&lat, &lon)| (lat, lon)),
+            )
+        })
+    }
+}
+
+impl GridGeometry for UnstructuredGrid {
+    fn number_of_points(&self) -> usize {
+        self.latitudes.len()
+    }
+
+    fn latlon(&self, index: usize) -> Option<(f64, f64)> {
+        let lat = *self.latitudes.get(index)?;
+        Some((lat, normalize_longitude(self.longitudes[index])))
+    }
+
+    fn grid_rotation(&self, _index: usize) -> Option<f64> {
+        Some(0.0)
+    }
+
+    fn uv_relative_to_grid(&self) -> bool {
+        false
+    }
+}
+
+/// Source of the coordinates of unstructured grids, looked up by the UUID of the grid
+pub trait CoordinateProvider {
+    fn coordinates(&self, uuid: &[u8; 16]) -> Option<Arc<UnstructuredGrid>>;
+}
+
+impl<F: Fn(&[u8; 16]) -> Option<Arc<UnstructuredGrid>>> CoordinateProvider for F {
+    fn coordinates(&self, uuid: &[u8; 16]) -> Option<Arc<UnstructuredGrid>> {
+        self(uuid)
+    }
+}
+
+/// [`CoordinateProvider`] holding the grids registered by the user
+#[derive(Debug, Default, Clone)]
+pub struct CoordinateRegistry {
+    grids: HashMap<[u8; 16], Arc<UnstructuredGrid>>,
+}
+
+impl CoordinateRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the coordinates of the grid with `uuid`, replacing any previous ones.
+    pub fn register(&mut self, uuid: [u8; 16], grid: UnstructuredGrid) {
+        self.grids.insert(uuid, Arc::new(grid));
+    }
+
+    /// Registers the coordinates of the grid with `uuid` read from a file in the format of
+    /// [`UnstructuredGrid::from_file`].
+    pub fn register_file<P: AsRef<Path>>(&mut self, uuid: [u8; 16], path: P) -> Result<()> {
+        self.register(uuid, UnstructuredGrid::from_file(path)?);
+        Ok(())
+    }
+}
+
+impl CoordinateProvider for CoordinateRegistry {
+    fn coordinates(&self, uuid: &[u8; 16]) -> Option<Arc<UnstructuredGrid>> {
+        self.grids.get(uuid).cloned()
+    }
+}
+
+impl GridDefinitionTemplate3_101 {
+    /// UUID of the horizontal grid in its usual hyphenated hexadecimal form
+    pub fn uuid_string(&self) -> String {
+        let mut s = String::with_capacity(36);
+        for (k, b) in self.uuid_of_h_grid.iter().enumerate() {
+            if matches!(k, 4 | 6 | 8 | 10) {
+                s.push('-');
+            }
+            write!(s, "{:02x}", b).unwrap();
+        }
+        s
+    }
+}
+
+/// Grid definition of template 3.101 whose coordinates are resolved to the given points
+#[cfg(test)]
+pub(crate) fn unstructured_grid(latitudes: Vec<f64>, longitudes: Vec<f64>) -> GridDefinition {
+    let grid = Arc::new(UnstructuredGrid::new(latitudes, longitudes).unwrap());
+    let mut body = vec![6, 0, 0, 1, 1];
+    body.extend([0; 16]);
+    let header = crate::message::GridDefinitionSectionHeader {
+        section_length: 14 + body.len() as u32,
+        source_of_grid_definition: 0,
+        number_of_data_points: grid.number_of_points() as u32,
+        number_of_octects_for_number_of_points: 0,
+        interpretation_of_number_of_points: 0,
+        template_number: 101,
+    };
+    let mut definition = GridDefinition::read(header, &mut &body[..]).unwrap();
+    definition
+        .resolve_coordinates(&|_: &[u8; 16]| Some(grid.clone()))
+        .unwrap();
+    definition
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::GridDefinitionSectionHeader;
+
+    const UUID: [u8; 16] = [
+        0x6b, 0xa7, 0xb8, 0x10, 0x9d, 0xad, 0x11, 0xd1, 0x80, 0xb4, 0x00, 0xc0, 0x4f, 0xd4, 0x30,
+        0xc8,
+    ];
+
+    fn section(number_of_data_points: u32) -> GridDefinition {
+        let mut body = vec![6, 0, 0, 26, 1];
+        body.extend(UUID);
+        let header = GridDefinitionSectionHeader {
+            section_length: 14 + body.len() as u32,
+            source_of_grid_definition: 0,
+            number_of_data_points,
+            number_of_octects_for_number_of_points: 0,
+            interpretation_of_number_of_points: 0,
+            template_number: 101,
+        };
+        GridDefinition::read(header, &mut &body[..]).unwrap()
+    }
+
+    #[test]
+    fn reads_coordinate_files() {
+        let text = "# lat lon\n10.5, 200\n\n-20 30.25\n  0,0  \n";
+        let grid = UnstructuredGrid::from_reader(text.as_bytes()).unwrap();
+        assert_eq!(grid.latitudes(), [10.5, -20.0, 0.0]);
+        assert_eq!(grid.longitudes(), [200.0, 30.25, 0.0]);
+        assert_eq!(grid.latlon(0), Some((10.5, -160.0)));
+        assert_eq!(grid.latlon(3), None);
+
+        let error = UnstructuredGrid::from_reader("1 2\n3 x\n".as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("line 2"), "{}", error);
+        assert!(UnstructuredGrid::from_reader("1 2 3\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn resolves_coordinates_by_uuid() {
+        let mut registry = CoordinateRegistry::new();
+        let grid = UnstructuredGrid::new(vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]).unwrap();
+        registry.register(UUID, grid);
+
+        let mut definition = section(3);
+        assert!(definition.as_geometry().is_none());
+        let crate::templates::GridDefinitionTemplate::Template3_101(tmpl) = &definition.template
+        else {
+            panic!("not template 3.101");
+        };
+        assert_eq!(tmpl.uuid_string(), "6ba7b810-9dad-11d1-80b4-00c04fd430c8");
+        assert_eq!(tmpl.number_of_grid_used, 26);
+        definition.resolve_coordinates(&registry).unwrap();
+        assert_eq!(
+            definition.as_geometry().unwrap().latlon(2),
+            Some((3.0, 6.0))
+        );
+
+        // Wrong number of points, unknown grid
+        assert!(section(4).resolve_coordinates(&registry).is_err());
+        let none = |_: &[u8; 16]| None;
+        assert!(section(3).resolve_coordinates(&none).is_err());
+    }
+}
@@ -0,0 +1,122 @@
+use super::GridGeometry;
+use crate::{Error, Result};
+
+/// Rotates grid-relative u/v components in place so that they point east/north.
+///
+/// Nothing is done when the grid says its components are already earth-relative. Points where
+/// the grid orientation is undefined are set to NAN.
+pub fn rotate_winds_to_earth<G: GridGeometry + ?Sized>(
+    grid: &G,
+    u: &mut [f32],
+    v: &mut [f32],
+) -> Result<()> {
+    if !grid.uv_relative_to_grid() {
+        return Ok(());
+    }
+    rotate_winds(grid, u, v, 1.0)
+}
+
+/// Rotates earth-relative u/v components in place so that they follow the grid axes.
+///
+/// This is the inverse of [`rotate_winds_to_earth`]. The components must be earth-relative, e.g.
+/// the output of [`rotate_winds_to_earth`]; they are rotated whatever the component flag of the
+/// grid says. Points where the grid orientation is undefined are set to NAN.
+pub fn rotate_winds_to_grid<G: GridGeometry + ?Sized>(
+    grid: &G,
+    u: &mut [f32],
+    v: &mut [f32],
+) -> Result<()> {
+    rotate_winds(grid, u, v, -1.0)
+}
+
+fn rotate_winds<G: GridGeometry + ?Sized>(
+    grid: &G,
+    u: &mut [f32],
+    v: &mut [f32],
+    direction: f64,
+) -> Result<()> {
+    if u.len() != grid.number_of_points() || v.len() != grid.number_of_points() {
+        return Err(Error::InvalidData(format!(
+            "u and v must have {} values, but got {} and {}",
+            grid.number_of_points(),
+            u.len(),
+            v.len()
+        )));
+    }
+    for (index, (u, v)) in u.iter_mut().zip(v.iter_mut()).enumerate() {
+        let Some(angle) = grid.grid_rotation(index) else {
+            (*u, *v) = (f32::NAN, f32::NAN);
+            continue;
+        };
+        let (sin, cos) = (direction * angle).sin_cos();
+        let (gu, gv) = (*u as f64, *v as f64);
+        *u = (gu * cos - gv * sin) as f32;
+        *v = (gu * sin + gv * cos) as f32;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::GridCatalog;
+
+    #[test]
+    fn lambert_rotation_round_trips() {
+        // NCEP grid 211: tangent cone at 25N, lov = 265E, grid-relative components
+        let grid = GridCatalog::builtin()
+            .get(211)
+            .unwrap()
+            .as_geometry()
+            .unwrap();
+        assert!(grid.uv_relative_to_grid());
+        let n = grid.number_of_points();
+        let u0: Vec<f32> = (0..n).map(|k| (k % 17) as f32 - 8.0).collect();
+        let v0: Vec<f32> = (0..n).map(|k| (k % 11) as f32 - 5.0).collect();
+        let (mut u, mut v) = (u0.clone(), v0.clone());
+        rotate_winds_to_earth(grid, &mut u, &mut v).unwrap();
+        assert_ne!(u, u0);
+        rotate_winds_to_grid(grid, &mut u, &mut v).unwrap();
+        for k in 0..n {
+            assert!((u[k] - u0[k]).abs() < 1e-4, "u at {}", k);
+            assert!((v[k] - v0[k]).abs() < 1e-4, "v at {}", k);
+        }
+    }
+
+    #[test]
+    fn lambert_rotation_follows_the_cone() {
+        // The +x axis is turned clockwise from east by n (lon - lov), with n = sin(25°)
+        let grid = GridCatalog::builtin()
+            .get(211)
+            .unwrap()
+            .as_geometry()
+            .unwrap();
+        let cone = 25f64.to_radians().sin();
+        for index in [0, 46, 92, 3000, 6044] {
+            let (_, lon) = grid.latlon(index).unwrap();
+            let expected = -cone * ((lon - 265.0 + 540.0) % 360.0 - 180.0).to_radians();
+            let angle = grid.grid_rotation(index).unwrap();
+            assert!((angle - expected).abs() < 1e-6, "index {}", index);
+        }
+
+        // A wind along +x at the first point (lon 226.5E), west of lov, blows towards the
+        // north-east of the earth
+        let (mut u, mut v) = (vec![0.0; 6045], vec![0.0; 6045]);
+        u[0] = 10.0;
+        rotate_winds_to_earth(grid, &mut u, &mut v).unwrap();
+        let angle = cone * (265.0 - 226.541f64).to_radians();
+        assert!((u[0] as f64 - 10.0 * angle.cos()).abs() < 1e-4);
+        assert!((v[0] as f64 - 10.0 * angle.sin()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rejects_mismatched_lengths() {
+        let grid = GridCatalog::builtin()
+            .get(211)
+            .unwrap()
+            .as_geometry()
+            .unwrap();
+        let (mut u, mut v) = (vec![0.0; 10], vec![0.0; 10]);
+        assert!(rotate_winds_to_grid(grid, &mut u, &mut v).is_err());
+    }
+}
@@ -1,3 +1,6 @@
+pub mod export;
+pub mod field;
+pub mod grid;
 pub mod message;
 pub mod reader;
 pub mod templates;
@@ -16,3 +19,20 @@ pub enum Error {
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
+
+/// Xorshift64 sequence of pseudo-random numbers following `seed`, for deterministic test data
+#[cfg(test)]
+pub(crate) fn xorshift(seed: u64) -> impl Iterator<Item = u64> {
+    std::iter::successors(Some(seed), |&state| {
+        let state = state ^ (state << 13);
+        let state = state ^ (state >> 7);
+        Some(state ^ (state << 17))
+    })
+    .skip(1)
+}
+
+/// `len` pseudo-random bytes from the [`xorshift`] sequence following `seed`
+#[cfg(test)]
+pub(crate) fn noise(seed: u64, len: usize) -> Vec<u8> {
+    xorshift(seed).take(len).map(|state| state as u8).collect()
+}
@@ -147,7 +147,7 @@ impl LocalUseSectionHeader {
 }
 
 /// Section 3: GRID DEFINITION SECTION (GDS)
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct GridDefinitionSectionHeader {
     pub section_length: u32,
     pub source_of_grid_definition: u8,
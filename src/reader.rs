@@ -1,4 +1,4 @@
-use std::io::Read;
+use std::io::{Read, Seek, SeekFrom};
 
 use byteorder::ReadBytesExt;
 
@@ -174,3 +174,167 @@ pub trait MessageReader<R: Read> {
         Ok(Some(()))
     }
 }
+
+/// Position of a GRIB2 message within a file or buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageLocation {
+    pub offset: u64,
+    pub length: u64,
+}
+
+/// Finds the boundaries of every message without decoding them.
+///
+/// Only section 0 of each message is read; the rest is skipped using its total length.
+pub fn scan_messages<R: Read + Seek>(reader: &mut R) -> Result<Vec<MessageLocation>> {
+    let mut locations = vec![];
+    loop {
+        let offset = reader.stream_position()?;
+        match reader.read_u32::<byteorder::LittleEndian>() {
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+            Ok(0x42495247) => {} // b"GRIB"
+            Ok(_) => {
+                return Err(Error::InvalidData(
+                    "message identifier must be 'GRIB'".to_string(),
+                ));
+            }
+        };
+        let is = IndicatorSectionHeader::read(reader)?;
+        if is.total_length < 16 {
+            return Err(Error::InvalidData(format!(
+                "total length of message is too short: {}",
+                is.total_length
+            )));
+        }
+        let end = offset.checked_add(is.total_length).ok_or_else(|| {
+            Error::InvalidData(format!(
+                "total length of message is too long: {}",
+                is.total_length
+            ))
+        })?;
+        locations.push(MessageLocation {
+            offset,
+            length: is.total_length,
+        });
+        reader.seek(SeekFrom::Start(end))?;
+    }
+    Ok(locations)
+}
+
+/// Splits an in-memory GRIB2 file into its messages.
+pub fn split_messages(data: &[u8]) -> Result<Vec<&[u8]>> {
+    scan_messages(&mut std::io::Cursor::new(data))?
+        .into_iter()
+        .map(|loc| {
+            data.get(loc.offset as usize..(loc.offset + loc.length) as usize)
+                .ok_or_else(|| Error::InvalidData("message is truncated".to_string()))
+        })
+        .collect()
+}
+
+/// Reads every message of an in-memory GRIB2 file in parallel.
+///
+/// Message boundaries are found first with [`split_messages`], then each message is handed to a
+/// fresh reader from `new_reader`. The returned readers are in message order.
+#[cfg(feature = "rayon")]
+pub fn par_read_messages<M, F>(data: &[u8], new_reader: F) -> Result<Vec<M>>
+where
+    M: for<'a> MessageReader<&'a [u8]> + Send,
+    F: Fn() -> M + Sync,
+{
+    par_decode_messages(data, |mut message| {
+        let mut reader = new_reader();
+        reader.read_next_message(&mut message)?;
+        Ok(reader)
+    })
+}
+
+/// Decodes every message of an in-memory GRIB2 file in parallel with `decode`, preserving message
+/// order in the output.
+#[cfg(feature = "rayon")]
+pub fn par_decode_messages<T, F>(data: &[u8], decode: F) -> Result<Vec<T>>
+where
+    T: Send,
+    F: Fn(&[u8]) -> Result<T> + Sync,
+{
+    use rayon::prelude::*;
+
+    split_messages(data)?.into_par_iter().map(&decode).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A message of `length` octets holding section 0 and padding, ending with "7777"
+    fn message(length: u64) -> Vec<u8> {
+        let mut data = b"GRIB\0\0\0\x02".to_vec();
+        data.extend(length.to_be_bytes());
+        data.resize(length as usize - 4, 0);
+        data.extend(b"7777");
+        data
+    }
+
+    #[test]
+    fn scans_and_splits_messages() {
+        let data = [message(20), message(36), message(24)].concat();
+        let locations = scan_messages(&mut std::io::Cursor::new(&data)).unwrap();
+        assert_eq!(
+            locations,
+            [
+                MessageLocation {
+                    offset: 0,
+                    length: 20
+                },
+                MessageLocation {
+                    offset: 20,
+                    length: 36
+                },
+                MessageLocation {
+                    offset: 56,
+                    length: 24
+                },
+            ]
+        );
+        let messages = split_messages(&data).unwrap();
+        assert_eq!(
+            messages.iter().map(|m| m.len()).collect::<Vec<_>>(),
+            [20, 36, 24]
+        );
+        assert!(
+            messages
+                .iter()
+                .all(|m| m.starts_with(b"GRIB") && m.ends_with(b"7777"))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_lengths() {
+        let mut data = message(20);
+        data[8..16].copy_from_slice(&u64::MAX.to_be_bytes());
+        let mut data = [message(20), data].concat();
+        assert!(matches!(
+            scan_messages(&mut std::io::Cursor::new(&data)),
+            Err(Error::InvalidData(_))
+        ));
+        data[28..36].copy_from_slice(&8u64.to_be_bytes());
+        assert!(matches!(
+            scan_messages(&mut std::io::Cursor::new(&data)),
+            Err(Error::InvalidData(_))
+        ));
+        data[28..36].copy_from_slice(&100u64.to_be_bytes());
+        assert!(matches!(split_messages(&data), Err(Error::InvalidData(_))));
+        assert!(matches!(
+            scan_messages(&mut std::io::Cursor::new(b"GRIT")),
+            Err(Error::InvalidData(_))
+        ));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn decodes_messages_in_order() {
+        let data = [message(20), message(36), message(24)].concat();
+        let lengths = par_decode_messages(&data, |m| Ok(m.len())).unwrap();
+        assert_eq!(lengths, [20, 36, 24]);
+    }
+}
@@ -0,0 +1,105 @@
+use std::io::Read;
+
+use crate::Result;
+
+/// Bit-map carried in section 6 (bit-map indicator 0)
+///
+/// Bit `k` (most significant bit first) tells whether grid point `k` has a value in the data
+/// section. Set bits are counted per 64-point block up front, so the position of a point's value
+/// in the packed data can be found in constant time.
+#[derive(Debug, Clone)]
+pub struct Bitmap {
+    bits: Vec<u8>,
+    len: usize,
+    block_ranks: Vec<u32>,
+}
+
+impl Bitmap {
+    /// Reads the bit-map for `number_of_data_points` grid points.
+    pub fn read<R: Read>(reader: &mut R, number_of_data_points: u32) -> Result<Self> {
+        let len = number_of_data_points as usize;
+        let mut bits = vec![0u8; len.div_ceil(8)];
+        reader.read_exact(&mut bits)?;
+        Ok(Self::from_bytes(bits, len))
+    }
+
+    /// Builds a bit-map from its packed octets. Bits beyond `len` are ignored.
+    pub fn from_bytes(mut bits: Vec<u8>, len: usize) -> Self {
+        bits.resize(len.div_ceil(8), 0);
+        if !len.is_multiple_of(8) {
+            let last = bits.len() - 1;
+            bits[last] &= 0xffu8 << (8 - len % 8);
+        }
+        let mut block_ranks = Vec::with_capacity(bits.len().div_ceil(8) + 1);
+        let mut rank = 0;
+        for block in bits.chunks(8) {
+            block_ranks.push(rank);
+            rank += block.iter().map(|b| b.count_ones()).sum::<u32>();
+        }
+        block_ranks.push(rank);
+        Self {
+            bits,
+            len,
+            block_ranks,
+        }
+    }
+
+    /// Number of grid points covered by the bit-map
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether grid point `index` has a value in the data section
+    pub fn is_present(&self, index: usize) -> bool {
+        index < self.len && self.bits[index / 8] & (0x80 >> (index % 8)) != 0
+    }
+
+    /// Number of grid points before `index` that have a value, i.e. the position of the value
+    /// of grid point `index` among the packed values.
+    pub fn rank(&self, index: usize) -> usize {
+        let index = index.min(self.len);
+        let block = index / 64;
+        let mut rank = self.block_ranks[block] as usize;
+        let start = block * 8;
+        let end = index / 8;
+        rank += self.bits[start..end]
+            .iter()
+            .map(|b| b.count_ones() as usize)
+            .sum::<usize>();
+        if !index.is_multiple_of(8) {
+            rank += (self.bits[end] & !(0xffu8 >> (index % 8))).count_ones() as usize;
+        }
+        rank
+    }
+
+    /// Number of grid points that have a value
+    pub fn count_present(&self) -> usize {
+        self.rank(self.len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rank_counts_set_bits_before_index() {
+        let bytes: Vec<u8> = (0..40u32).map(|i| (i * 37 + 11) as u8).collect();
+        let len = 301;
+        let bitmap = Bitmap::from_bytes(bytes.clone(), len);
+        let is_set = |k: usize| bytes[k / 8] & (0x80 >> (k % 8)) != 0;
+        let mut rank = 0;
+        for index in 0..len {
+            assert_eq!(bitmap.rank(index), rank, "index {}", index);
+            assert_eq!(bitmap.is_present(index), is_set(index));
+            rank += usize::from(is_set(index));
+        }
+        assert_eq!(bitmap.count_present(), rank);
+        assert_eq!(bitmap.rank(len + 100), rank);
+        assert!(!bitmap.is_present(len));
+    }
+}
@@ -0,0 +1,866 @@
+pub mod unpack;
+
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
+
+use byteorder::ReadBytesExt;
+use itertools::Itertools;
+
+use crate::templates::data_representation::DataRepresentationTemplate5_200;
+use crate::templates::read_octets;
+use crate::{Error, Result};
+
+use super::{Bitmap, DataRepresentationTemplate5_0, DataRepresentationTemplate5_3};
+use unpack::{BitCursor, packed_len, unpack_bits};
+
+/// Reusable intermediate buffers for the `read_data_*_into` decoders
+///
+/// Keep one per decoding thread and pass it to every call, so that the buffers grow to the size
+/// of the largest field once and are reused afterwards.
+#[derive(Debug, Default)]
+pub struct DecodeScratch {
+    packed: Vec<u8>,
+    raw: Vec<u32>,
+    group_refs: Vec<u32>,
+    group_widths: Vec<u32>,
+    group_lengths: Vec<u32>,
+}
+
+impl DecodeScratch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Reads `count` packed values of `bits` bits each into `out`, consuming whole octets from the
+/// reader.
+fn read_packed_into<R: Read>(
+    reader: &mut R,
+    count: usize,
+    bits: u32,
+    packed: &mut Vec<u8>,
+    out: &mut Vec<u32>,
+) -> Result<()> {
+    packed.clear();
+    packed.resize(packed_len(count, bits), 0);
+    reader.read_exact(packed)?;
+    out.clear();
+    out.resize(count, 0);
+    unpack_bits(packed, 0, bits, out)
+}
+
+/// Template 7.0: Grid point data - simple packing
+///
+/// NAN is represented as i32::MIN
+pub fn read_data_7_0<R: Read>(
+    reader: &mut R,
+    number_of_values: u32,
+    tmpl: &DataRepresentationTemplate5_0,
+) -> Result<Vec<i32>> {
+    let mut values = Vec::new();
+    read_data_7_0_into(
+        reader,
+        number_of_values,
+        tmpl,
+        &mut DecodeScratch::new(),
+        &mut values,
+    )?;
+    Ok(values)
+}
+
+/// Template 7.0: Grid point data - simple packing, decoded into `values`
+///
+/// `values` is cleared first; its capacity is reused.
+pub fn read_data_7_0_into<R: Read>(
+    reader: &mut R,
+    number_of_values: u32,
+    tmpl: &DataRepresentationTemplate5_0,
+    scratch: &mut DecodeScratch,
+    values: &mut Vec<i32>,
+) -> Result<()> {
+    // TODO: handle NA value?
+    read_packed_into(
+        reader,
+        number_of_values as usize,
+        tmpl.bits_per_value as u32,
+        &mut scratch.packed,
+        &mut scratch.raw,
+    )?;
+    values.clear();
+    values.extend(scratch.raw.iter().map(|&v| v as i32));
+    Ok(())
+}
+
+/// Template 7.0: Grid point data - simple packing, value of a single grid point
+///
+/// The reader must be positioned at the start of the data section body; it is left there on
+/// return. Only the octets holding the requested value are read. With a bit-map, `index` is the
+/// grid point index and points without a value are returned as i32::MIN. `number_of_values` is
+/// the number of packed values given in section 5; indices beyond the values or the bit-map
+/// are rejected.
+pub fn read_data_7_0_at<R: Read + Seek>(
+    reader: &mut R,
+    number_of_values: u32,
+    tmpl: &DataRepresentationTemplate5_0,
+    bitmap: Option<&Bitmap>,
+    index: usize,
+) -> Result<i32> {
+    let start = reader.stream_position()?;
+    let value = read_packed_value_at(reader, start, number_of_values, tmpl, bitmap, index);
+    reader.seek(SeekFrom::Start(start))?;
+    value
+}
+
+/// Template 7.0: Grid point data - simple packing, values of a set of grid points
+///
+/// Same as [`read_data_7_0_at`] for many points at once. Values are returned in the order of
+/// `indices`.
+pub fn read_data_7_0_at_indices<R: Read + Seek>(
+    reader: &mut R,
+    number_of_values: u32,
+    tmpl: &DataRepresentationTemplate5_0,
+    bitmap: Option<&Bitmap>,
+    indices: &[usize],
+) -> Result<Vec<i32>> {
+    let start = reader.stream_position()?;
+    let values = indices
+        .iter()
+        .map(|&index| read_packed_value_at(reader, start, number_of_values, tmpl, bitmap, index))
+        .collect();
+    reader.seek(SeekFrom::Start(start))?;
+    values
+}
+
+/// Template 7.0: Grid point data - simple packing, values of runs of grid points
+///
+/// Reads the values of the grid points in `ranges`, in the order of the ranges, for instance the
+/// rows of a window (see [`GridWindow::source_ranges`](crate::grid::GridWindow::source_ranges)). Only the octets holding those values are
+/// read, so rows outside the window are never unpacked. The reader must be positioned at the
+/// start of the data section body; it is left there on return. With a bit-map, points without
+/// a value are returned as i32::MIN. Ranges must be increasing and within the values or the
+/// bit-map, as for [`read_data_7_0_at`].
+pub fn read_data_7_0_ranges<R: Read + Seek>(
+    reader: &mut R,
+    number_of_values: u32,
+    tmpl: &DataRepresentationTemplate5_0,
+    bitmap: Option<&Bitmap>,
+    ranges: &[Range<usize>],
+) -> Result<Vec<i32>> {
+    let start = reader.stream_position()?;
+    let mut values = Vec::new();
+    let result = read_packed_ranges(
+        reader,
+        start,
+        number_of_values,
+        tmpl,
+        bitmap,
+        ranges,
+        &mut values,
+    );
+    reader.seek(SeekFrom::Start(start))?;
+    result.map(|_| values)
+}
+
+fn read_packed_ranges<R: Read + Seek>(
+    reader: &mut R,
+    start: u64,
+    number_of_values: u32,
+    tmpl: &DataRepresentationTemplate5_0,
+    bitmap: Option<&Bitmap>,
+    ranges: &[Range<usize>],
+    values: &mut Vec<i32>,
+) -> Result<()> {
+    // Positions of the first and past-the-last values of every range among the packed values
+    let positions = ranges
+        .iter()
+        .map(|range| {
+            if range.start > range.end {
+                return Err(Error::InvalidData(format!(
+                    "range of grid points {}..{} is reversed",
+                    range.start, range.end
+                )));
+            }
+            Ok((
+                packed_position(number_of_values, bitmap, range.start)?,
+                packed_position(number_of_values, bitmap, range.end)?,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    values.reserve(ranges.iter().map(|r| r.len()).sum());
+
+    let bits = tmpl.bits_per_value as usize;
+    let (mut packed, mut raw) = (Vec::new(), Vec::new());
+    for (range, (k0, k1)) in ranges.iter().zip(positions) {
+        raw.clear();
+        raw.resize(k1 - k0, 0);
+        if bits > 0 && k1 > k0 {
+            let (first_bit, end_bit) = (k0 * bits, k1 * bits);
+            packed.clear();
+            packed.resize(end_bit.div_ceil(8) - first_bit / 8, 0);
+            reader.seek(SeekFrom::Start(start + (first_bit / 8) as u64))?;
+            reader.read_exact(&mut packed)?;
+            unpack_bits(&packed, first_bit % 8, bits as u32, &mut raw)?;
+        }
+        match bitmap {
+            Some(bitmap) => {
+                let mut raw = raw.iter();
+                values.extend(range.clone().map(|index| match bitmap.is_present(index) {
+                    true => *raw.next().unwrap() as i32,
+                    false => i32::MIN,
+                }));
+            }
+            None => values.extend(raw.iter().map(|&v| v as i32)),
+        }
+    }
+    Ok(())
+}
+
+/// Position among the packed values of the value at grid point `index`, or past-the-end
+/// position of the values before it, checked against the number of packed values
+fn packed_position(number_of_values: u32, bitmap: Option<&Bitmap>, index: usize) -> Result<usize> {
+    let (k, points) = match bitmap {
+        Some(bitmap) => (bitmap.rank(index.min(bitmap.len())), bitmap.len()),
+        None => (index, number_of_values as usize),
+    };
+    if index > points {
+        return Err(Error::InvalidData(format!(
+            "grid point {} is out of range: there are {} points",
+            index, points
+        )));
+    }
+    if k > number_of_values as usize {
+        return Err(Error::InvalidData(format!(
+            "bit-map has more points with a value than the {} packed values",
+            number_of_values
+        )));
+    }
+    Ok(k)
+}
+
+fn read_packed_value_at<R: Read + Seek>(
+    reader: &mut R,
+    start: u64,
+    number_of_values: u32,
+    tmpl: &DataRepresentationTemplate5_0,
+    bitmap: Option<&Bitmap>,
+    index: usize,
+) -> Result<i32> {
+    // Past-the-end position of the value, so that the last one is in range
+    let k = packed_position(number_of_values, bitmap, index.saturating_add(1))?;
+    let k = match bitmap {
+        Some(bitmap) if !bitmap.is_present(index) => return Ok(i32::MIN),
+        _ => k - 1,
+    };
+    let bits = tmpl.bits_per_value as u32;
+    if bits == 0 {
+        return Ok(0);
+    }
+    if bits > 32 {
+        return Err(Error::InvalidData(format!(
+            "bit width must be 32 or less, but got {}",
+            bits
+        )));
+    }
+    let bit_offset = k * bits as usize;
+    let mut buf = [0u8; 5];
+    let buf = &mut buf[..packed_len(1, bits + (bit_offset % 8) as u32)];
+    reader.seek(SeekFrom::Start(start + (bit_offset / 8) as u64))?;
+    reader.read_exact(buf)?;
+    let mut value = [0u32];
+    unpack_bits(buf, bit_offset % 8, bits, &mut value)?;
+    Ok(value[0] as i32)
+}
+
+/// Template 7.3: Grid point data - complex packing and spatial differencing
+///
+/// `size` is the length of the data section body and `number_of_values` the number of packed
+/// values given in section 5; templates describing more octets or values are rejected before
+/// anything is allocated.
+///
+/// NAN is represented as i32::MIN
+pub fn read_data_7_3<R: Read>(
+    reader: &mut R,
+    size: usize,
+    number_of_values: u32,
+    tmpl: &DataRepresentationTemplate5_3,
+) -> Result<Vec<i32>> {
+    let mut values = Vec::new();
+    read_data_7_3_into(
+        reader,
+        size,
+        number_of_values,
+        tmpl,
+        &mut DecodeScratch::new(),
+        &mut values,
+    )?;
+    Ok(values)
+}
+
+/// Template 7.3: Grid point data - complex packing and spatial differencing, decoded into `values`
+///
+/// `values` is cleared first; its capacity is reused.
+pub fn read_data_7_3_into<R: Read>(
+    mut reader: &mut R,
+    size: usize,
+    number_of_values: u32,
+    tmpl: &DataRepresentationTemplate5_3,
+    scratch: &mut DecodeScratch,
+    values: &mut Vec<i32>,
+) -> Result<()> {
+    let tmpl2 = &tmpl.template_2;
+    let tmpl0 = &tmpl2.template_0;
+    if tmpl.order_of_spatial_differencing != 2 {
+        return Err(Error::UnsupportedData(format!(
+            "Only 2nd order spatial differencing is supported, but got {}",
+            tmpl.order_of_spatial_differencing
+        )));
+    }
+    if !(1..=4).contains(&tmpl.number_of_octets_extra_descriptors) {
+        return Err(Error::InvalidData(format!(
+            "extra descriptors must be 1 to 4 octets long, but got {}",
+            tmpl.number_of_octets_extra_descriptors
+        )));
+    }
+    let ng = tmpl2.number_of_groups_of_data_values;
+    if ng == 0 {
+        return Err(Error::InvalidData(
+            "complex packing with no groups of data values".to_string(),
+        ));
+    }
+    // Octets of the extra descriptors and of the references, widths and lengths of the groups
+    let header_len = 3 * tmpl.number_of_octets_extra_descriptors as u64
+        + [
+            tmpl0.bits_per_value,
+            tmpl2.number_of_bits_used_for_the_group_widths,
+            tmpl2.number_of_bits_for_scaled_group_lengths,
+        ]
+        .iter()
+        .map(|&bits| (ng as u64 * bits as u64).div_ceil(8))
+        .sum::<u64>();
+    if header_len > size as u64 {
+        return Err(Error::InvalidData(format!(
+            "descriptors of {} groups need {} octets, but the data section has {}",
+            ng, header_len, size
+        )));
+    }
+    let z1: i32 = read_octets(&mut reader, tmpl.number_of_octets_extra_descriptors)?;
+    let z2: i32 = read_octets(&mut reader, tmpl.number_of_octets_extra_descriptors)?;
+    let z_min: i32 = read_octets(&mut reader, tmpl.number_of_octets_extra_descriptors)?;
+    read_packed_into(
+        reader,
+        ng as usize,
+        tmpl0.bits_per_value as u32,
+        &mut scratch.packed,
+        &mut scratch.group_refs,
+    )?;
+    read_packed_into(
+        reader,
+        ng as usize,
+        tmpl2.number_of_bits_used_for_the_group_widths as u32,
+        &mut scratch.packed,
+        &mut scratch.group_widths,
+    )?;
+    read_packed_into(
+        reader,
+        ng as usize,
+        tmpl2.number_of_bits_for_scaled_group_lengths as u32,
+        &mut scratch.packed,
+        &mut scratch.group_lengths,
+    )?;
+    // Turn the scaled widths and lengths into the actual ones in place
+    for (gi, (gw, gl)) in scratch
+        .group_widths
+        .iter_mut()
+        .zip_eq(scratch.group_lengths.iter_mut())
+        .enumerate()
+    {
+        *gw = gw
+            .checked_add(tmpl2.reference_for_group_widths as u32)
+            .ok_or_else(|| Error::InvalidData(format!("width of group {} overflows", gi)))?;
+        *gl = if (gi as u32) < ng - 1 {
+            (tmpl2.length_increment_for_the_group_lengths as u32)
+                .checked_mul(*gl)
+                .and_then(|l| l.checked_add(tmpl2.reference_for_group_lengths))
+                .ok_or_else(|| Error::InvalidData(format!("length of group {} overflows", gi)))?
+        } else {
+            tmpl2.true_length_of_last_group
+        };
+    }
+
+    let total_bits: u64 = scratch
+        .group_widths
+        .iter()
+        .zip(&scratch.group_lengths)
+        .map(|(&w, &l)| w as u64 * l as u64)
+        .sum();
+    let total_length: u64 = scratch.group_lengths.iter().map(|&l| l as u64).sum();
+    if total_length < 2 {
+        return Err(Error::InvalidData(format!(
+            "2nd order spatial differencing needs at least 2 values, but got {}",
+            total_length
+        )));
+    }
+    if total_length > number_of_values as u64 {
+        return Err(Error::InvalidData(format!(
+            "groups hold {} values, but section 5 gives {}",
+            total_length, number_of_values
+        )));
+    }
+    if header_len + total_bits.div_ceil(8) > size as u64 {
+        return Err(Error::InvalidData(format!(
+            "groups need {} octets, but the data section has {}",
+            header_len + total_bits.div_ceil(8),
+            size
+        )));
+    }
+    let (total_bits, total_length) = (total_bits as usize, total_length as usize);
+    scratch.packed.clear();
+    scratch.packed.resize(total_bits.div_ceil(8), 0);
+    reader.read_exact(&mut scratch.packed)?;
+    let mut cursor = BitCursor::new(&scratch.packed);
+    scratch.raw.clear();
+    scratch.raw.resize(total_length, 0);
+    values.clear();
+    values.reserve(total_length);
+    for ((&gref, &group_width), &group_length) in scratch
+        .group_refs
+        .iter()
+        .zip_eq(&scratch.group_widths)
+        .zip_eq(&scratch.group_lengths)
+    {
+        let raw = &mut scratch.raw[values.len()..values.len() + group_length as usize];
+        cursor.read_into(group_width, raw)?;
+        // Wrapping arithmetic so that corrupt data gives garbage values rather than a panic
+        let base = z_min.wrapping_add(gref as i32);
+        values.extend(raw.iter().map(|&v| base.wrapping_add(v as i32)));
+    }
+    values[0] = z1;
+    values[1] = z2;
+    for i in 2..values.len() {
+        values[i] = values[i]
+            .wrapping_add(values[i - 1].wrapping_mul(2))
+            .wrapping_sub(values[i - 2]);
+    }
+    Ok(())
+}
+
+/// Template 7.200 (Run length packing with level values)
+///
+/// NAN is represented as i32::MIN
+pub fn read_data_7_200<R: Read>(
+    reader: &mut R,
+    size: usize,
+    number_of_values: u32,
+    drs_template: &DataRepresentationTemplate5_200,
+) -> Result<Vec<i32>> {
+    let mut values = Vec::with_capacity(number_of_values as usize);
+    read_data_7_200_into(reader, size, drs_template, &mut values)?;
+    Ok(values)
+}
+
+/// Template 7.200 (Run length packing with level values), decoded into `values`
+///
+/// `values` is cleared first; its capacity is reused.
+pub fn read_data_7_200_into<R: Read>(
+    reader: &mut R,
+    size: usize,
+    drs_template: &DataRepresentationTemplate5_200,
+    values: &mut Vec<i32>,
+) -> Result<()> {
+    if drs_template.number_of_bits != 8 {
+        return Err(Error::UnsupportedData(format!(
+            "Only supports 8 bits in our 7.200 implementation, but got {}",
+            drs_template.number_of_bits
+        )));
+    }
+    values.clear();
+    let mut lv = reader.read_u8()?;
+    let mut p = 0;
+    while p < size {
+        p += 1;
+        let mut run_length: u32 = 1;
+        let mut m: u32 = 1;
+        let mut next = 0;
+        while p < size {
+            next = reader.read_u8()?;
+            if next as u16 > drs_template.mv {
+                run_length += (next as u16 - drs_template.mv - 1) as u32 * m;
+                m *= (255 - drs_template.mv) as u32;
+                p += 1;
+            } else {
+                break;
+            }
+        }
+        let value = match lv {
+            0 => i32::MIN,
+            _ => match drs_template
+                .mvl_scaled_representative_values
+                .get((lv - 1) as usize)
+            {
+                Some(&value) => value as i32,
+                None => {
+                    return Err(Error::InvalidData(format!(
+                        "level value {} is greater than the maximum {}",
+                        lv, drs_template.mvl
+                    )));
+                }
+            },
+        };
+        for _ in 0..run_length {
+            values.push(value);
+        }
+        lv = next;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use bitstream_io::{BigEndian, BitRead, BitReader};
+
+    use super::*;
+    use crate::noise;
+    use crate::templates::DataRepresentationTemplate5_2;
+
+    /// Bytes of an xorshift sequence, so that the tests do not depend on an RNG crate
+    /// The bit-by-bit 7.3 decoder this module replaced, with wrapping arithmetic so that random
+    /// input does not overflow
+    fn old_read_data_7_3<R: Read>(
+        mut reader: &mut R,
+        tmpl: &DataRepresentationTemplate5_3,
+    ) -> Vec<i32> {
+        let tmpl2 = &tmpl.template_2;
+        let tmpl0 = &tmpl2.template_0;
+        let n = tmpl.number_of_octets_extra_descriptors;
+        let z1: i32 = read_octets(&mut reader, n).unwrap();
+        let z2: i32 = read_octets(&mut reader, n).unwrap();
+        let z_min: i32 = read_octets(&mut reader, n).unwrap();
+        let ng = tmpl2.number_of_groups_of_data_values;
+        let mut reader = BitReader::<_, BigEndian>::new(&mut reader);
+        let mut read_groups = |bits: u8| {
+            let values = (0..ng)
+                .map(|_| reader.read_var::<u32>(bits as u32).unwrap())
+                .collect::<Vec<u32>>();
+            reader.byte_align();
+            values
+        };
+        let group_refs = read_groups(tmpl0.bits_per_value);
+        let group_widths = read_groups(tmpl2.number_of_bits_used_for_the_group_widths);
+        let group_lengths = read_groups(tmpl2.number_of_bits_for_scaled_group_lengths);
+        let mut values: Vec<i32> = vec![];
+        for (gi, ((gref, gw), gl)) in group_refs
+            .into_iter()
+            .zip_eq(group_widths)
+            .zip_eq(group_lengths)
+            .enumerate()
+        {
+            let group_width = tmpl2.reference_for_group_widths as u32 + gw;
+            let group_length = if (gi as u32) < ng - 1 {
+                tmpl2.reference_for_group_lengths
+                    + (tmpl2.length_increment_for_the_group_lengths as u32 * gl)
+            } else {
+                tmpl2.true_length_of_last_group
+            };
+            for _ in 0..group_length {
+                let v = reader.read_var::<u32>(group_width).unwrap();
+                values.push(z_min.wrapping_add(gref as i32).wrapping_add(v as i32));
+            }
+        }
+        values[0] = z1;
+        values[1] = z2;
+        for i in 2..values.len() {
+            values[i] = values[i]
+                .wrapping_add(2i32.wrapping_mul(values[i - 1]))
+                .wrapping_sub(values[i - 2]);
+        }
+        values
+    }
+
+    fn template_5_3(
+        bits_per_value: u8,
+        number_of_groups: u32,
+        width_bits: u8,
+        length_bits: u8,
+    ) -> DataRepresentationTemplate5_3 {
+        DataRepresentationTemplate5_3 {
+            template_2: DataRepresentationTemplate5_2 {
+                template_0: DataRepresentationTemplate5_0 {
+                    reference_value: 0.0,
+                    binary_scale_factor: 0,
+                    decimal_scale_factor: 0,
+                    bits_per_value,
+                    type_of_original_field_values: 0,
+                },
+                group_splitting_method_used: 1,
+                missing_value_management_used: 0,
+                primary_missing_value_substitute: 0,
+                secondary_missing_value_substitute: 0,
+                number_of_groups_of_data_values: number_of_groups,
+                reference_for_group_widths: 0,
+                number_of_bits_used_for_the_group_widths: width_bits,
+                reference_for_group_lengths: 10,
+                length_increment_for_the_group_lengths: 1,
+                true_length_of_last_group: 5,
+                number_of_bits_for_scaled_group_lengths: length_bits,
+            },
+            order_of_spatial_differencing: 2,
+            number_of_octets_extra_descriptors: 2,
+        }
+    }
+
+    #[test]
+    fn complex_packing_matches_old_decoder() {
+        let mut scratch = DecodeScratch::new();
+        let mut values = Vec::new();
+        for (seed, bits_per_value, ng, width_bits, length_bits) in [
+            (1, 8, 50, 3, 3),
+            (2, 10, 37, 3, 2),
+            (3, 13, 64, 2, 4),
+            (4, 5, 1, 3, 3),
+            (5, 16, 9, 4, 1),
+        ] {
+            let tmpl = template_5_3(bits_per_value, ng, width_bits, length_bits);
+            // Random input always long enough for the largest groups
+            let data = noise(seed, 4096);
+            let expected = old_read_data_7_3(&mut Cursor::new(&data), &tmpl);
+            let n = expected.len() as u32;
+            read_data_7_3_into(
+                &mut Cursor::new(&data),
+                data.len(),
+                n,
+                &tmpl,
+                &mut scratch,
+                &mut values,
+            )
+            .unwrap();
+            assert_eq!(values, expected, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn complex_packing_rejects_invalid_templates() {
+        let data = noise(1, 256);
+        let decode = |tmpl| read_data_7_3(&mut Cursor::new(&data), data.len(), 10_000, &tmpl);
+        assert!(matches!(
+            decode(template_5_3(8, 0, 3, 3)),
+            Err(Error::InvalidData(_))
+        ));
+        let mut tmpl = template_5_3(8, 1, 3, 3);
+        tmpl.template_2.true_length_of_last_group = 1;
+        assert!(matches!(decode(tmpl), Err(Error::InvalidData(_))));
+        let mut tmpl = template_5_3(8, 4, 3, 3);
+        tmpl.order_of_spatial_differencing = 1;
+        assert!(matches!(decode(tmpl), Err(Error::UnsupportedData(_))));
+        let mut tmpl = template_5_3(8, 4, 3, 3);
+        tmpl.number_of_octets_extra_descriptors = 0;
+        assert!(matches!(decode(tmpl), Err(Error::InvalidData(_))));
+    }
+
+    #[test]
+    fn complex_packing_rejects_sizes_beyond_the_section() {
+        let data = noise(1, 256);
+        let decode = |tmpl, number_of_values| {
+            read_data_7_3(&mut Cursor::new(&data), data.len(), number_of_values, &tmpl)
+        };
+        // Fits: 2 groups of 10 + 5 values at most 15 bits wide
+        let tmpl = template_5_3(8, 2, 4, 1);
+        assert_eq!(decode(tmpl, 15).unwrap().len(), 15);
+        // More values than section 5 gives
+        assert!(matches!(
+            decode(template_5_3(8, 2, 4, 1), 14),
+            Err(Error::InvalidData(_))
+        ));
+        // Descriptors of more groups than the section holds
+        assert!(matches!(
+            decode(template_5_3(8, u32::MAX, 4, 1), u32::MAX),
+            Err(Error::InvalidData(_))
+        ));
+        // Groups longer than the section
+        let mut tmpl = template_5_3(8, 2, 4, 1);
+        tmpl.template_2.reference_for_group_widths = 30;
+        tmpl.template_2.true_length_of_last_group = 1_000_000;
+        assert!(matches!(decode(tmpl, u32::MAX), Err(Error::InvalidData(_))));
+        // Widths overflowing
+        let mut tmpl = template_5_3(8, 2, 4, 1);
+        tmpl.template_2.reference_for_group_widths = 1;
+        tmpl.template_2.number_of_bits_used_for_the_group_widths = 32;
+        let data = [0xff; 256];
+        assert!(matches!(
+            read_data_7_3(&mut Cursor::new(&data), data.len(), u32::MAX, &tmpl),
+            Err(Error::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn run_length_packing_expands_runs() {
+        // Levels 1 and 2, runs continued by values above mv = 3 in base 255 - 3 = 252
+        let tmpl = DataRepresentationTemplate5_200 {
+            number_of_bits: 8,
+            mv: 3,
+            mvl: 3,
+            decimal_scale_factor: 0,
+            mvl_scaled_representative_values: vec![10, 20, 30],
+        };
+        let data = [1, 2, 5, 0, 3, 4 + 2, 4 + 1];
+        let values = read_data_7_200(&mut Cursor::new(&data), data.len(), 0, &tmpl).unwrap();
+        let mut expected = vec![10];
+        expected.extend([20; 2]);
+        expected.push(i32::MIN);
+        expected.extend([30; 1 + 2 + 252]);
+        assert_eq!(values, expected);
+
+        let data = [4, 1];
+        assert!(matches!(
+            read_data_7_200(&mut Cursor::new(&data), data.len(), 0, &tmpl),
+            Err(Error::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn point_access_matches_full_decode() {
+        let tmpl = DataRepresentationTemplate5_0 {
+            reference_value: 0.0,
+            binary_scale_factor: 0,
+            decimal_scale_factor: 0,
+            bits_per_value: 13,
+            type_of_original_field_values: 0,
+        };
+        let data = noise(7, packed_len(100, 13));
+        let all = read_data_7_0(&mut Cursor::new(&data), 100, &tmpl).unwrap();
+        let mut reader = Cursor::new(&data);
+        for index in [0, 1, 7, 50, 99] {
+            assert_eq!(
+                read_data_7_0_at(&mut reader, 100, &tmpl, None, index).unwrap(),
+                all[index]
+            );
+        }
+        let ranges = [3..9, 40..41, 90..100];
+        let expected: Vec<i32> = ranges
+            .iter()
+            .flat_map(|r| all[r.clone()].to_vec())
+            .collect();
+        assert_eq!(
+            read_data_7_0_ranges(&mut reader, 100, &tmpl, None, &ranges).unwrap(),
+            expected
+        );
+        assert_eq!(reader.position(), 0);
+    }
+
+    #[test]
+    fn point_access_with_bitmap() {
+        let tmpl = DataRepresentationTemplate5_0 {
+            reference_value: 0.0,
+            binary_scale_factor: 0,
+            decimal_scale_factor: 0,
+            bits_per_value: 7,
+            type_of_original_field_values: 0,
+        };
+        let bitmap = Bitmap::from_bytes(noise(3, 25), 200);
+        let present = bitmap.count_present();
+        let data = noise(9, packed_len(present, 7));
+        let packed = read_data_7_0(&mut Cursor::new(&data), present as u32, &tmpl).unwrap();
+        let mut packed = packed.into_iter();
+        let all: Vec<i32> = (0..200)
+            .map(|index| match bitmap.is_present(index) {
+                true => packed.next().unwrap(),
+                false => i32::MIN,
+            })
+            .collect();
+        let mut reader = Cursor::new(&data);
+        let indices: Vec<usize> = (0..200).step_by(3).collect();
+        let expected: Vec<i32> = indices.iter().map(|&index| all[index]).collect();
+        assert_eq!(
+            read_data_7_0_at_indices(&mut reader, present as u32, &tmpl, Some(&bitmap), &indices)
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            read_data_7_0_ranges(
+                &mut reader,
+                present as u32,
+                &tmpl,
+                Some(&bitmap),
+                &[10..60, 150..200]
+            )
+            .unwrap(),
+            [&all[10..60], &all[150..200]].concat()
+        );
+    }
+
+    #[test]
+    fn point_access_rejects_points_out_of_range() {
+        let tmpl = DataRepresentationTemplate5_0 {
+            reference_value: 0.0,
+            binary_scale_factor: 0,
+            decimal_scale_factor: 0,
+            bits_per_value: 8,
+            type_of_original_field_values: 0,
+        };
+        // 4 values followed by the end of the section
+        let data = [1, 2, 3, 4, b'7', b'7', b'7', b'7'];
+        let mut reader = Cursor::new(&data);
+        assert_eq!(read_data_7_0_at(&mut reader, 4, &tmpl, None, 3).unwrap(), 4);
+        assert!(matches!(
+            read_data_7_0_at(&mut reader, 4, &tmpl, None, 4),
+            Err(Error::InvalidData(_))
+        ));
+        assert!(matches!(
+            read_data_7_0_at_indices(&mut reader, 4, &tmpl, None, &[0, 5]),
+            Err(Error::InvalidData(_))
+        ));
+
+        let bitmap = Bitmap::from_bytes(vec![0b1011_0100], 6);
+        assert_eq!(
+            read_data_7_0_at_indices(&mut reader, 4, &tmpl, Some(&bitmap), &[0, 1, 5]).unwrap(),
+            [1, i32::MIN, 4]
+        );
+        assert!(matches!(
+            read_data_7_0_at(&mut reader, 4, &tmpl, Some(&bitmap), 6),
+            Err(Error::InvalidData(_))
+        ));
+        // More points with a value than packed values
+        assert!(matches!(
+            read_data_7_0_at(&mut reader, 3, &tmpl, Some(&bitmap), 5),
+            Err(Error::InvalidData(_))
+        ));
+
+        assert_eq!(
+            read_data_7_0_ranges(&mut reader, 4, &tmpl, None, &[2..4, 0..0]).unwrap(),
+            [3, 4]
+        );
+        assert_eq!(
+            read_data_7_0_ranges(&mut reader, 4, &tmpl, Some(&bitmap), &[4..6, 6..6]).unwrap(),
+            [i32::MIN, 4]
+        );
+        let reversed = Range { start: 3, end: 1 };
+        for ranges in [[0..1, 2..5], [0..1, reversed]] {
+            assert!(matches!(
+                read_data_7_0_ranges(&mut reader, 4, &tmpl, None, &ranges),
+                Err(Error::InvalidData(_))
+            ));
+        }
+        assert!(matches!(
+            read_data_7_0_ranges(&mut reader, 4, &tmpl, Some(&bitmap), &[0..1, 5..7]),
+            Err(Error::InvalidData(_))
+        ));
+        assert_eq!(reader.position(), 0);
+    }
+
+    #[test]
+    fn point_access_rejects_wide_values() {
+        let tmpl = DataRepresentationTemplate5_0 {
+            reference_value: 0.0,
+            binary_scale_factor: 0,
+            decimal_scale_factor: 0,
+            bits_per_value: 40,
+            type_of_original_field_values: 0,
+        };
+        let data = noise(1, 64);
+        assert!(matches!(
+            read_data_7_0_at(&mut Cursor::new(&data), 8, &tmpl, None, 3),
+            Err(Error::InvalidData(_))
+        ));
+    }
+}
@@ -0,0 +1,189 @@
+//! Word-at-a-time unpacking of big-endian packed unsigned integers.
+//!
+//! The kernels below read whole 64-bit words instead of single bits. Common widths (8, 12, 16,
+//! 24 and 32 bits) on byte-aligned input get dedicated loops over fixed-size chunks, which the
+//! compiler turns into SIMD code where the target supports it. Other widths and unaligned input
+//! fall back to the scalar 64-bit window. All paths produce exactly the values
+//! `bitstream_io::BitReader::read_var::<u32>` would.
+
+use crate::{Error, Result};
+
+/// Number of bytes needed to hold `count` values of `bits` bits each.
+pub fn packed_len(count: usize, bits: u32) -> usize {
+    (count * bits as usize).div_ceil(8)
+}
+
+/// Unpacks `out.len()` values of `bits` bits each, starting `bit_offset` bits into `data`.
+pub fn unpack_bits(data: &[u8], bit_offset: usize, bits: u32, out: &mut [u32]) -> Result<()> {
+    if bits > 32 {
+        return Err(Error::InvalidData(format!(
+            "bit width must be 32 or less, but got {}",
+            bits
+        )));
+    }
+    let end = bit_offset + out.len() * bits as usize;
+    if end > data.len() * 8 {
+        return Err(Error::InvalidData(format!(
+            "packed data is too short: {} bits required, but only {} bits available",
+            end,
+            data.len() * 8
+        )));
+    }
+
+    if bits == 0 {
+        out.fill(0);
+        return Ok(());
+    }
+    if !bit_offset.is_multiple_of(8) {
+        unpack_generic(data, bit_offset, bits, out);
+        return Ok(());
+    }
+
+    let data = &data[bit_offset / 8..];
+    match bits {
+        8 => {
+            for (o, &b) in out.iter_mut().zip(data) {
+                *o = b as u32;
+            }
+        }
+        16 => {
+            for (o, c) in out.iter_mut().zip(data.chunks_exact(2)) {
+                *o = u16::from_be_bytes([c[0], c[1]]) as u32;
+            }
+        }
+        24 => {
+            for (o, c) in out.iter_mut().zip(data.chunks_exact(3)) {
+                *o = u32::from_be_bytes([0, c[0], c[1], c[2]]);
+            }
+        }
+        32 => {
+            for (o, c) in out.iter_mut().zip(data.chunks_exact(4)) {
+                *o = u32::from_be_bytes([c[0], c[1], c[2], c[3]]);
+            }
+        }
+        12 => {
+            let n = out.len();
+            let mut pairs = out.chunks_exact_mut(2);
+            for (o, c) in (&mut pairs).zip(data.chunks_exact(3)) {
+                let w = u32::from_be_bytes([0, c[0], c[1], c[2]]);
+                o[0] = w >> 12;
+                o[1] = w & 0xfff;
+            }
+            if let [last] = pairs.into_remainder() {
+                let c = &data[(n - 1) / 2 * 3..];
+                *last = ((c[0] as u32) << 4) | (c[1] as u32 >> 4);
+            }
+        }
+        _ => unpack_generic(data, 0, bits, out),
+    }
+    Ok(())
+}
+
+/// Scalar fallback: slides a 64-bit big-endian window over the input for every value.
+fn unpack_generic(data: &[u8], bit_offset: usize, bits: u32, out: &mut [u32]) {
+    let shift = 64 - bits;
+    let mut pos = bit_offset;
+    for o in out.iter_mut() {
+        *o = ((load_word(data, pos / 8) << (pos % 8)) >> shift) as u32;
+        pos += bits as usize;
+    }
+}
+
+/// Loads 8 bytes starting at `byte` as a big-endian word, zero-padding past the end of `data`.
+#[inline]
+fn load_word(data: &[u8], byte: usize) -> u64 {
+    match data.get(byte..byte + 8) {
+        Some(b) => u64::from_be_bytes(b.try_into().unwrap()),
+        None => {
+            let mut buf = [0u8; 8];
+            let rest = data.get(byte..).unwrap_or_default();
+            buf[..rest.len()].copy_from_slice(rest);
+            u64::from_be_bytes(buf)
+        }
+    }
+}
+
+/// Sequential bit reader over an in-memory buffer, used where the bit width changes from one
+/// run of values to the next (e.g. the groups of complex packing).
+pub struct BitCursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitCursor<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    /// Current position in bits from the start of the buffer.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Unpacks `out.len()` values of `bits` bits each and advances past them.
+    pub fn read_into(&mut self, bits: u32, out: &mut [u32]) -> Result<()> {
+        unpack_bits(self.data, self.pos, bits, out)?;
+        self.pos += out.len() * bits as usize;
+        Ok(())
+    }
+
+    /// Skips to the next byte boundary.
+    pub fn byte_align(&mut self) {
+        self.pos = self.pos.next_multiple_of(8);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitstream_io::{BigEndian, BitRead, BitReader};
+
+    use super::*;
+    use crate::noise;
+
+    #[test]
+    fn matches_bitstream_io_for_every_width_and_offset() {
+        let count = 1001;
+        let data = noise(0x9e3779b97f4a7c15, packed_len(count, 32) + 1);
+        for bits in 0..=32 {
+            for bit_offset in 0..8 {
+                let mut reader = BitReader::<_, BigEndian>::new(&data[..]);
+                reader.skip(bit_offset as u32).unwrap();
+                let expected: Vec<u32> = (0..count)
+                    .map(|_| reader.read_var::<u32>(bits).unwrap_or(0))
+                    .collect();
+                let mut values = vec![0; count];
+                unpack_bits(&data, bit_offset, bits, &mut values).unwrap();
+                assert_eq!(values, expected, "{} bits at offset {}", bits, bit_offset);
+            }
+        }
+    }
+
+    #[test]
+    fn cursor_reads_runs_of_varying_widths() {
+        let data = noise(0x9e3779b97f4a7c15, 64);
+        let mut reader = BitReader::<_, BigEndian>::new(&data[..]);
+        let mut cursor = BitCursor::new(&data);
+        for (bits, count) in [(3, 5), (17, 2), (8, 4), (0, 3), (31, 3), (12, 5)] {
+            let expected: Vec<u32> = (0..count)
+                .map(|_| reader.read_var::<u32>(bits).unwrap_or(0))
+                .collect();
+            let mut values = vec![0; count];
+            cursor.read_into(bits, &mut values).unwrap();
+            assert_eq!(values, expected, "{} bits", bits);
+        }
+        assert_eq!(cursor.position(), 3 * 5 + 17 * 2 + 8 * 4 + 31 * 3 + 12 * 5);
+    }
+
+    #[test]
+    fn rejects_wide_values_and_short_input() {
+        let mut values = [0; 4];
+        assert!(matches!(
+            unpack_bits(&[0; 32], 0, 33, &mut values),
+            Err(Error::InvalidData(_))
+        ));
+        assert!(matches!(
+            unpack_bits(&[0; 4], 1, 8, &mut values),
+            Err(Error::InvalidData(_))
+        ));
+    }
+}
@@ -1,7 +1,7 @@
 use std::io::Read;
 
 use super::GribRead;
-use crate::Result;
+use crate::{Error, Result};
 
 #[derive(Debug)]
 pub struct DataRepresentationTemplate5_0 {
@@ -22,6 +22,23 @@ impl DataRepresentationTemplate5_0 {
             type_of_original_field_values: reader.read_grib_value()?,
         })
     }
+
+    /// Converts decoded integers into physical values, Y = (R + X * 2^E) / 10^D.
+    ///
+    /// i32::MIN (missing) becomes NAN. `out` must be as long as `raw`.
+    pub fn scale_into(&self, raw: &[i32], out: &mut [f32]) -> Result<()> {
+        check_lengths(raw, out)?;
+        let reference_value = self.reference_value as f64;
+        let binary_scale = 2f64.powi(self.binary_scale_factor as i32);
+        let decimal_scale = 10f64.powi(-(self.decimal_scale_factor as i32));
+        for (o, &x) in out.iter_mut().zip(raw) {
+            *o = match x {
+                i32::MIN => f32::NAN,
+                x => ((reference_value + x as f64 * binary_scale) * decimal_scale) as f32,
+            };
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -103,4 +120,74 @@ impl DataRepresentationTemplate5_200 {
         }
         Ok(tmpl)
     }
+
+    /// Converts decoded level values into physical values, Y = X / 10^D.
+    ///
+    /// i32::MIN (level 0, missing) becomes NAN. `out` must be as long as `raw`.
+    pub fn scale_into(&self, raw: &[i32], out: &mut [f32]) -> Result<()> {
+        check_lengths(raw, out)?;
+        let decimal_scale = 10f64.powi(-(self.decimal_scale_factor as i32));
+        for (o, &x) in out.iter_mut().zip(raw) {
+            *o = match x {
+                i32::MIN => f32::NAN,
+                x => (x as f64 * decimal_scale) as f32,
+            };
+        }
+        Ok(())
+    }
+}
+
+fn check_lengths(raw: &[i32], out: &[f32]) -> Result<()> {
+    if raw.len() != out.len() {
+        return Err(Error::InvalidData(format!(
+            "{} values can't be scaled into a buffer of {}",
+            raw.len(),
+            out.len()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simple_packing_scales_values() {
+        // Y = (R + X * 2^E) / 10^D
+        let tmpl = DataRepresentationTemplate5_0 {
+            reference_value: 2500.0,
+            binary_scale_factor: -1,
+            decimal_scale_factor: 1,
+            bits_per_value: 12,
+            type_of_original_field_values: 0,
+        };
+        let mut out = [0.0; 3];
+        tmpl.scale_into(&[0, 5, i32::MIN], &mut out).unwrap();
+        assert_eq!(out[..2], [250.0, 250.25]);
+        assert!(out[2].is_nan());
+        assert!(matches!(
+            tmpl.scale_into(&[0, 5], &mut out),
+            Err(Error::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn run_length_packing_scales_levels() {
+        let tmpl = DataRepresentationTemplate5_200 {
+            number_of_bits: 8,
+            mv: 3,
+            mvl: 2,
+            decimal_scale_factor: 2,
+            mvl_scaled_representative_values: vec![150, -25],
+        };
+        let mut out = [0.0; 3];
+        tmpl.scale_into(&[150, -25, i32::MIN], &mut out).unwrap();
+        assert_eq!(out[..2], [1.5, -0.25]);
+        assert!(out[2].is_nan());
+        assert!(matches!(
+            tmpl.scale_into(&[150, -25, 0, 0], &mut out),
+            Err(Error::InvalidData(_))
+        ));
+    }
 }
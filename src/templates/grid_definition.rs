@@ -1,10 +1,81 @@
 use std::io::Read;
 
+use byteorder::{BigEndian, ReadBytesExt};
+
 use super::GribRead;
-use crate::Result;
+use crate::{Error, Result};
+
+/// Grid definition template of section 3, selected by the template number
+#[derive(Debug, Clone)]
+pub enum GridDefinitionTemplate {
+    Template3_0(GridDefinitionTemplate3_0),
+    Template3_1(GridDefinitionTemplate3_1),
+    Template3_10(GridDefinitionTemplate3_10),
+    Template3_20(GridDefinitionTemplate3_20),
+    Template3_30(GridDefinitionTemplate3_30),
+    Template3_40(GridDefinitionTemplate3_40),
+    Template3_90(GridDefinitionTemplate3_90),
+    Template3_101(GridDefinitionTemplate3_101),
+    Template3_140(GridDefinitionTemplate3_140),
+    Template3_204(GridDefinitionTemplate3_204),
+}
+
+impl GridDefinitionTemplate {
+    /// Reads the template following the section 3 header.
+    pub fn read<R: Read>(template_number: u16, reader: &mut R) -> Result<Self> {
+        Ok(match template_number {
+            0 => Self::Template3_0(GridDefinitionTemplate3_0::read(reader)?),
+            1 => Self::Template3_1(GridDefinitionTemplate3_1::read(reader)?),
+            10 => Self::Template3_10(GridDefinitionTemplate3_10::read(reader)?),
+            20 => Self::Template3_20(GridDefinitionTemplate3_20::read(reader)?),
+            30 => Self::Template3_30(GridDefinitionTemplate3_30::read(reader)?),
+            40 => Self::Template3_40(GridDefinitionTemplate3_40::read(reader)?),
+            90 => Self::Template3_90(GridDefinitionTemplate3_90::read(reader)?),
+            101 => Self::Template3_101(GridDefinitionTemplate3_101::read(reader)?),
+            140 => Self::Template3_140(GridDefinitionTemplate3_140::read(reader)?),
+            204 => Self::Template3_204(GridDefinitionTemplate3_204::read(reader)?),
+            n => {
+                return Err(Error::UnsupportedData(format!(
+                    "grid definition template 3.{} is not supported",
+                    n
+                )));
+            }
+        })
+    }
+
+    /// Number of the template (Table 3.1)
+    pub fn template_number(&self) -> u16 {
+        match self {
+            Self::Template3_0(_) => 0,
+            Self::Template3_1(_) => 1,
+            Self::Template3_10(_) => 10,
+            Self::Template3_20(_) => 20,
+            Self::Template3_30(_) => 30,
+            Self::Template3_40(_) => 40,
+            Self::Template3_90(_) => 90,
+            Self::Template3_101(_) => 101,
+            Self::Template3_140(_) => 140,
+            Self::Template3_204(_) => 204,
+        }
+    }
+
+    /// Number of octets of the template in section 3, from octet 15
+    pub fn body_len(&self) -> u32 {
+        match self {
+            Self::Template3_0(_) | Self::Template3_10(_) | Self::Template3_40(_) => 58,
+            Self::Template3_1(_) => 70,
+            Self::Template3_20(_) => 51,
+            Self::Template3_30(_) => 67,
+            Self::Template3_90(_) => 66,
+            Self::Template3_101(_) => 21,
+            Self::Template3_140(_) => 50,
+            Self::Template3_204(_) => 58,
+        }
+    }
+}
 
 /// Template 3.0 (Latitude/longitude)
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct GridDefinitionTemplate3_0 {
     pub shape_of_earth: u8,
     pub scale_factor_of_radius: u8,
@@ -53,3 +124,476 @@ impl GridDefinitionTemplate3_0 {
         Ok(tmpl)
     }
 }
+
+/// Template 3.1 (Rotated latitude/longitude)
+#[derive(Debug, Clone)]
+pub struct GridDefinitionTemplate3_1 {
+    pub template_0: GridDefinitionTemplate3_0,
+    pub latitude_of_southern_pole: i32,
+    pub longitude_of_southern_pole: u32,
+    pub angle_of_rotation: f32,
+}
+
+impl GridDefinitionTemplate3_1 {
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        Ok(Self {
+            template_0: GridDefinitionTemplate3_0::read(reader)?,
+            latitude_of_southern_pole: reader.read_grib_value()?,
+            longitude_of_southern_pole: reader.read_grib_value()?,
+            angle_of_rotation: reader.read_grib_value()?,
+        })
+    }
+}
+
+/// Template 3.10 (Mercator)
+#[derive(Debug, Clone)]
+pub struct GridDefinitionTemplate3_10 {
+    pub shape_of_earth: u8,
+    pub scale_factor_of_radius: u8,
+    pub scale_value_of_radius: u32,
+    pub scale_factor_of_major_axis: u8,
+    pub scale_value_of_major_axis: u32,
+    pub scale_factor_of_minor_axis: u8,
+    pub scale_value_of_minor_axis: u32,
+    pub n_i: u32,
+    pub n_j: u32,
+    pub la1: i32,
+    pub lo1: i32,
+    pub resolution_and_component_flags: u8,
+    pub lad: i32,
+    pub la2: i32,
+    pub lo2: i32,
+    pub scanning_mode: u8,
+    pub orientation_of_the_grid: u32,
+    pub d_i: u32,
+    pub d_j: u32,
+}
+
+impl GridDefinitionTemplate3_10 {
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        Ok(Self {
+            shape_of_earth: reader.read_grib_value()?,
+            scale_factor_of_radius: reader.read_grib_value()?,
+            scale_value_of_radius: reader.read_grib_value()?,
+            scale_factor_of_major_axis: reader.read_grib_value()?,
+            scale_value_of_major_axis: reader.read_grib_value()?,
+            scale_factor_of_minor_axis: reader.read_grib_value()?,
+            scale_value_of_minor_axis: reader.read_grib_value()?,
+            n_i: reader.read_grib_value()?,
+            n_j: reader.read_grib_value()?,
+            la1: reader.read_grib_value()?,
+            lo1: reader.read_grib_value()?,
+            resolution_and_component_flags: reader.read_grib_value()?,
+            lad: reader.read_grib_value()?,
+            la2: reader.read_grib_value()?,
+            lo2: reader.read_grib_value()?,
+            scanning_mode: reader.read_grib_value()?,
+            orientation_of_the_grid: reader.read_grib_value()?,
+            d_i: reader.read_grib_value()?,
+            d_j: reader.read_grib_value()?,
+        })
+    }
+}
+
+/// Template 3.20 (Polar stereographic projection)
+#[derive(Debug, Clone)]
+pub struct GridDefinitionTemplate3_20 {
+    pub shape_of_earth: u8,
+    pub scale_factor_of_radius: u8,
+    pub scale_value_of_radius: u32,
+    pub scale_factor_of_major_axis: u8,
+    pub scale_value_of_major_axis: u32,
+    pub scale_factor_of_minor_axis: u8,
+    pub scale_value_of_minor_axis: u32,
+    pub n_x: u32,
+    pub n_y: u32,
+    pub la1: i32,
+    pub lo1: i32,
+    pub resolution_and_component_flags: u8,
+    pub lad: i32,
+    pub lov: i32,
+    pub d_x: u32,
+    pub d_y: u32,
+    pub projection_centre_flag: u8,
+    pub scanning_mode: u8,
+}
+
+impl GridDefinitionTemplate3_20 {
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        Ok(Self {
+            shape_of_earth: reader.read_grib_value()?,
+            scale_factor_of_radius: reader.read_grib_value()?,
+            scale_value_of_radius: reader.read_grib_value()?,
+            scale_factor_of_major_axis: reader.read_grib_value()?,
+            scale_value_of_major_axis: reader.read_grib_value()?,
+            scale_factor_of_minor_axis: reader.read_grib_value()?,
+            scale_value_of_minor_axis: reader.read_grib_value()?,
+            n_x: reader.read_grib_value()?,
+            n_y: reader.read_grib_value()?,
+            la1: reader.read_grib_value()?,
+            lo1: reader.read_grib_value()?,
+            resolution_and_component_flags: reader.read_grib_value()?,
+            lad: reader.read_grib_value()?,
+            lov: reader.read_grib_value()?,
+            d_x: reader.read_grib_value()?,
+            d_y: reader.read_grib_value()?,
+            projection_centre_flag: reader.read_grib_value()?,
+            scanning_mode: reader.read_grib_value()?,
+        })
+    }
+}
+
+/// Template 3.30 (Lambert conformal)
+#[derive(Debug, Clone)]
+pub struct GridDefinitionTemplate3_30 {
+    pub shape_of_earth: u8,
+    pub scale_factor_of_radius: u8,
+    pub scale_value_of_radius: u32,
+    pub scale_factor_of_major_axis: u8,
+    pub scale_value_of_major_axis: u32,
+    pub scale_factor_of_minor_axis: u8,
+    pub scale_value_of_minor_axis: u32,
+    pub n_x: u32,
+    pub n_y: u32,
+    pub la1: i32,
+    pub lo1: i32,
+    pub resolution_and_component_flags: u8,
+    pub lad: i32,
+    pub lov: i32,
+    pub d_x: u32,
+    pub d_y: u32,
+    pub projection_centre_flag: u8,
+    pub scanning_mode: u8,
+    pub latin1: i32,
+    pub latin2: i32,
+    pub latitude_of_southern_pole: i32,
+    pub longitude_of_southern_pole: i32,
+}
+
+impl GridDefinitionTemplate3_30 {
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        Ok(Self {
+            shape_of_earth: reader.read_grib_value()?,
+            scale_factor_of_radius: reader.read_grib_value()?,
+            scale_value_of_radius: reader.read_grib_value()?,
+            scale_factor_of_major_axis: reader.read_grib_value()?,
+            scale_value_of_major_axis: reader.read_grib_value()?,
+            scale_factor_of_minor_axis: reader.read_grib_value()?,
+            scale_value_of_minor_axis: reader.read_grib_value()?,
+            n_x: reader.read_grib_value()?,
+            n_y: reader.read_grib_value()?,
+            la1: reader.read_grib_value()?,
+            lo1: reader.read_grib_value()?,
+            resolution_and_component_flags: reader.read_grib_value()?,
+            lad: reader.read_grib_value()?,
+            lov: reader.read_grib_value()?,
+            d_x: reader.read_grib_value()?,
+            d_y: reader.read_grib_value()?,
+            projection_centre_flag: reader.read_grib_value()?,
+            scanning_mode: reader.read_grib_value()?,
+            latin1: reader.read_grib_value()?,
+            latin2: reader.read_grib_value()?,
+            latitude_of_southern_pole: reader.read_grib_value()?,
+            longitude_of_southern_pole: reader.read_grib_value()?,
+        })
+    }
+}
+
+/// Largest number of parallels between a pole and the equator of Gaussian grids
+///
+/// Finding the latitudes takes time quadratic in the number of parallels; the finest grids in
+/// use have a few thousand.
+pub const MAX_GAUSSIAN_N: u32 = 8000;
+
+/// Template 3.40 (Gaussian latitude/longitude)
+#[derive(Debug, Clone)]
+pub struct GridDefinitionTemplate3_40 {
+    pub shape_of_earth: u8,
+    pub scale_factor_of_radius: u8,
+    pub scale_value_of_radius: u32,
+    pub scale_factor_of_major_axis: u8,
+    pub scale_value_of_major_axis: u32,
+    pub scale_factor_of_minor_axis: u8,
+    pub scale_value_of_minor_axis: u32,
+    pub n_i: u32,
+    pub n_j: u32,
+    pub basic_angle: u32,
+    pub subdivisions_of_basic_angle: u32,
+    pub la1: i32,
+    pub lo1: i32,
+    pub resolution_and_component_flags: u8,
+    pub la2: i32,
+    pub lo2: i32,
+    pub d_i: u32,
+    /// Number of parallels between a pole and the equator
+    pub n: u32,
+    pub scanning_mode: u8,
+}
+
+impl GridDefinitionTemplate3_40 {
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        Ok(Self {
+            shape_of_earth: reader.read_grib_value()?,
+            scale_factor_of_radius: reader.read_grib_value()?,
+            scale_value_of_radius: reader.read_grib_value()?,
+            scale_factor_of_major_axis: reader.read_grib_value()?,
+            scale_value_of_major_axis: reader.read_grib_value()?,
+            scale_factor_of_minor_axis: reader.read_grib_value()?,
+            scale_value_of_minor_axis: reader.read_grib_value()?,
+            n_i: reader.read_grib_value()?,
+            n_j: reader.read_grib_value()?,
+            basic_angle: reader.read_grib_value()?,
+            subdivisions_of_basic_angle: reader.read_grib_value()?,
+            la1: reader.read_grib_value()?,
+            lo1: reader.read_grib_value()?,
+            resolution_and_component_flags: reader.read_grib_value()?,
+            la2: reader.read_grib_value()?,
+            lo2: reader.read_grib_value()?,
+            d_i: reader.read_grib_value()?,
+            n: match reader.read_grib_value()? {
+                0 => {
+                    return Err(Error::InvalidData(
+                        "number of parallels between a pole and the equator must not be 0"
+                            .to_string(),
+                    ));
+                }
+                n if n > MAX_GAUSSIAN_N => {
+                    return Err(Error::UnsupportedData(format!(
+                        "Gaussian grids with more than {} parallels between a pole and the \
+                         equator are not supported: {}",
+                        MAX_GAUSSIAN_N, n
+                    )));
+                }
+                n => n,
+            },
+            scanning_mode: reader.read_grib_value()?,
+        })
+    }
+
+    /// Whether rows have different numbers of points (reduced Gaussian grid), in which case
+    /// `n_i` is missing and the numbers of points per row follow the template.
+    pub fn is_reduced(&self) -> bool {
+        self.n_i == u32::MAX
+    }
+}
+
+/// Template 3.90 (Space view perspective or orthographic)
+#[derive(Debug, Clone)]
+pub struct GridDefinitionTemplate3_90 {
+    pub shape_of_earth: u8,
+    pub scale_factor_of_radius: u8,
+    pub scale_value_of_radius: u32,
+    pub scale_factor_of_major_axis: u8,
+    pub scale_value_of_major_axis: u32,
+    pub scale_factor_of_minor_axis: u8,
+    pub scale_value_of_minor_axis: u32,
+    pub n_x: u32,
+    pub n_y: u32,
+    /// Latitude of the sub-satellite point
+    pub lap: i32,
+    /// Longitude of the sub-satellite point
+    pub lop: i32,
+    pub resolution_and_component_flags: u8,
+    /// Apparent diameter of the earth in grid lengths, in the x direction
+    pub d_x: u32,
+    /// Apparent diameter of the earth in grid lengths, in the y direction
+    pub d_y: u32,
+    /// X coordinate of the sub-satellite point, in units of 10^-3 grid length
+    pub x_p: u32,
+    /// Y coordinate of the sub-satellite point, in units of 10^-3 grid length
+    pub y_p: u32,
+    pub scanning_mode: u8,
+    pub orientation_of_the_grid: u32,
+    /// Altitude of the camera from the centre of the earth, in units of the equatorial radius
+    /// multiplied by 10^6
+    pub n_r: u32,
+    /// X coordinate of the origin of the sector image
+    pub x_o: u32,
+    /// Y coordinate of the origin of the sector image
+    pub y_o: u32,
+}
+
+impl GridDefinitionTemplate3_90 {
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        Ok(Self {
+            shape_of_earth: reader.read_grib_value()?,
+            scale_factor_of_radius: reader.read_grib_value()?,
+            scale_value_of_radius: reader.read_grib_value()?,
+            scale_factor_of_major_axis: reader.read_grib_value()?,
+            scale_value_of_major_axis: reader.read_grib_value()?,
+            scale_factor_of_minor_axis: reader.read_grib_value()?,
+            scale_value_of_minor_axis: reader.read_grib_value()?,
+            n_x: reader.read_grib_value()?,
+            n_y: reader.read_grib_value()?,
+            lap: reader.read_grib_value()?,
+            lop: reader.read_grib_value()?,
+            resolution_and_component_flags: reader.read_grib_value()?,
+            d_x: reader.read_grib_value()?,
+            d_y: reader.read_grib_value()?,
+            x_p: reader.read_grib_value()?,
+            y_p: reader.read_grib_value()?,
+            scanning_mode: reader.read_grib_value()?,
+            orientation_of_the_grid: reader.read_grib_value()?,
+            n_r: reader.read_grib_value()?,
+            x_o: reader.read_grib_value()?,
+            y_o: reader.read_grib_value()?,
+        })
+    }
+}
+
+/// Template 3.101 (General unstructured grid)
+///
+/// The template only identifies the grid; the coordinates of its points come from an external
+/// grid file.
+#[derive(Debug, Clone)]
+pub struct GridDefinitionTemplate3_101 {
+    pub shape_of_earth: u8,
+    pub number_of_grid_used: u32,
+    pub number_of_grid_in_reference: u8,
+    /// UUID of the horizontal grid
+    pub uuid_of_h_grid: [u8; 16],
+}
+
+impl GridDefinitionTemplate3_101 {
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let shape_of_earth = reader.read_grib_value()?;
+        let number_of_grid_used = reader.read_u24::<BigEndian>()?;
+        let number_of_grid_in_reference = reader.read_grib_value()?;
+        let mut uuid_of_h_grid = [0u8; 16];
+        reader.read_exact(&mut uuid_of_h_grid)?;
+        Ok(Self {
+            shape_of_earth,
+            number_of_grid_used,
+            number_of_grid_in_reference,
+            uuid_of_h_grid,
+        })
+    }
+}
+
+/// Template 3.140 (Lambert azimuthal equal area projection)
+#[derive(Debug, Clone)]
+pub struct GridDefinitionTemplate3_140 {
+    pub shape_of_earth: u8,
+    pub scale_factor_of_radius: u8,
+    pub scale_value_of_radius: u32,
+    pub scale_factor_of_major_axis: u8,
+    pub scale_value_of_major_axis: u32,
+    pub scale_factor_of_minor_axis: u8,
+    pub scale_value_of_minor_axis: u32,
+    pub n_x: u32,
+    pub n_y: u32,
+    pub la1: i32,
+    pub lo1: i32,
+    pub standard_parallel: i32,
+    pub central_longitude: i32,
+    pub resolution_and_component_flags: u8,
+    pub d_x: u32,
+    pub d_y: u32,
+    pub scanning_mode: u8,
+}
+
+impl GridDefinitionTemplate3_140 {
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        Ok(Self {
+            shape_of_earth: reader.read_grib_value()?,
+            scale_factor_of_radius: reader.read_grib_value()?,
+            scale_value_of_radius: reader.read_grib_value()?,
+            scale_factor_of_major_axis: reader.read_grib_value()?,
+            scale_value_of_major_axis: reader.read_grib_value()?,
+            scale_factor_of_minor_axis: reader.read_grib_value()?,
+            scale_value_of_minor_axis: reader.read_grib_value()?,
+            n_x: reader.read_grib_value()?,
+            n_y: reader.read_grib_value()?,
+            la1: reader.read_grib_value()?,
+            lo1: reader.read_grib_value()?,
+            standard_parallel: reader.read_grib_value()?,
+            central_longitude: reader.read_grib_value()?,
+            resolution_and_component_flags: reader.read_grib_value()?,
+            d_x: reader.read_grib_value()?,
+            d_y: reader.read_grib_value()?,
+            scanning_mode: reader.read_grib_value()?,
+        })
+    }
+}
+
+/// Template 3.204 (Curvilinear orthogonal grids)
+///
+/// The latitudes and longitudes of the points are sent as separate fields.
+#[derive(Debug, Clone)]
+pub struct GridDefinitionTemplate3_204 {
+    pub shape_of_earth: u8,
+    pub scale_factor_of_radius: u8,
+    pub scale_value_of_radius: u32,
+    pub scale_factor_of_major_axis: u8,
+    pub scale_value_of_major_axis: u32,
+    pub scale_factor_of_minor_axis: u8,
+    pub scale_value_of_minor_axis: u32,
+    pub n_i: u32,
+    pub n_j: u32,
+    pub resolution_and_component_flags: u8,
+    pub scanning_mode: u8,
+}
+
+impl GridDefinitionTemplate3_204 {
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut reserved = [0u8; 16];
+        Ok(Self {
+            shape_of_earth: reader.read_grib_value()?,
+            scale_factor_of_radius: reader.read_grib_value()?,
+            scale_value_of_radius: reader.read_grib_value()?,
+            scale_factor_of_major_axis: reader.read_grib_value()?,
+            scale_value_of_major_axis: reader.read_grib_value()?,
+            scale_factor_of_minor_axis: reader.read_grib_value()?,
+            scale_value_of_minor_axis: reader.read_grib_value()?,
+            n_i: reader.read_grib_value()?,
+            n_j: reader.read_grib_value()?,
+            resolution_and_component_flags: {
+                reader.read_exact(&mut reserved)?;
+                reader.read_grib_value()?
+            },
+            scanning_mode: {
+                reader.read_exact(&mut reserved)?;
+                reader.read_grib_value()?
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_read_their_length() {
+        for number in [0, 1, 10, 20, 30, 40, 90, 101, 140, 204] {
+            let mut body = [0xff; 100];
+            if number == 40 {
+                body[53..57].copy_from_slice(&320u32.to_be_bytes());
+            }
+            let mut reader = &body[..];
+            let template = GridDefinitionTemplate::read(number, &mut reader).unwrap();
+            assert_eq!(template.template_number(), number);
+            assert_eq!(
+                template.body_len() as usize,
+                body.len() - reader.len(),
+                "template 3.{}",
+                number
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_too_many_gaussian_parallels() {
+        for n in [MAX_GAUSSIAN_N + 1, u32::MAX] {
+            let mut body = [0; 58];
+            body[53..57].copy_from_slice(&n.to_be_bytes());
+            assert!(matches!(
+                GridDefinitionTemplate3_40::read(&mut &body[..]),
+                Err(Error::UnsupportedData(_))
+            ));
+        }
+        let mut body = [0; 58];
+        body[53..57].copy_from_slice(&MAX_GAUSSIAN_N.to_be_bytes());
+        assert!(GridDefinitionTemplate3_40::read(&mut &body[..]).is_ok());
+    }
+}
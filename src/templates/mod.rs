@@ -1,3 +1,4 @@
+pub mod bitmap;
 pub mod data;
 pub mod data_representation;
 pub mod grid_definition;
@@ -7,6 +8,7 @@ use byteorder::{BigEndian, ReadBytesExt};
 use std::io::Read;
 use std::io::Result;
 
+pub use bitmap::*;
 pub use data::*;
 pub use data_representation::*;
 pub use grid_definition::*;